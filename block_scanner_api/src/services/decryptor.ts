@@ -39,6 +39,13 @@ export class ZcashDecryptor {
             });
 
             process.on('close', (code) => {
+                // Exit code 3: the transaction parsed but nothing decrypted
+                if (code === 3) {
+                    logger.debug(`No outputs found for tx ${txid}`);
+                    resolve(null);
+                    return;
+                }
+
                 if (code !== 0) {
                     logger.warn(`Decryptor exited with code ${code} for tx ${txid}: ${stderr}`);
                     // Return null for transactions that don't match the UFVK
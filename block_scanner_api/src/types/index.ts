@@ -17,19 +17,66 @@ export interface TransactionDetails {
     transaction_id: string;
     transaction_hash: string;
     amount_zats: number;
-    amount_zec: number;
+    amount_zec: string;
     incoming_zats: number;
-    incoming_zec: number;
+    incoming_zec: string;
     change_zats: number;
-    change_zec: number;
+    change_zec: string;
     outgoing_zats: number;
-    outgoing_zec: number;
+    outgoing_zec: string;
     fee_zats: number;
-    fee_zec: number;
+    fee_zec: string;
     timestamp: string;
     block_height: number;
+    network: string | null;
+    block_time: string | null;
+    block_hash: string | null;
+    confirmations: number | null;
+    is_final: boolean | null;
     outputs: OutputInfo[];
     tx_size_bytes: number;
+    tx_version: number;
+    opaque_components: OpaqueComponent[];
+    bundles: BundleStats;
+    fiat: FiatValue | null;
+    status: 'decrypted' | 'no_matches' | 'parse_error' | 'network_error' | 'error';
+    errors: Diagnostic[];
+    warnings: Diagnostic[];
+}
+
+export interface OpaqueComponent {
+    protocol: string;
+    kind: string;
+    index: number;
+    net_value_zats: number;
+}
+
+export interface FiatValue {
+    currency: string;
+    price: number;
+    price_source: string;
+    price_time: string;
+    amount: number;
+    incoming: number;
+    change: number;
+    outgoing: number;
+}
+
+export interface BundleStats {
+    transparent_inputs: number;
+    transparent_outputs: number;
+    sapling_spends: number;
+    sapling_outputs: number;
+    sapling_value_balance_zats: number;
+    sapling_anchors: string[];
+    orchard_actions: number;
+    orchard_value_balance_zats: number;
+    orchard_anchor: string | null;
+}
+
+export interface Diagnostic {
+    code: string;
+    message: string;
 }
 
 export interface OutputInfo {
@@ -39,6 +86,8 @@ export interface OutputInfo {
     transfer_type: string;
     direction: string;
     memo: string;
+    diversifier_index?: number | null;
+    idempotency_key?: string;
 }
 
 // GetBlock.io API types
@@ -0,0 +1,889 @@
+//! Report and event types produced by `zcash-tx-decryptor`.
+//!
+//! These are the JSON shapes the decryptor prints, serves over HTTP and
+//! stores, published separately so consumers can deserialize them without
+//! depending on librustzcash.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+mod zec;
+
+pub use zec::Zec;
+
+/// Stable identifier of a decrypted output: `<txid>:<pool>:<index>:<account>`,
+/// e.g. `"c7b9...:orchard:1:treasury"`, where `account_id` names the account
+/// by something that outlives its position among the keys (its label, or a
+/// fingerprint of its viewing key). The same output gets the same key whenever
+/// it is seen, from the mempool or a block, on a rescan or after a restart, so
+/// downstream systems can upsert on it instead of crediting it twice.
+pub fn idempotency_key(txid: &str, protocol: &str, index: usize, account_id: &str) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        txid,
+        protocol.to_ascii_lowercase(),
+        index,
+        account_id
+    )
+}
+
+/// Complete transaction details after decryption
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionDetails {
+    /// Full transaction ID (TXID)
+    pub transaction_id: String,
+
+    /// Shortened transaction hash
+    pub transaction_hash: String,
+
+    /// Total amount received in zatoshis (Incoming + WalletInternal)
+    /// (1 ZEC = 100,000,000 zats)
+    pub amount_zats: i64,
+
+    /// Total amount received in ZEC (Incoming + WalletInternal), as an exact
+    /// decimal string, e.g. "0.30000000"
+    pub amount_zec: Zec,
+
+    /// Total strictly incoming amount (external receives only), in zatoshis
+    pub incoming_zats: i64,
+
+    /// Total strictly incoming amount, in ZEC
+    pub incoming_zec: Zec,
+
+    /// Total internal change (WalletInternal), in zatoshis
+    pub change_zats: i64,
+
+    /// Total internal change (WalletInternal), in ZEC
+    pub change_zec: Zec,
+
+    /// Total value of outputs that were decrypted via OVK (Outgoing), in zatoshis
+    pub outgoing_zats: i64,
+
+    /// Total value of outputs that were decrypted via OVK (Outgoing), in ZEC
+    pub outgoing_zec: Zec,
+
+    /// Transaction fee in zatoshis (if known; 0 in view-only mode)
+    pub fee_zats: i64,
+
+    /// Transaction fee in ZEC (if known; 0 in view-only mode)
+    pub fee_zec: Zec,
+
+    /// Block time when known (`decrypt-block`, or from the node with
+    /// --rpc-url), otherwise when the transaction was processed by this tool
+    pub timestamp: DateTime<Utc>,
+
+    /// Block height where transaction was confirmed (best-effort hint)
+    pub block_height: u32,
+
+    /// Network the transaction was decrypted on: "main", "test" or
+    /// "regtest"; absent from failure reports made before it was known
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// Time of the block the transaction was mined in, when read from the
+    /// block itself (`decrypt-block`)
+    #[serde(default)]
+    pub block_time: Option<DateTime<Utc>>,
+
+    /// Hash of the block the transaction was mined in, when known
+    #[serde(default)]
+    pub block_hash: Option<String>,
+
+    /// Confirmations reported by the node (0 while in the mempool); only with
+    /// --rpc-url
+    #[serde(default)]
+    pub confirmations: Option<u32>,
+
+    /// Whether the block is deeper than the node will reorganize, so the
+    /// transaction can no longer be rolled back; only with --rpc-url
+    #[serde(default)]
+    pub is_final: Option<bool>,
+
+    /// All decrypted outputs in this transaction
+    pub outputs: Vec<OutputInfo>,
+
+    /// Estimated transaction size in bytes
+    pub tx_size_bytes: usize,
+
+    /// Transaction format version (1-5); 0 when the transaction did not parse
+    #[serde(default)]
+    pub tx_version: u32,
+
+    /// Shielded components this tool cannot decrypt, such as Sprout JoinSplits
+    /// in pre-NU5 transactions
+    #[serde(default)]
+    pub opaque_components: Vec<OpaqueComponent>,
+
+    /// Structure of the transaction's bundles, whether or not anything
+    /// decrypted
+    #[serde(default)]
+    pub bundles: BundleStats,
+
+    /// Fiat equivalents of the amounts, when requested with `--fiat`
+    #[serde(default)]
+    pub fiat: Option<FiatValue>,
+
+    /// Outputs left out by `--min-value-zats` or `--min-confirmations`, with
+    /// `--summarize-filtered` and only if any were
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filtered: Option<FilteredOutputs>,
+
+    /// What the viewing keys can decrypt, so an empty `outputs` can be told
+    /// apart from a pool the keys can't see; absent from failure reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_capabilities: Option<KeyCapabilities>,
+
+    /// Overall outcome; check this before reading `outputs`
+    pub status: DecryptStatus,
+
+    /// Why the transaction could not be processed; empty unless `status` is a
+    /// failure
+    #[serde(default)]
+    pub errors: Vec<Diagnostic>,
+
+    /// Problems that did not stop decryption, e.g. an ignored TXID mismatch
+    #[serde(default)]
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// A shielded component that is reported but not decrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpaqueComponent {
+    /// Protocol: "Sprout"
+    pub protocol: String,
+
+    /// Component kind: "JoinSplit"
+    pub kind: String,
+
+    /// Index of the component within the transaction
+    pub index: usize,
+
+    /// Value moved into the transparent pool (negative: out of it), in zatoshis
+    pub net_value_zats: i64,
+}
+
+/// Fiat equivalents of a transaction's amounts, rounded to cents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiatValue {
+    /// Currency code as requested, lower case (e.g. "usd")
+    pub currency: String,
+
+    /// Price of 1 ZEC in `currency`
+    pub price: f64,
+
+    /// Where the price came from: "coingecko" or "fixed"
+    pub price_source: String,
+
+    /// Time the price applies to: the block time when known, otherwise when
+    /// the transaction was decrypted
+    pub price_time: DateTime<Utc>,
+
+    /// Total received (incoming + change), as `amount_zats`
+    pub amount: f64,
+    pub incoming: f64,
+    pub change: f64,
+    pub outgoing: f64,
+}
+
+/// Component counts, value balances and anchors of a transaction's bundles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundleStats {
+    pub transparent_inputs: usize,
+    pub transparent_outputs: usize,
+    pub sapling_spends: usize,
+    pub sapling_outputs: usize,
+
+    /// Net value leaving the Sapling pool (valueBalanceSapling), in zatoshis
+    pub sapling_value_balance_zats: i64,
+
+    /// Distinct Sapling anchors referenced by the spends (hex, as serialized);
+    /// v4 carries one per spend, v5 one shared anchor
+    pub sapling_anchors: Vec<String>,
+
+    pub orchard_actions: usize,
+
+    /// Net value leaving the Orchard pool (valueBalanceOrchard), in zatoshis
+    pub orchard_value_balance_zats: i64,
+
+    /// Orchard anchor (hex, as serialized); absent without an Orchard bundle
+    pub orchard_anchor: Option<String>,
+}
+
+/// Outcome of processing one transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecryptStatus {
+    /// At least one output decrypted
+    Decrypted,
+    /// The transaction parsed, but no output belongs to the viewing key(s)
+    NoMatches,
+    /// The raw transaction is not valid hex or does not parse
+    ParseError,
+    /// A node or other remote endpoint could not be reached
+    NetworkError,
+    /// Any other failure; see `errors`
+    Error,
+}
+
+/// An error or warning attached to a [`TransactionDetails`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Stable machine-readable identifier, e.g. "parse_error" or "txid_mismatch"
+    pub code: String,
+
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Information about a single decrypted output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputInfo {
+    /// Protocol: "Sapling", "Orchard", or "Transparent" for an output to a
+    /// watched transparent address
+    pub protocol: String,
+
+    /// Amount in zatoshis
+    pub amount_zats: i64,
+
+    /// Index of output within the bundle
+    pub index: usize,
+
+    /// Account (position of the viewing key on the command line) that decrypted the output
+    pub account: u32,
+
+    /// The output's [`idempotency_key`]; empty in reports from before it was
+    /// added
+    #[serde(default)]
+    pub idempotency_key: String,
+
+    /// Label given with `--ufvk label=<name>:<ufvk>`, if any
+    #[serde(default)]
+    pub account_label: Option<String>,
+
+    /// Known counterparty from the `--tags` address book, matched on the
+    /// address paid (ours, or the recipient's for outgoing outputs) or the memo
+    #[serde(default)]
+    pub counterparty: Option<String>,
+
+    /// Order or invoice ID found in the memo by `--memo-regex` or
+    /// `--memo-prefix`
+    #[serde(default)]
+    pub payment_reference: Option<String>,
+
+    /// Raw transfer type: Incoming, WalletInternal, or Outgoing
+    pub transfer_type: String,
+
+    /// High-level direction label: "received", "change", or "sent"
+    pub direction: String,
+
+    /// "coinbase" for an output of a block's coinbase transaction: a mining
+    /// reward or funding stream rather than a payment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Funding stream a coinbase output pays, recognized by its value, e.g.
+    /// "zcash_community_grants"; unset for the miner's share
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_stream: Option<String>,
+
+    /// Memo text attached to output (if any)
+    pub memo: String,
+
+    /// Our address that received the output (Sapling address, or an
+    /// Orchard-only unified address); not set for outgoing outputs
+    pub address: Option<String>,
+
+    /// For outgoing outputs, the address we paid, recovered with the OVK in the
+    /// same encoding as `address`
+    #[serde(default)]
+    pub recipient_address: Option<String>,
+
+    /// ZIP-32 diversifier index of `address` under the account's key, i.e. the
+    /// N of "address #N" (for transparent outputs, the xpub child index);
+    /// unset for outgoing outputs and for indices beyond 64 bits
+    #[serde(default)]
+    pub diversifier_index: Option<u64>,
+
+    /// Note commitment (Sapling cmu / Orchard cmx, hex)
+    pub note_commitment: Option<String>,
+
+    /// Global position of the note in its pool's commitment tree; only known
+    /// when scanning blocks (`scan-compact`)
+    pub position: Option<u64>,
+
+    /// Nullifier the note will reveal when spent (hex), for notes we received.
+    /// Sapling nullifiers depend on `position`, so they are only set when it is.
+    pub nullifier: Option<String>,
+
+    /// Raw cryptographic fields of the output and its note, only with
+    /// `--detail full`; never set for transparent outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<OutputDetail>,
+}
+
+/// Low-level fields of a Sapling output or Orchard action and the note it
+/// carried, for debugging wallet incompatibilities. Byte strings are hex, in
+/// the order they are serialized in the transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputDetail {
+    /// What `index` counts: "sapling_output" or "orchard_action"
+    pub index_kind: String,
+
+    /// Position of the output or action in its bundle
+    pub index: usize,
+
+    /// Value commitment (Sapling cv / Orchard cv_net)
+    pub cv: String,
+
+    /// Sapling note commitment u-coordinate
+    pub cmu: Option<String>,
+
+    /// Orchard extracted note commitment
+    pub cmx: Option<String>,
+
+    /// Ephemeral public key (epk)
+    pub ephemeral_key: String,
+
+    /// Length of the note ciphertext, 580 bytes for valid outputs
+    pub enc_ciphertext_len: usize,
+
+    /// Length of the outgoing ciphertext, 80 bytes for valid outputs
+    pub out_ciphertext_len: usize,
+
+    /// Diversifier of the recipient address
+    pub diversifier: String,
+
+    /// Note randomness seed. For a Sapling note from before ZIP 212 this is
+    /// rcm itself
+    pub rseed: String,
+
+    /// Whether the note uses ZIP 212 plaintext version 2 (rseed rather than
+    /// rcm); always true for Orchard
+    pub zip212: bool,
+
+    /// Note commitment trapdoor; Sapling only, as Orchard's is derived from
+    /// `rseed` and `rho`
+    pub rcm: Option<String>,
+
+    /// Orchard rho (the nullifier spent by the same action)
+    pub rho: Option<String>,
+}
+
+/// Decrypted outputs of one transaction that were not reported
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilteredOutputs {
+    /// Outputs worth less than `--min-value-zats`
+    pub dust_outputs: u32,
+
+    /// Their total value in zatoshis
+    pub dust_zats: i64,
+
+    /// Outputs of a transaction with fewer than `--min-confirmations`
+    pub unconfirmed_outputs: u32,
+
+    /// Their total value in zatoshis
+    pub unconfirmed_zats: i64,
+}
+
+/// Pools the viewing keys behind a report can decrypt. Outputs in a pool
+/// that no key has a component for are never reported; a transaction with
+/// such outputs also gets a `pool_not_viewable` warning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyCapabilities {
+    /// Shielded pools at least one key has a component for: "Orchard" and/or
+    /// "Sapling"
+    pub viewable_pools: Vec<String>,
+
+    /// Each account's key
+    pub accounts: Vec<AccountKey>,
+}
+
+/// The components of one account's viewing key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountKey {
+    /// Position of the UFVK in the given list
+    pub account: u32,
+
+    /// Label given with the UFVK as `label=<name>:<ufvk>`
+    #[serde(default)]
+    pub account_label: Option<String>,
+
+    /// Protocols the key has a component for: "Orchard", "Sapling" and/or
+    /// "Transparent"
+    pub components: Vec<String>,
+}
+
+/// A decrypted output emitted as soon as it is seen, e.g. by `watch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentEvent {
+    /// Event kind: "mempool_output"
+    pub event: String,
+
+    /// Full transaction ID (TXID)
+    pub transaction_id: String,
+
+    /// When this tool first saw the transaction
+    pub detected_at: DateTime<Utc>,
+
+    /// Height the transaction is expected to be mined at (chain tip + 1)
+    pub expected_height: u32,
+
+    /// Network the output was seen on: "main", "test" or "regtest"
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// The decrypted output
+    #[serde(flatten)]
+    pub output: OutputInfo,
+}
+
+/// Emitted by `scan-compact` and `sync` when blocks recorded in the `--db`
+/// result store turn out to have been reorged away; the outputs listed were
+/// removed and should no longer be credited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    /// Event kind: "reorg"
+    pub event: String,
+
+    /// When the reorg was detected
+    pub detected_at: DateTime<Utc>,
+
+    /// Lowest height whose recorded block is no longer on the chain;
+    /// everything from here up is rescanned
+    pub fork_height: u32,
+
+    /// Network the reorg happened on: "main", "test" or "regtest"
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// Confirmed outputs that were stored from the orphaned blocks
+    pub rolled_back: Vec<StoredOutput>,
+}
+
+/// Written by `sync` and `scan-compact` to `--progress-fd` as a scan
+/// advances, at most once a second and when it finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    /// Event kind: "progress"
+    pub event: String,
+
+    /// Mode reporting: "sync" or "scan-compact"
+    pub mode: String,
+
+    /// When the progress was measured
+    pub at: DateTime<Utc>,
+
+    /// Network being scanned: "main", "test" or "regtest"
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// Highest block height handled so far
+    pub height: u32,
+
+    /// Height the scan runs to
+    pub target_height: u32,
+
+    /// Share of the scan done, 0 to 100
+    pub percent: f64,
+
+    /// Transactions found with outputs for the viewing keys so far
+    pub hits: u64,
+
+    /// Estimated seconds until the scan reaches `target_height`; absent
+    /// until something has been done to estimate from
+    #[serde(default)]
+    pub eta_secs: Option<u64>,
+}
+
+/// Emitted with `--summarize-filtered` for a transaction some of whose
+/// outputs were left out by `--min-value-zats` or `--min-confirmations`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredEvent {
+    /// Event kind: "filtered_outputs"
+    pub event: String,
+
+    /// Full transaction ID (TXID)
+    pub transaction_id: String,
+
+    /// When this tool saw the transaction
+    pub detected_at: DateTime<Utc>,
+
+    /// Height of the transaction's events, as in [`PaymentEvent`]
+    pub expected_height: u32,
+
+    /// Network the transaction was seen on, as in [`PaymentEvent`]
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// What was left out
+    pub filtered: FilteredOutputs,
+}
+
+/// A decrypted output as persisted in the `--db` result store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredOutput {
+    /// Full transaction ID (TXID)
+    pub transaction_id: String,
+
+    /// Block height (expected mining height for unconfirmed outputs)
+    pub block_height: u32,
+
+    /// False for outputs seen only in the mempool
+    pub confirmed: bool,
+
+    /// Network the output was recorded on; None for outputs stored before
+    /// the network was recorded
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// Account that decrypted the output
+    pub account: u32,
+
+    /// The output's [`idempotency_key`], unique in the store
+    #[serde(default)]
+    pub idempotency_key: String,
+
+    /// Label of the account when the output was stored, if any
+    pub account_label: Option<String>,
+
+    /// Counterparty from the `--tags` address book when the output was
+    /// stored, if any
+    #[serde(default)]
+    pub counterparty: Option<String>,
+
+    /// Payment reference found in the memo when the output was stored, if
+    /// any
+    #[serde(default)]
+    pub payment_reference: Option<String>,
+
+    /// "coinbase" for mining rewards and funding streams
+    #[serde(default)]
+    pub source: Option<String>,
+
+    /// Funding stream a coinbase output pays, if recognized
+    #[serde(default)]
+    pub funding_stream: Option<String>,
+
+    /// Protocol: "Sapling", "Orchard" or "Transparent"
+    pub protocol: String,
+
+    /// Index of output within the bundle
+    pub index: usize,
+
+    /// Raw transfer type: Incoming, WalletInternal, or Outgoing
+    pub transfer_type: String,
+
+    /// Amount in zatoshis
+    pub amount_zats: i64,
+
+    /// Memo text attached to output (if any)
+    pub memo: String,
+
+    /// Our receiving address, or the recipient's for Outgoing outputs, if known
+    pub address: Option<String>,
+
+    /// Diversifier index of our receiving address, if known
+    pub diversifier_index: Option<u64>,
+
+    /// When the output was first stored (RFC 3339)
+    pub recorded_at: String,
+
+    /// Time of the block the transaction was mined in (RFC 3339), once
+    /// filled in by `db enrich`
+    pub block_time: Option<String>,
+}
+
+/// Shielded balance of one account, as returned by `GET /balance/{account}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    /// Account the balance belongs to
+    pub account: u32,
+
+    /// Network of the result store the balance was read from, if known
+    #[serde(default)]
+    pub network: Option<String>,
+
+    /// Value of unspent received notes in mined transactions
+    pub confirmed_zats: i64,
+
+    /// Value of unspent received notes seen only in the mempool
+    pub pending_zats: i64,
+
+    /// Value of unspent coinbase notes with fewer than 100 confirmations,
+    /// which cannot be spent yet
+    pub immature_zats: i64,
+}
+
+/// Per-account totals over a range of stored or streamed outputs, as printed
+/// by `report`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowReport {
+    /// Lowest block height included, if bounded
+    pub from_height: Option<u32>,
+
+    /// Highest block height included, if bounded
+    pub to_height: Option<u32>,
+
+    /// First day included (UTC, YYYY-MM-DD), if bounded
+    pub from_date: Option<String>,
+
+    /// Last day included (UTC, YYYY-MM-DD), if bounded
+    pub to_date: Option<String>,
+
+    /// One entry per account with outputs in the range, by account ID
+    pub accounts: Vec<AccountFlow>,
+}
+
+/// What one account received, kept and sent over a [`FlowReport`] range.
+/// Only mined outputs count, except in `pending_zats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountFlow {
+    /// Account the totals belong to
+    pub account: u32,
+
+    /// Label of the account, if any output carried one
+    pub account_label: Option<String>,
+
+    /// Distinct mined transactions with outputs for the account
+    pub transactions: u64,
+
+    /// Mined outputs counted
+    pub outputs: u64,
+
+    /// Value received from others (Incoming)
+    pub received_zats: i64,
+
+    /// Value returned to the account as change (WalletInternal)
+    pub change_zats: i64,
+
+    /// Value paid to others, as recovered with the OVK (Outgoing)
+    pub sent_zats: i64,
+
+    /// Fees paid by fully shielded transactions that spent the account's
+    /// notes; unset when reporting from events, which carry no value balances
+    pub fee_zats: Option<i64>,
+
+    /// `received_zats - sent_zats - fee_zats` (fees counted as 0 while unknown)
+    pub net_zats: i64,
+
+    /// Value received in transactions seen only in the mempool
+    pub pending_zats: i64,
+}
+
+/// A received note covered by an `audit unspent` attestation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditedNote {
+    /// Transaction that created the note
+    pub transaction_id: String,
+
+    /// Height of the block containing that transaction
+    pub block_height: u32,
+
+    /// Account that received the note
+    pub account: u32,
+
+    /// Protocol: "Sapling" or "Orchard"
+    pub protocol: String,
+
+    /// Note value in zatoshis
+    pub amount_zats: u64,
+
+    /// Nullifier the note reveals when spent (hex)
+    pub nullifier: String,
+
+    /// Transaction that revealed the nullifier, if the note was spent
+    pub spent_in: Option<String>,
+}
+
+/// Result of `audit unspent` over a contiguous block range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnspentAttestation {
+    /// First block audited
+    pub from_height: u32,
+
+    /// Last block audited
+    pub to_height: u32,
+
+    /// Hash of the last block audited, pinning the chain the audit ran against
+    pub tip_block_hash: String,
+
+    /// BLAKE2b-256 fingerprint of each audited UFVK, in account order
+    pub key_fingerprints: Vec<String>,
+
+    /// Every note received in the range
+    pub notes: Vec<AuditedNote>,
+
+    /// Total value of `notes` in zatoshis
+    pub total_zats: u64,
+
+    /// True if no note in `notes` was spent within the range
+    pub all_unspent: bool,
+
+    /// When the audit ran
+    pub generated_at: DateTime<Utc>,
+}
+
+/// An attestation with an Ed25519 signature over its compact JSON encoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    pub attestation: UnspentAttestation,
+
+    /// Ed25519 public key (hex)
+    pub public_key: String,
+
+    /// Ed25519 signature over `serde_json::to_vec(&attestation)` (hex)
+    pub signature: String,
+}
+
+/// A spend in a transaction that reveals one of the given nullifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedSpend {
+    /// Spending transaction
+    pub transaction_id: String,
+
+    /// Protocol: "Sapling" or "Orchard"
+    pub protocol: String,
+
+    /// Index of the spend (Sapling) or action (Orchard) within the bundle
+    pub index: usize,
+
+    /// Nullifier revealed (hex)
+    pub nullifier: String,
+}
+
+/// A decoded address (`inspect-address`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressInfo {
+    /// The address as given
+    pub address: String,
+
+    /// Encoding: "unified", "sapling", "transparent" or "tex"
+    pub kind: String,
+
+    /// Network the address is encoded for: "main", "test" or "regtest"
+    pub network: String,
+
+    /// Receivers in the address, in encoding order
+    pub receivers: Vec<AddressReceiver>,
+
+    /// Whether the address derives from one of the given UFVKs: every
+    /// shielded receiver matched the same key, scope and diversifier index.
+    /// None when no key was given or the address has no shielded receiver.
+    pub derivable: Option<bool>,
+}
+
+/// One receiver of an [`AddressInfo`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressReceiver {
+    /// Receiver type: "Orchard", "Sapling", "P2PKH", "P2SH" or "Unknown"
+    pub protocol: String,
+
+    /// ZIP-316 typecode, for unknown receivers
+    #[serde(default)]
+    pub typecode: Option<u32>,
+
+    /// Raw receiver bytes (hex)
+    pub data: String,
+
+    /// Whether this receiver was checked against the given UFVKs; transparent
+    /// and unknown receivers are not
+    pub checked: bool,
+
+    /// Key this receiver derives from, if any
+    pub owner: Option<ReceiverOwner>,
+}
+
+/// A decoded viewing key (`check-key`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyInfo {
+    /// Encoding: "ufvk", "uivk" or "sapling_efvk"
+    pub kind: String,
+
+    /// Network the key is encoded for: "main", "test" or "regtest"
+    pub network: String,
+
+    /// Protocols the key has a component for: "Orchard", "Sapling" and/or
+    /// "Transparent"
+    pub components: Vec<String>,
+
+    /// Whether outputs sent from the wallet can be recovered too (full
+    /// viewing keys carry outgoing viewing keys; incoming ones don't)
+    pub views_outgoing: bool,
+
+    /// Address at the lowest valid diversifier index, with a receiver for
+    /// each component
+    pub default_address: Option<String>,
+
+    /// Diversifier index of `default_address`
+    pub default_address_index: Option<u64>,
+
+    /// The key re-encoded as a UFVK, for a Sapling EFVK
+    #[serde(default)]
+    pub ufvk: Option<String>,
+
+    /// Height the key was checked against, if one was given
+    #[serde(default)]
+    pub height: Option<u32>,
+
+    /// Consensus branch ID in effect at `height` (hex)
+    #[serde(default)]
+    pub consensus_branch_id: Option<String>,
+
+    /// Reasons the key may not decrypt what is expected, e.g. a component
+    /// whose protocol is not active yet at `height`
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// The key, scope and diversifier index an address receiver derives from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiverOwner {
+    /// Position of the UFVK in the given list
+    pub account: u32,
+
+    /// Label given with the UFVK as `label=<name>:<ufvk>`
+    #[serde(default)]
+    pub account_label: Option<String>,
+
+    /// "external" (receiving) or "internal" (change)
+    pub scope: String,
+
+    /// ZIP-32 diversifier index, when it fits in 64 bits
+    pub diversifier_index: Option<u64>,
+}
+
+/// One shielded output disclosed with enough of its note for anyone holding
+/// the transaction to verify value, recipient and memo (`disclose`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentDisclosure {
+    /// Format version, currently 1
+    pub version: u32,
+
+    /// Network: "main", "test" or "regtest"
+    pub network: String,
+
+    /// Transaction the output is in
+    pub transaction_id: String,
+
+    /// Height the transaction was mined at (selects ZIP-212 rules)
+    pub block_height: u32,
+
+    /// Protocol: "Sapling" or "Orchard"
+    pub protocol: String,
+
+    /// Index of the output (Sapling) or action (Orchard) within the bundle
+    pub index: usize,
+
+    /// Note value in zatoshis
+    pub value_zats: u64,
+
+    /// Recipient: a Sapling address, or a unified address holding only the
+    /// Orchard receiver
+    pub recipient: String,
+
+    /// Note randomness seed (hex); the commitment randomness and the
+    /// ephemeral secret key used to encrypt the note both derive from it
+    pub rseed: String,
+
+    /// Memo bytes (hex), without trailing zero padding
+    pub memo: String,
+}
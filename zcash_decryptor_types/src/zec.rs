@@ -0,0 +1,151 @@
+//! [`Zec`]: an amount in zatoshis that serializes as an exact ZEC decimal.
+
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+const ZATS_PER_ZEC: u64 = 100_000_000;
+
+/// An amount of ZEC, held in zatoshis so it is exact. Serializes as a decimal
+/// string with all eight places, e.g. `"0.30000000"`; deserializes from that
+/// or from a number, as older reports have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zec(pub i64);
+
+impl Zec {
+    pub fn from_zats(zats: i64) -> Self {
+        Zec(zats)
+    }
+
+    pub fn zats(self) -> i64 {
+        self.0
+    }
+
+    /// The amount as a float of ZEC, the shape reports had before amounts
+    /// became strings. Rounds, e.g. 0.1 + 0.2 ZEC prints as
+    /// 0.30000000000000004.
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64 / ZATS_PER_ZEC as f64
+    }
+}
+
+impl fmt::Display for Zec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let zats = self.0.unsigned_abs();
+        write!(
+            f,
+            "{}{}.{:08}",
+            sign,
+            zats / ZATS_PER_ZEC,
+            zats % ZATS_PER_ZEC
+        )
+    }
+}
+
+impl FromStr for Zec {
+    type Err = String;
+
+    /// Parse a decimal ZEC amount with at most eight places.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid ZEC amount '{}'", s);
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || fraction.len() > 8 || !all_digits(whole) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+        let whole: i64 = whole.parse().map_err(|_| invalid())?;
+        let fraction: i64 = format!("{:0<8}", fraction).parse().map_err(|_| invalid())?;
+        let zats = whole
+            .checked_mul(ZATS_PER_ZEC as i64)
+            .and_then(|zats| zats.checked_add(fraction))
+            .ok_or_else(invalid)?;
+        Ok(Zec(if negative { -zats } else { zats }))
+    }
+}
+
+impl Serialize for Zec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Zec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Number(f64),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Text(text) => text.parse().map_err(de::Error::custom),
+            Repr::Number(zec) => Ok(Zec((zec * ZATS_PER_ZEC as f64).round() as i64)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_pads_to_eight_places() {
+        assert_eq!(Zec(30_000_000).to_string(), "0.30000000");
+        assert_eq!(Zec(1).to_string(), "0.00000001");
+        assert_eq!(Zec(-150_000_000).to_string(), "-1.50000000");
+        assert_eq!(Zec(-1).to_string(), "-0.00000001");
+        assert_eq!(Zec(i64::MIN).to_string(), "-92233720368.54775808");
+    }
+
+    #[test]
+    fn from_str_round_trips() {
+        for zats in [0, 1, 30_000_000, -1, -150_000_000, i64::MAX, i64::MIN + 1] {
+            assert_eq!(Zec(zats).to_string().parse::<Zec>(), Ok(Zec(zats)));
+        }
+        assert_eq!("1".parse::<Zec>(), Ok(Zec(100_000_000)));
+        assert_eq!("0.5".parse::<Zec>(), Ok(Zec(50_000_000)));
+        assert_eq!("-0.12345678".parse::<Zec>(), Ok(Zec(-12_345_678)));
+    }
+
+    #[test]
+    fn from_str_rejects_malformed() {
+        for text in ["", "-", ".5", "0.123456789", "1e8", "+1", "1.0.0", "--1", "0x10"] {
+            assert!(text.parse::<Zec>().is_err(), "accepted '{}'", text);
+        }
+    }
+
+    #[test]
+    fn from_str_rejects_overflow() {
+        assert!("92233720368.54775808".parse::<Zec>().is_err());
+        assert!("92233720369".parse::<Zec>().is_err());
+        assert!("99999999999999999999".parse::<Zec>().is_err());
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        assert_eq!(serde_json::to_string(&Zec(-5)).unwrap(), r#""-0.00000005""#);
+    }
+
+    #[test]
+    fn deserializes_strings_and_legacy_numbers() {
+        let zec = |json: &str| serde_json::from_str::<Zec>(json);
+        assert_eq!(zec(r#""0.30000000""#).unwrap(), Zec(30_000_000));
+        assert_eq!(zec(r#""-2.00000001""#).unwrap(), Zec(-200_000_001));
+        assert_eq!(zec("0.30000000000000004").unwrap(), Zec(30_000_000));
+        assert_eq!(zec("-1.5").unwrap(), Zec(-150_000_000));
+        assert!(zec(r#""0.000000001""#).is_err());
+        assert!(zec(r#""92233720368.54775808""#).is_err());
+    }
+
+    #[test]
+    fn as_f64_is_the_legacy_shape() {
+        assert_eq!(Zec(30_000_000).as_f64(), 0.3);
+        assert_eq!(Zec(-150_000_000).as_f64(), -1.5);
+    }
+}
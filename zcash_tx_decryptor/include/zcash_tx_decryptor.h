@@ -0,0 +1,48 @@
+#ifndef ZCASH_TX_DECRYPTOR_H
+#define ZCASH_TX_DECRYPTOR_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * Decrypt a raw transaction with one UFVK, as if mined at `height` on
+ * `network` ("main", "test" or "regtest").
+ *
+ * Returns the `--format json` object as a NUL-terminated UTF-8 string, which
+ * the caller must release with `ztd_string_free`. Errors are reported in the
+ * same JSON shape (see its `status` and `errors` fields), so the result is
+ * never NULL.
+ *
+ * # Safety
+ *
+ * Each argument must be NULL or point to a NUL-terminated string that stays
+ * valid for the duration of the call.
+ */
+char *ztd_decrypt_tx(const char *raw_tx_hex,
+                     const char *ufvk,
+                     uint32_t height,
+                     const char *network);
+
+/*
+ * Release a string returned by `ztd_decrypt_tx`. Passing NULL is a no-op.
+ *
+ * # Safety
+ *
+ * `s` must be NULL or a pointer returned by `ztd_decrypt_tx` that has not
+ * already been freed.
+ */
+void ztd_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZCASH_TX_DECRYPTOR_H */
@@ -0,0 +1,174 @@
+use anyhow::{anyhow, Result};
+use zcash_client_backend::{address::Address, keys::UnifiedFullViewingKey};
+use zcash_primitives::{
+    consensus::{Network, Parameters},
+    legacy::TransparentAddress,
+    zip32::Scope,
+};
+
+use crate::{
+    decrypt::{index_to_u64, KeyGeneration},
+    models::{AddressInfo, AddressReceiver, ReceiverOwner},
+    network::{self, ChainParams},
+};
+
+/// Decode `address` with `network`'s encodings. On failure, says which other
+/// network the address is for, if any.
+pub fn decode(network: &ChainParams, address: &str) -> Result<Address> {
+    let address = address.trim();
+    if let Some(decoded) = Address::decode(network, address) {
+        return Ok(decoded);
+    }
+    let other = all_networks()
+        .into_iter()
+        .find(|other| Address::decode(other, address).is_some());
+    match other {
+        Some(other) => Err(anyhow!(
+            "Address is for {:?}, not {:?}",
+            other.network_type(),
+            network.network_type()
+        )),
+        None => Err(anyhow!("Not a valid Zcash address: {}", address)),
+    }
+}
+
+/// Network an address is encoded for, trying mainnet, testnet and regtest in
+/// turn. Transparent addresses are the same on testnet and regtest and are
+/// taken as testnet.
+pub fn detect_network(address: &str) -> Result<ChainParams> {
+    all_networks()
+        .into_iter()
+        .find(|network| Address::decode(network, address.trim()).is_some())
+        .ok_or_else(|| anyhow!("Not a valid Zcash address: {}", address.trim()))
+}
+
+/// List the receivers of `address` and check its shielded receivers against
+/// `ufvks`, owned by the accounts of the matching `generations` as in the
+/// `Decryptor`.
+///
+/// Transparent receivers are listed but not checked: this build derives no
+/// transparent keys, and matching one would mean searching address indices.
+pub fn inspect(
+    network: &ChainParams,
+    address: &str,
+    ufvks: &[UnifiedFullViewingKey],
+    generations: &[KeyGeneration],
+) -> Result<AddressInfo> {
+    let decoded = decode(network, address)?;
+    let owner_of = |key: usize, scope: Scope, index: Option<u64>| {
+        let account = generations.get(key).map_or(key as u32, |g| g.account);
+        ReceiverOwner {
+            account,
+            account_label: generations
+                .iter()
+                .filter(|g| g.account == account)
+                .find_map(|g| g.label.clone()),
+            scope: scope_name(scope).to_string(),
+            diversifier_index: index,
+        }
+    };
+
+    let sapling_receiver = |pa: &sapling::PaymentAddress| {
+        let owner = ufvks.iter().enumerate().find_map(|(key, ufvk)| {
+            let (index, scope) = ufvk.sapling()?.decrypt_diversifier(pa)?;
+            Some(owner_of(key, scope, index_to_u64(&index)))
+        });
+        receiver(
+            "Sapling",
+            None,
+            pa.to_bytes().to_vec(),
+            !ufvks.is_empty(),
+            owner,
+        )
+    };
+    let orchard_receiver = |addr: &orchard::Address| {
+        let owner = ufvks.iter().enumerate().find_map(|(key, ufvk)| {
+            let fvk = ufvk.orchard()?;
+            [Scope::External, Scope::Internal]
+                .into_iter()
+                .find_map(|scope| {
+                    let index = fvk.to_ivk(scope).diversifier_index(addr)?;
+                    Some(owner_of(key, scope, index_to_u64(&index)))
+                })
+        });
+        receiver(
+            "Orchard",
+            None,
+            addr.to_raw_address_bytes().to_vec(),
+            !ufvks.is_empty(),
+            owner,
+        )
+    };
+    let transparent_receiver = |addr: &TransparentAddress| match addr {
+        TransparentAddress::PublicKeyHash(hash) => {
+            receiver("P2PKH", None, hash.to_vec(), false, None)
+        }
+        TransparentAddress::ScriptHash(hash) => receiver("P2SH", None, hash.to_vec(), false, None),
+    };
+
+    let (kind, receivers) = match &decoded {
+        Address::Unified(ua) => {
+            let mut receivers = Vec::new();
+            receivers.extend(ua.orchard().map(orchard_receiver));
+            receivers.extend(ua.sapling().map(sapling_receiver));
+            receivers.extend(ua.transparent().map(transparent_receiver));
+            receivers.extend(ua.unknown().iter().map(|(typecode, data)| {
+                receiver("Unknown", Some(*typecode), data.clone(), false, None)
+            }));
+            ("unified", receivers)
+        }
+        Address::Sapling(pa) => ("sapling", vec![sapling_receiver(pa)]),
+        Address::Transparent(addr) => ("transparent", vec![transparent_receiver(addr)]),
+        Address::Tex(hash) => (
+            "tex",
+            vec![receiver("P2PKH", None, hash.to_vec(), false, None)],
+        ),
+    };
+
+    Ok(AddressInfo {
+        address: address.trim().to_string(),
+        kind: kind.to_string(),
+        network: network::network_name(network.network_type()).to_string(),
+        derivable: derivable(&receivers),
+        receivers,
+    })
+}
+
+/// True when every checked receiver has the same owner, false when any has
+/// none or they disagree, None when nothing was checked.
+fn derivable(receivers: &[AddressReceiver]) -> Option<bool> {
+    let mut checked = receivers.iter().filter(|r| r.checked).peekable();
+    let first = checked.peek()?.owner.clone();
+    Some(first.is_some() && checked.all(|r| r.owner == first))
+}
+
+fn receiver(
+    protocol: &str,
+    typecode: Option<u32>,
+    data: Vec<u8>,
+    checked: bool,
+    owner: Option<ReceiverOwner>,
+) -> AddressReceiver {
+    AddressReceiver {
+        protocol: protocol.to_string(),
+        typecode,
+        data: hex::encode(data),
+        checked,
+        owner,
+    }
+}
+
+fn all_networks() -> [ChainParams; 3] {
+    [
+        ChainParams::Standard(Network::MainNetwork),
+        ChainParams::Standard(Network::TestNetwork),
+        ChainParams::regtest(),
+    ]
+}
+
+fn scope_name(scope: Scope) -> &'static str {
+    match scope {
+        Scope::External => "external",
+        Scope::Internal => "internal",
+    }
+}
@@ -0,0 +1,114 @@
+//! `--sink amqp://<user>:<pass>@<host>/<vhost>?exchange=<name>&routing_key=<key>`:
+//! publish events to an AMQP 0-9-1 broker such as RabbitMQ, as persistent
+//! JSON messages. Publisher confirms are on, so an event counts as delivered
+//! only once the broker has taken responsibility for it. Output events carry
+//! their idempotency key as the message ID.
+//!
+//! `exchange` defaults to the default exchange and `routing_key` to
+//! `zcash-tx-decryptor`, which together deliver to the queue of that name.
+//! Other query parameters (e.g. `heartbeat`) are passed to the client.
+
+use anyhow::{anyhow, Context, Result};
+use lapin::{
+    options::{BasicPublishOptions, ConfirmSelectOptions},
+    BasicProperties, Channel, Connection, ConnectionProperties,
+};
+use reqwest::Url;
+
+use crate::pipeline::{self, DeliveryError, ResultSink};
+
+const DEFAULT_ROUTING_KEY: &str = "zcash-tx-decryptor";
+
+pub struct AmqpSink {
+    /// Broker URI without our own query parameters
+    uri: String,
+    exchange: String,
+    routing_key: String,
+    /// Open connection, dropped after a failure so the next delivery
+    /// reconnects
+    connection: Option<(Connection, Channel)>,
+}
+
+impl AmqpSink {
+    /// Sink for a `--sink amqp://...` URL. The broker is connected to on the
+    /// first delivery.
+    pub fn new(url: &str) -> Result<Self> {
+        // Not quoted in the error: the URL may carry a password
+        let mut uri = Url::parse(url).context("Invalid AMQP sink URL")?;
+        let mut exchange = String::new();
+        let mut routing_key = DEFAULT_ROUTING_KEY.to_string();
+        let mut others = Vec::new();
+        for (name, value) in uri.query_pairs() {
+            match name.as_ref() {
+                "exchange" => exchange = value.into_owned(),
+                "routing_key" => routing_key = value.into_owned(),
+                _ => others.push((name.into_owned(), value.into_owned())),
+            }
+        }
+        if others.is_empty() {
+            uri.set_query(None);
+        } else {
+            uri.query_pairs_mut().clear().extend_pairs(&others);
+        }
+
+        Ok(AmqpSink {
+            uri: uri.to_string(),
+            exchange,
+            routing_key,
+            connection: None,
+        })
+    }
+
+    async fn publish(&mut self, event: &str) -> Result<()> {
+        let connected = self
+            .connection
+            .as_ref()
+            .is_some_and(|(connection, _)| connection.status().connected());
+        if !connected {
+            let connection = Connection::connect(&self.uri, ConnectionProperties::default())
+                .await
+                .context("Failed to connect to the AMQP broker")?;
+            let channel = connection.create_channel().await?;
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await?;
+            self.connection = Some((connection, channel));
+        }
+        let (_, channel) = self.connection.as_ref().expect("connected above");
+
+        let mut properties = BasicProperties::default()
+            .with_content_type("application/json".into())
+            .with_delivery_mode(2);
+        if let Some(key) = pipeline::idempotency_key(event) {
+            properties = properties.with_message_id(key.into());
+        }
+        let confirmation = channel
+            .basic_publish(
+                &self.exchange,
+                &self.routing_key,
+                BasicPublishOptions::default(),
+                event.as_bytes(),
+                properties,
+            )
+            .await?
+            .await?;
+        if confirmation.is_nack() {
+            return Err(anyhow!("The AMQP broker refused the event"));
+        }
+        Ok(())
+    }
+}
+
+impl ResultSink for AmqpSink {
+    fn name(&self) -> &'static str {
+        "amqp"
+    }
+
+    async fn deliver(&mut self, event: &str) -> Result<(), DeliveryError> {
+        let result = self.publish(event).await;
+        if result.is_err() {
+            self.connection = None;
+        }
+        result.map_err(DeliveryError::transient)
+    }
+}
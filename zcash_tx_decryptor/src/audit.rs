@@ -0,0 +1,125 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use ed25519_dalek::{Signer, SigningKey};
+use zcash_client_backend::proto::compact_formats::CompactBlock;
+use zcash_primitives::block::BlockHash;
+
+use crate::{
+    compact::{self, CompactScanner},
+    decrypt::key_fingerprint,
+    models::{AuditedNote, SignedAttestation, UnspentAttestation},
+};
+
+/// Check that every note received in `blocks` is still unspent at the end of
+/// them, and return a signed attestation of the result.
+///
+/// `blocks` must be exactly `from_height..=to_height`, each linked to its
+/// predecessor by hash, so that a spend cannot hide in a missing block.
+/// Notes received before `from_height` are not covered.
+pub fn unspent(
+    scanner: &CompactScanner,
+    encoded_ufvks: &[String],
+    blocks: &[CompactBlock],
+    from_height: u32,
+    to_height: u32,
+    signing_key: &SigningKey,
+) -> Result<SignedAttestation> {
+    check_range(blocks, from_height, to_height)?;
+
+    let mut spends = HashMap::new();
+    let mut received = Vec::new();
+    for block in blocks {
+        received.extend(scanner.scan_notes(block)?);
+        spends.extend(compact::spent_nullifiers(block));
+    }
+
+    let notes: Vec<AuditedNote> = received
+        .into_iter()
+        .map(|note| AuditedNote {
+            spent_in: spends.get(&note.nullifier).cloned(),
+            transaction_id: note.txid,
+            block_height: note.height,
+            account: note.account,
+            protocol: note.protocol.to_string(),
+            amount_zats: note.value_zats,
+            nullifier: hex::encode(note.nullifier),
+        })
+        .collect();
+
+    // `check_range` guarantees a last block with a 32-byte hash.
+    let tip = blocks
+        .last()
+        .map(|block| BlockHash::from_slice(&block.hash));
+    let attestation = UnspentAttestation {
+        from_height,
+        to_height,
+        tip_block_hash: tip.map(|hash| hash.to_string()).unwrap_or_default(),
+        key_fingerprints: encoded_ufvks
+            .iter()
+            .map(|ufvk| key_fingerprint(ufvk))
+            .collect(),
+        total_zats: notes.iter().map(|note| note.amount_zats).sum(),
+        all_unspent: notes.iter().all(|note| note.spent_in.is_none()),
+        notes,
+        generated_at: Utc::now(),
+    };
+
+    let signature = signing_key.sign(&serde_json::to_vec(&attestation)?);
+    Ok(SignedAttestation {
+        attestation,
+        public_key: hex::encode(signing_key.verifying_key().to_bytes()),
+        signature: hex::encode(signature.to_bytes()),
+    })
+}
+
+/// Load an Ed25519 signing key from a file holding its 32-byte seed as hex.
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read signing key {}", path.display()))?;
+    let seed: [u8; 32] = hex::decode(contents.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Signing key must be 64 hex characters (a 32-byte seed)"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn check_range(blocks: &[CompactBlock], from_height: u32, to_height: u32) -> Result<()> {
+    if from_height > to_height {
+        return Err(anyhow!("--from-height is above --to-height"));
+    }
+    let expected = u64::from(to_height - from_height) + 1;
+    if blocks.len() as u64 != expected {
+        return Err(anyhow!(
+            "Expected {} compact block(s) for heights {}..={}, found {}",
+            expected,
+            from_height,
+            to_height,
+            blocks.len()
+        ));
+    }
+
+    for (i, block) in blocks.iter().enumerate() {
+        let height = u64::from(from_height) + i as u64;
+        if block.height != height {
+            return Err(anyhow!(
+                "Compact block #{} has height {}, expected {}",
+                i,
+                block.height,
+                height
+            ));
+        }
+        if block.hash.len() != 32 {
+            return Err(anyhow!("Compact block {} has no valid hash", height));
+        }
+        if i > 0 && block.prev_hash != blocks[i - 1].hash {
+            return Err(anyhow!(
+                "Compact block {} does not extend block {}",
+                height,
+                height - 1
+            ));
+        }
+    }
+    Ok(())
+}
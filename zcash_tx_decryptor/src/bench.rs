@@ -0,0 +1,158 @@
+//! `bench`: trial-decryption throughput by thread and key count, for sizing
+//! hardware before scanning with many viewing keys.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use orchard::{
+    builder::{Builder, BundleType},
+    bundle::Authorized,
+    primitives::redpallas::Signature,
+    value::NoteValue,
+    Anchor, Proof,
+};
+use rand::{rngs::OsRng, Rng, RngCore};
+use zcash_client_backend::keys::{UnifiedFullViewingKey, UnifiedSpendingKey};
+use zcash_primitives::{
+    consensus::{BlockHeight, BranchId, NetworkUpgrade, Parameters},
+    transaction::{components::Amount, TransactionData, TxVersion},
+    zip32::{AccountId, Scope},
+};
+
+use crate::{decrypt::Decryptor, network::ChainParams};
+
+/// Transactions to decrypt, each with the height it is decrypted at.
+pub type Workload = Vec<(BlockHeight, Vec<u8>)>;
+
+/// One line of the results table.
+pub struct BenchRow {
+    pub threads: usize,
+    pub keys: usize,
+    pub transactions: usize,
+    pub outputs: u64,
+    pub elapsed: Duration,
+}
+
+/// `count` throwaway UFVKs from random seeds. None of them can match a real
+/// or synthetic output, so every output is trial-decrypted in full against
+/// every key, which is the common case when scanning.
+pub fn random_keys(network: &ChainParams, count: usize) -> Result<Vec<UnifiedFullViewingKey>> {
+    (0..count)
+        .map(|_| {
+            let mut seed = [0u8; 32];
+            OsRng.fill_bytes(&mut seed);
+            let usk = UnifiedSpendingKey::from_seed(network, &seed, AccountId::ZERO)
+                .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+            Ok(usk.to_unified_full_viewing_key())
+        })
+        .collect()
+}
+
+/// `count` v5 transactions at NU5 activation, each with an Orchard bundle of
+/// `actions` outputs to a random address.
+///
+/// The note ciphertexts are real, so trial decryption does the same work as
+/// on chain. Proofs and signatures are placeholders: decryption never checks
+/// them, and building real proofs would take far longer than the benchmark.
+pub fn synthetic_transactions(
+    network: &ChainParams,
+    count: usize,
+    actions: usize,
+) -> Result<Workload> {
+    let height = network
+        .activation_height(NetworkUpgrade::Nu5)
+        .ok_or_else(|| anyhow!("NU5 is not active on this network"))?;
+    let recipient = random_keys(network, 1)?[0]
+        .orchard()
+        .ok_or_else(|| anyhow!("Generated key has no Orchard component"))?
+        .address_at(0u32, Scope::External);
+
+    let mut rng = OsRng;
+    let mut workload = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut builder = Builder::new(BundleType::DEFAULT, Anchor::empty_tree());
+        for _ in 0..actions {
+            let value = NoteValue::from_raw(rng.gen_range(10_000..100_000_000));
+            builder
+                .add_output(None, recipient, value, None)
+                .map_err(|e| anyhow!("Failed to add synthetic output: {:?}", e))?;
+        }
+        let (bundle, _) = builder
+            .build::<Amount>(&mut rng)
+            .map_err(|e| anyhow!("Failed to build synthetic bundle: {:?}", e))?
+            .ok_or_else(|| anyhow!("Synthetic bundle is empty"))?;
+        let bundle = bundle.map_authorization(
+            &mut (),
+            |_, _, _| Signature::from([0u8; 64]),
+            |_, _| Authorized::from_parts(Proof::new(Vec::new()), Signature::from([0u8; 64])),
+        );
+
+        let tx = TransactionData::from_parts(
+            TxVersion::Zip225,
+            BranchId::Nu5,
+            0,
+            height + 40,
+            None,
+            None,
+            None,
+            Some(bundle),
+        )
+        .freeze()
+        .context("Failed to assemble synthetic transaction")?;
+        let mut bytes = Vec::new();
+        tx.write(&mut bytes)
+            .context("Failed to serialize synthetic transaction")?;
+        workload.push((height, bytes));
+    }
+    Ok(workload)
+}
+
+/// Decrypt `workload` once with the first `keys` of `ufvks` on `threads`
+/// workers.
+pub fn run_once(
+    network: ChainParams,
+    ufvks: &[UnifiedFullViewingKey],
+    keys: usize,
+    threads: usize,
+    workload: &Workload,
+) -> Result<BenchRow> {
+    let decryptor = Decryptor::new(network, ufvks[..keys].to_vec(), None, false);
+    let started = Instant::now();
+    let results = crate::parallel_decrypt(Some(threads), workload, |(height, tx)| {
+        decryptor.decrypt(*height, None, tx).map(drop)
+    })?;
+    let elapsed = started.elapsed();
+    if let Some(Err(e)) = results.into_iter().find(Result::is_err) {
+        return Err(e.context("Benchmark transaction failed to decrypt"));
+    }
+
+    Ok(BenchRow {
+        threads,
+        keys,
+        transactions: workload.len(),
+        outputs: decryptor.trial_outputs(),
+        elapsed,
+    })
+}
+
+/// Print the results table; rates are per second of wall time.
+pub fn print_table(rows: &[BenchRow]) {
+    println!(
+        "{:>7} {:>5} {:>8} {:>9} {:>9} {:>10} {:>12} {:>14}",
+        "threads", "keys", "txs", "outputs", "seconds", "tx/s", "outputs/s", "key-trials/s"
+    );
+    for row in rows {
+        let secs = row.elapsed.as_secs_f64().max(f64::EPSILON);
+        println!(
+            "{:>7} {:>5} {:>8} {:>9} {:>9.2} {:>10.1} {:>12.1} {:>14.1}",
+            row.threads,
+            row.keys,
+            row.transactions,
+            row.outputs,
+            secs,
+            row.transactions as f64 / secs,
+            row.outputs as f64 / secs,
+            (row.outputs * row.keys as u64) as f64 / secs,
+        );
+    }
+}
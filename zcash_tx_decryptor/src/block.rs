@@ -0,0 +1,170 @@
+use std::{io::Cursor, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use zcash_primitives::{
+    block::{BlockHash, BlockHeader},
+    consensus::BlockHeight,
+    transaction::Transaction,
+};
+use zcash_protocol::consensus::{NetworkUpgrade, Parameters};
+
+use crate::{
+    exit::ErrorKind,
+    network::ChainParams,
+    parse::{self, BranchTable},
+};
+
+/// A full block, split into its transactions.
+pub struct RawBlock {
+    pub hash: BlockHash,
+
+    /// Hash of the block this one builds on
+    pub prev_hash: BlockHash,
+
+    /// Height committed to by the coinbase transaction (BIP 34)
+    pub height: u32,
+
+    /// Block time from the header
+    pub time: DateTime<Utc>,
+
+    /// Raw bytes of each transaction, coinbase first
+    pub transactions: Vec<Vec<u8>>,
+}
+
+/// Load a block in the node's serialization (`getblock <hash> 0`), as hex or
+/// binary, and split it into transactions without any RPC.
+///
+/// Each transaction is parsed only to find where it ends. The coinbase is read
+/// first at `height_hint`, or if that is None at a height estimated from the
+/// coinbase itself (see [`coinbase_hint`]); the remaining transactions are
+/// read at the height the coinbase commits to.
+pub fn load_raw_block(
+    path: &Path,
+    params: &ChainParams,
+    height_hint: Option<BlockHeight>,
+    branch_id_override: Option<u32>,
+) -> Result<RawBlock> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read raw block {}", path.display()))?;
+    let data = if parse::is_hex_text(&data) {
+        let hex: Vec<u8> = data.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        hex::decode(hex)
+            .context("Raw block hex is invalid")
+            .context(ErrorKind::Parse)?
+    } else {
+        data
+    };
+    parse_raw_block(&data, params, height_hint, branch_id_override)
+}
+
+/// Split a block in the node's serialization into transactions; see
+/// [`load_raw_block`].
+pub fn parse_raw_block(
+    data: &[u8],
+    params: &ChainParams,
+    height_hint: Option<BlockHeight>,
+    branch_id_override: Option<u32>,
+) -> Result<RawBlock> {
+    let mut reader = Cursor::new(data);
+    let header = BlockHeader::read(&mut reader)
+        .context("Failed to parse block header")
+        .context(ErrorKind::Parse)?;
+    let count = read_compact_size(&mut reader)?;
+    let mut pos = reader.position() as usize;
+
+    let table = params.branch_table();
+    let mut height =
+        height_hint.unwrap_or_else(|| coinbase_hint(&data[pos..], params, &table));
+    let mut transactions = Vec::new();
+    for i in 0..count {
+        let parsed =
+            parse::parse_transaction(&data[pos..], params, height, &table, branch_id_override)
+                .with_context(|| format!("Failed to parse transaction {} of the block", i))
+                .context(ErrorKind::Parse)?;
+        if i == 0 {
+            height = BlockHeight::from_u32(coinbase_height(&parsed.tx)?);
+        }
+        transactions.push(data[pos..pos + parsed.size].to_vec());
+        pos += parsed.size;
+    }
+    if pos != data.len() {
+        return Err(anyhow!(
+            "{} trailing byte(s) after the block's {} transaction(s)",
+            data.len() - pos,
+            count
+        ));
+    }
+    tracing::debug!(
+        height = u32::from(height),
+        hash = %header.hash(),
+        transactions = count,
+        bytes = data.len(),
+        "parsed block"
+    );
+
+    Ok(RawBlock {
+        hash: header.hash(),
+        prev_hash: header.prev_block,
+        height: u32::from(height),
+        time: DateTime::from_timestamp(i64::from(header.time), 0).unwrap_or_default(),
+        transactions,
+    })
+}
+
+/// Height to read a block's coinbase at before its height is known, estimated
+/// as for a lone transaction: NU5 coinbases expire at their own height, and
+/// the branch bounds the rest. Pre-v5 formats don't depend on the branch ID,
+/// so when there is no estimate any height after Sapling activation will do.
+fn coinbase_hint(tx_bytes: &[u8], params: &ChainParams, table: &BranchTable) -> BlockHeight {
+    parse::estimate_height(tx_bytes, params, table)
+        .map(|estimate| BlockHeight::from_u32(estimate.height))
+        .or_else(|| params.activation_height(NetworkUpgrade::Sapling))
+        .unwrap_or_else(|| BlockHeight::from_u32(0))
+}
+
+/// Height pushed at the start of the coinbase input's script (BIP 34).
+fn coinbase_height(tx: &Transaction) -> Result<u32> {
+    let script = tx
+        .transparent_bundle()
+        .and_then(|bundle| bundle.vin.first())
+        .map(|input| &input.script_sig.0[..])
+        .ok_or_else(|| anyhow!("First transaction of the block is not a coinbase"))?;
+
+    match script.first() {
+        // OP_0, OP_1..OP_16
+        Some(0x00) => Ok(0),
+        Some(&op @ 0x51..=0x60) => Ok(u32::from(op - 0x50)),
+        // A direct push of a little-endian script number
+        Some(&len @ 1..=4) => {
+            let bytes = script
+                .get(1..1 + len as usize)
+                .ok_or_else(|| anyhow!("Coinbase script is truncated"))?;
+            Ok(bytes
+                .iter()
+                .rev()
+                .fold(0u32, |height, &b| (height << 8) | u32::from(b)))
+        }
+        _ => Err(anyhow!("Coinbase script does not start with the block height")),
+    }
+}
+
+/// Bitcoin-style CompactSize integer.
+fn read_compact_size(reader: &mut Cursor<&[u8]>) -> Result<u64> {
+    let mut next = |n: usize| -> Result<u64> {
+        let start = reader.position() as usize;
+        let bytes = reader
+            .get_ref()
+            .get(start..start + n)
+            .ok_or_else(|| anyhow!("Block truncated in transaction count"))?;
+        reader.set_position((start + n) as u64);
+        Ok(bytes.iter().rev().fold(0, |v, &b| (v << 8) | u64::from(b)))
+    };
+
+    match next(1)? {
+        0xfd => next(2),
+        0xfe => next(4),
+        0xff => next(8),
+        n => Ok(n),
+    }
+}
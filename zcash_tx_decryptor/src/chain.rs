@@ -0,0 +1,121 @@
+//! Chain context for reports: where a transaction was mined and how deep it
+//! is, asked of the node with `--rpc-url`; and reorg detection for
+//! incremental scans into a result store.
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use zcash_client_backend::proto::compact_formats::CompactBlock;
+
+use crate::{
+    models::{Diagnostic, TxReport},
+    rpc::RpcClient,
+    store::ResultStore,
+};
+
+/// Confirmations after which a transaction counts as final. zcashd refuses
+/// reorgs deeper than 99 blocks (`MAX_REORG_LENGTH`), so a block 100 deep is
+/// behind its rolling checkpoint and can't be undone without manual
+/// intervention.
+pub const FINALITY_DEPTH: u32 = 100;
+
+/// Fill in `report`'s height, block time, block hash, confirmations and
+/// finality from the node. An unmined transaction gets 0 confirmations. A
+/// failed lookup becomes a warning on the report rather than an error.
+pub async fn enrich(rpc: &RpcClient, report: &mut TxReport) {
+    let location = match rpc.get_transaction_location(&report.txid).await {
+        Ok(location) => location,
+        Err(e) => {
+            report.warnings.push(Diagnostic {
+                code: "chain_context_unavailable".to_string(),
+                message: format!("{:#}", e),
+            });
+            return;
+        }
+    };
+
+    if let Some(height) = location.height {
+        report.height = height;
+    }
+    if let Some(time) = location.blocktime {
+        report.block_time = DateTime::from_timestamp(time, 0).or(report.block_time);
+    }
+    if location.blockhash.is_some() {
+        report.block_hash = location.blockhash;
+    }
+    let confirmations = location.confirmations.unwrap_or(0);
+    report.confirmations = Some(confirmations);
+    report.is_final = Some(confirmations >= FINALITY_DEPTH);
+}
+
+/// Lowest height recorded in `store` whose block is no longer on the chain
+/// that `blocks` (in height order) extend, or None if there was no reorg.
+///
+/// Recorded heights the blocks cover are compared with them directly. If the
+/// first block doesn't build on the block recorded below it, the fork is
+/// further down, and the node is asked for its hashes until one matches.
+pub async fn find_fork(
+    store: &ResultStore,
+    rpc: &RpcClient,
+    blocks: &[CompactBlock],
+) -> Result<Option<u32>> {
+    let Some(first) = blocks.first() else {
+        return Ok(None);
+    };
+    let first_height = u32::from(first.height());
+
+    if let Some(parent) = first_height.checked_sub(1) {
+        if let Some(recorded) = store.block_hash(parent)? {
+            if recorded != block_hashes(first)?.1 {
+                return walk_back(store, rpc, parent, first_height).await.map(Some);
+            }
+        }
+    }
+
+    for block in blocks {
+        let height = u32::from(block.height());
+        if let Some(recorded) = store.block_hash(height)? {
+            if recorded != block_hashes(block)?.0 {
+                return Ok(Some(height));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Walk down from `orphaned` to the lowest recorded block the node no longer
+/// has, giving up beyond [`FINALITY_DEPTH`] below `from`.
+pub async fn walk_back(
+    store: &ResultStore,
+    rpc: &RpcClient,
+    orphaned: u32,
+    from: u32,
+) -> Result<u32> {
+    let mut height = orphaned;
+    while let Some(below) = height.checked_sub(1) {
+        match store.block_hash(below)? {
+            Some(recorded) if recorded != rpc.get_block_hash(below).await? => height = below,
+            _ => return Ok(height),
+        }
+        if from - height > FINALITY_DEPTH {
+            return Err(anyhow!(
+                "Reorg reaches below height {}, more than {} blocks deep; rebuild the result \
+                 store",
+                height,
+                FINALITY_DEPTH
+            ));
+        }
+    }
+    Ok(height)
+}
+
+/// Hash and parent hash of `block` in display order, as the node reports
+/// them.
+pub fn block_hashes(block: &CompactBlock) -> Result<(String, String)> {
+    if block.header.is_empty() && (block.hash.len() != 32 || block.prev_hash.len() != 32) {
+        return Err(anyhow!(
+            "Compact block {} has no valid block hash",
+            block.height
+        ));
+    }
+    Ok((block.hash().to_string(), block.prev_hash().to_string()))
+}
@@ -0,0 +1,306 @@
+//! Coinbase outputs: the miner's share of the block reward and the funding
+//! streams (ZIP 207) paid out of it.
+//!
+//! A funding stream output is recognized by its value, which consensus fixes
+//! at a share of the block subsidy for the stream's height range, and by its
+//! recipient, the stream's address for the height's address period (ZIP 214).
+//! Every recipient is a transparent P2SH address, so only watched
+//! transparent outputs can match. Custom networks have no known streams, so
+//! their coinbase outputs are never classified.
+
+use zcash_protocol::consensus::Network;
+
+use crate::network::ChainParams;
+
+/// Confirmations a coinbase output needs before it can be spent.
+pub const COINBASE_MATURITY: u32 = 100;
+
+/// Block subsidy before any halving, in zatoshis (12.5 ZEC).
+const MAX_BLOCK_SUBSIDY: u64 = 1_250_000_000;
+
+const SLOW_START_SHIFT: u32 = 10_000;
+const POST_BLOSSOM_HALVING_INTERVAL: u32 = 1_680_000;
+/// Blocks between funding stream recipient changes (ZIP 214).
+const ADDRESS_CHANGE_INTERVAL: u32 = POST_BLOSSOM_HALVING_INTERVAL / 48;
+
+/// A funding stream and the heights it is paid at on mainnet and testnet
+/// (start inclusive, end exclusive).
+struct FundingStream {
+    name: &'static str,
+    /// Percentage of the block subsidy
+    percent: u64,
+    mainnet: (u32, u32),
+    testnet: (u32, u32),
+    /// Recipients by address period from the stream's start; a single
+    /// address is paid for every period
+    mainnet_recipients: &'static [&'static str],
+    testnet_recipients: &'static [&'static str],
+}
+
+/// ZIP 214 streams from Canopy to the second halving, and the Zcash
+/// Community Grants stream of ZIP 1015 and ZIP 1016 after it. The ZIP 1015
+/// lockbox has no coinbase output.
+const STREAMS: &[FundingStream] = &[
+    FundingStream {
+        name: "electric_coin_company",
+        percent: 7,
+        mainnet: (1_046_400, 2_726_400),
+        testnet: (1_028_500, 2_796_000),
+        mainnet_recipients: MAINNET_ECC_ADDRESSES,
+        testnet_recipients: TESTNET_ECC_ADDRESSES,
+    },
+    FundingStream {
+        name: "zcash_foundation",
+        percent: 5,
+        mainnet: (1_046_400, 2_726_400),
+        testnet: (1_028_500, 2_796_000),
+        mainnet_recipients: &["t3dvVE3SQEi7kqNzwrfNePxZ1d4hUyztBA1"],
+        testnet_recipients: &["t27eWDgjFYJGVXmzrXeVjnb5J3uXDM9xH9v"],
+    },
+    FundingStream {
+        name: "major_grants",
+        percent: 8,
+        mainnet: (1_046_400, 2_726_400),
+        testnet: (1_028_500, 2_796_000),
+        mainnet_recipients: &["t3XyYW8yBFRuMnfvm5KLGFbEVz25kckZXym"],
+        testnet_recipients: &["t2Gvxv2uNM7hbbACjNox4H6DjByoKZ2Fa3P"],
+    },
+    FundingStream {
+        name: "zcash_community_grants",
+        percent: 8,
+        mainnet: (2_726_400, 4_406_400),
+        testnet: (2_976_000, 3_396_000),
+        mainnet_recipients: &["t3cFfPt1Bcvgez9ZbMBFWeZsskxTkPzGCow"],
+        testnet_recipients: &["t2HifwjUj9uyxr9bknR8LFuQbc98c3vkXtu"],
+    },
+];
+
+/// Electric Coin Company recipients on mainnet, one per address period.
+const MAINNET_ECC_ADDRESSES: &[&str] = &[
+    "t3LmX1cxWPPPqL4TZHx42HU3U5ghbFjRiif",
+    "t3Toxk1vJQ6UjWQ42tUJz2rV2feUWkpbTDs",
+    "t3ZBdBe4iokmsjdhMuwkxEdqMCFN16YxKe6",
+    "t3ZuaJziLM8xZ32rjDUzVjVtyYdDSz8GLWB",
+    "t3bAtYWa4bi8VrtvqySxnbr5uqcG9czQGTZ",
+    "t3dktADfb5Rmxncpe1HS5BRS5Gcj7MZWYBi",
+    "t3hgskquvKKoCtvxw86yN7q8bzwRxNgUZmc",
+    "t3R1VrLzwcxAZzkX4mX3KGbWpNsgtYtMntj",
+    "t3ff6fhemqPMVujD3AQurxRxTdvS1pPSaa2",
+    "t3cEUQFG3KYnFG6qYhPxSNgGi3HDjUPwC3J",
+    "t3WR9F5U4QvUFqqx9zFmwT6xFqduqRRXnaa",
+    "t3PYc1LWngrdUrJJbHkYPCKvJuvJjcm85Ch",
+    "t3bgkjiUeatWNkhxY3cWyLbTxKksAfk561R",
+    "t3Z5rrR8zahxUpZ8itmCKhMSfxiKjUp5Dk5",
+    "t3PU1j7YW3fJ67jUbkGhSRto8qK2qXCUiW3",
+    "t3S3yaT7EwNLaFZCamfsxxKwamQW2aRGEkh",
+    "t3eutXKJ9tEaPSxZpmowhzKhPfJvmtwTEZK",
+    "t3gbTb7brxLdVVghSPSd3ycGxzHbUpukeDm",
+    "t3UCKW2LrHFqPMQFEbZn6FpjqnhAAbfpMYR",
+    "t3NyHsrnYbqaySoQqEQRyTWkjvM2PLkU7Uu",
+    "t3QEFL6acxuZwiXtW3YvV6njDVGjJ1qeaRo",
+    "t3PdBRr2S1XTDzrV8bnZkXF3SJcrzHWe1wj",
+    "t3ZWyRPpWRo23pKxTLtWsnfEKeq9T4XPxKM",
+    "t3he6QytKCTydhpztykFsSsb9PmBT5JBZLi",
+    "t3VWxWDsLb2TURNEP6tA1ZSeQzUmPKFNxRY",
+    "t3NmWLvZkbciNAipauzsFRMxoZGqmtJksbz",
+    "t3cKr4YxVPvPBG1mCvzaoTTdBNokohsRJ8n",
+    "t3T3smGZn6BoSFXWWXa1RaoQdcyaFjMfuYK",
+    "t3gkDUe9Gm4GGpjMk86TiJZqhztBVMiUSSA",
+    "t3eretuBeBXFHe5jAqeSpUS1cpxVh51fAeb",
+    "t3dN8g9zi2UGJdixGe9txeSxeofLS9t3yFQ",
+    "t3S799pq9sYBFwccRecoTJ3SvQXRHPrHqvx",
+    "t3fhYnv1S5dXwau7GED3c1XErzt4n4vDxmf",
+    "t3cmE3vsBc5xfDJKXXZdpydCPSdZqt6AcNi",
+    "t3h5fPdjJVHaH4HwynYDM5BB3J7uQaoUwKi",
+    "t3Ma35c68BgRX8sdLDJ6WR1PCrKiWHG4Da9",
+    "t3LokMKPL1J8rkJZvVpfuH7dLu6oUWqZKQK",
+    "t3WFFGbEbhJWnASZxVLw2iTJBZfJGGX73mM",
+    "t3L8GLEsUn4QHNaRYcX3EGyXmQ8kjpT1zTa",
+    "t3PgfByBhaBSkH8uq4nYJ9ZBX4NhGCJBVYm",
+    "t3WecsqKDhWXD4JAgBVcnaCC2itzyNZhJrv",
+    "t3ZG9cSfopnsMQupKW5v9sTotjcP5P6RTbn",
+    "t3hC1Ywb5zDwUYYV8LwhvF5rZ6m49jxXSG5",
+    "t3VgMqDL15ZcyQDeqBsBW3W6rzfftrWP2yB",
+    "t3LC94Y6BwLoDtBoK2NuewaEbnko1zvR9rm",
+    "t3cWCUZJR3GtALaTcatrrpNJ3MGbMFVLRwQ",
+    "t3YYF4rPLVxDcF9hHFsXyc5Yq1TFfbojCY6",
+    "t3XHAGxRP2FNfhAjxGjxbrQPYtQQjc3RCQD",
+];
+
+/// Electric Coin Company recipients on testnet, one per address period.
+const TESTNET_ECC_ADDRESSES: &[&str] = &[
+    "t26ovBdKAJLtrvBsE2QGF4nqBkEuptuPFZz",
+    "t26ovBdKAJLtrvBsE2QGF4nqBkEuptuPFZz",
+    "t26ovBdKAJLtrvBsE2QGF4nqBkEuptuPFZz",
+    "t26ovBdKAJLtrvBsE2QGF4nqBkEuptuPFZz",
+    "t2NNHrgPpE388atmWSF4DxAb3xAoW5Yp45M",
+    "t2VMN28itPyMeMHBEd9Z1hm6YLkQcGA1Wwe",
+    "t2CHa1TtdfUV8UYhNm7oxbzRyfr8616BYh2",
+    "t2F77xtr28U96Z2bC53ZEdTnQSUAyDuoa67",
+    "t2ARrzhbgcpoVBDPivUuj6PzXzDkTBPqfcT",
+    "t278aQ8XbvFR15mecRguiJDQQVRNnkU8kJw",
+    "t2Dp1BGnZsrTXZoEWLyjHmg3EPvmwBnPDGB",
+    "t2KzeqXgf4ju33hiSqCuKDb8iHjPCjMq9iL",
+    "t2Nyxqv1BiWY1eUSiuxVw36oveawYuo18tr",
+    "t2DKFk5JRsVoiuinK8Ti6eM4Yp7v8BbfTyH",
+    "t2CUaBca4k1x36SC4q8Nc8eBoqkMpF3CaLg",
+    "t296SiKL7L5wvFmEdMxVLz1oYgd6fTfcbZj",
+    "t29fBCFbhgsjL3XYEZ1yk1TUh7eTusB6dPg",
+    "t2FGofLJXa419A76Gpf5ncxQB4gQXiQMXjK",
+    "t2ExfrnRVnRiXDvxerQ8nZbcUQvNvAJA6Qu",
+    "t28JUffLp47eKPRHKvwSPzX27i9ow8LSXHx",
+    "t2JXWPtrtyL861rFWMZVtm3yfgxAf4H7uPA",
+    "t2QdgbJoWfYHgyvEDEZBjHmgkr9yNJff3Hi",
+    "t2QW43nkco8r32ZGRN6iw6eSzyDjkMwCV3n",
+    "t2DgYDXMJTYLwNcxighQ9RCgPxMVATRcUdC",
+    "t2Bop7dg33HGZx3wunnQzi2R2ntfpjuti3M",
+    "t2HVeEwovcLq9RstAbYkqngXNEsCe2vjJh9",
+    "t2HxbP5keQSx7p592zWQ5bJ5GrMmGDsV2Xa",
+    "t2TJzUg2matao3mztBRJoWnJY6ekUau6tPD",
+    "t29pMzxmo6wod25YhswcjKv3AFRNiBZHuhj",
+    "t2QBQMRiJKYjshJpE6RhbF7GLo51yE6d4wZ",
+    "t2F5RqnqguzZeiLtYHFx4yYfy6pDnut7tw5",
+    "t2CHvyZANE7XCtg8AhZnrcHCC7Ys1jJhK13",
+    "t2BRzpMdrGWZJ2upsaNQv6fSbkbTy7EitLo",
+    "t2BFixHGQMAWDY67LyTN514xRAB94iEjXp3",
+    "t2Uvz1iVPzBEWfQBH1p7NZJsFhD74tKaG8V",
+    "t2CmFDj5q6rJSRZeHf1SdrowinyMNcj438n",
+    "t2ErNvWEReTfPDBaNizjMPVssz66aVZh1hZ",
+    "t2GeJQ8wBUiHKDVzVM5ZtKfY5reCg7CnASs",
+    "t2L2eFtkKv1G6j55kLytKXTGuir4raAy3yr",
+    "t2EK2b87dpPazb7VvmEGc8iR6SJ289RywGL",
+    "t2DJ7RKeZJxdA4nZn8hRGXE8NUyTzjujph9",
+    "t2K1pXo4eByuWpKLkssyMLe8QKUbxnfFC3H",
+    "t2TB4mbSpuAcCWkH94Leb27FnRxo16AEHDg",
+    "t2Phx4gVL4YRnNsH3jM1M7jE4Fo329E66Na",
+    "t2VQZGmeNomN8c3USefeLL9nmU6M8x8CVzC",
+    "t2RicCvTVTY5y9JkreSRv3Xs8q2K67YxHLi",
+    "t2JrSLxTGc8wtPDe9hwbaeUjCrCfc4iZnDD",
+    "t2Uh9Au1PDDSw117sAbGivKREkmMxVC5tZo",
+    "t2FDwoJKLeEBMTy3oP7RLQ1Fihhvz49a3Bv",
+    "t2FY18mrgtb7QLeHA8ShnxLXuW8cNQ2n1v8",
+    "t2L15TkDYum7dnQRBqfvWdRe8Yw3jVy9z7g",
+];
+
+/// The funding stream a coinbase output of `value_zats` to `address` at
+/// `height` pays, if any; `None` for the miner's share.
+pub fn funding_stream(
+    network: &ChainParams,
+    height: u32,
+    value_zats: i64,
+    address: Option<&str>,
+) -> Option<&'static str> {
+    let address = address?;
+    let network = match network {
+        ChainParams::Standard(network) => *network,
+        ChainParams::Custom { .. } => return None,
+    };
+    let subsidy = block_subsidy(network, height)?;
+    STREAMS
+        .iter()
+        .filter(|stream| i64::try_from(subsidy * stream.percent / 100) == Ok(value_zats))
+        .find(|stream| recipient(network, stream, height) == Some(address))
+        .map(|stream| stream.name)
+}
+
+/// The address `stream` pays at `height`, if the stream is active then.
+fn recipient(network: Network, stream: &FundingStream, height: u32) -> Option<&'static str> {
+    let ((start, end), recipients) = match network {
+        Network::MainNetwork => (stream.mainnet, stream.mainnet_recipients),
+        Network::TestNetwork => (stream.testnet, stream.testnet_recipients),
+    };
+    if !(start..end).contains(&height) {
+        return None;
+    }
+    match recipients {
+        [address] => Some(address),
+        _ => {
+            let index = address_period(network, height) - address_period(network, start);
+            recipients.get(index as usize).copied()
+        }
+    }
+}
+
+/// Address period of `height`, counted so that the first halving starts
+/// period 48 (ZIP 214).
+fn address_period(network: Network, height: u32) -> u32 {
+    let first_halving = match network {
+        Network::MainNetwork => 1_046_400,
+        Network::TestNetwork => 1_116_000,
+    };
+    (height + POST_BLOSSOM_HALVING_INTERVAL - first_halving) / ADDRESS_CHANGE_INTERVAL
+}
+
+/// Block subsidy at `height` (ZIP 208), for heights from Blossom on; every
+/// funding stream starts later.
+fn block_subsidy(network: Network, height: u32) -> Option<u64> {
+    let blossom = match network {
+        Network::MainNetwork => 653_600,
+        Network::TestNetwork => 584_000,
+    };
+    let after_blossom = height.checked_sub(blossom)?;
+    // Pre-Blossom halving intervals were half as long
+    let halvings =
+        (2 * (blossom - SLOW_START_SHIFT) + after_blossom) / POST_BLOSSOM_HALVING_INTERVAL;
+    // Blossom halved the target spacing and with it the subsidy
+    MAX_BLOCK_SUBSIDY.checked_shr(halvings + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET: ChainParams = ChainParams::Standard(Network::MainNetwork);
+    const TESTNET: ChainParams = ChainParams::Standard(Network::TestNetwork);
+
+    #[test]
+    fn matches_value_and_period_recipient() {
+        let ecc = Some("t3LmX1cxWPPPqL4TZHx42HU3U5ghbFjRiif");
+        assert_eq!(
+            funding_stream(&MAINNET, 1_046_400, 21_875_000, ecc),
+            Some("electric_coin_company")
+        );
+        // The next address period pays the next address
+        assert_eq!(funding_stream(&MAINNET, 1_081_400, 21_875_000, ecc), None);
+        let next = Some("t3Toxk1vJQ6UjWQ42tUJz2rV2feUWkpbTDs");
+        assert_eq!(
+            funding_stream(&MAINNET, 1_081_400, 21_875_000, next),
+            Some("electric_coin_company")
+        );
+        let last = Some("t3XHAGxRP2FNfhAjxGjxbrQPYtQQjc3RCQD");
+        assert_eq!(
+            funding_stream(&MAINNET, 2_726_399, 21_875_000, last),
+            Some("electric_coin_company")
+        );
+        let testnet_ecc = Some("t26ovBdKAJLtrvBsE2QGF4nqBkEuptuPFZz");
+        assert_eq!(
+            funding_stream(&TESTNET, 1_028_500, 43_750_000, testnet_ecc),
+            Some("electric_coin_company")
+        );
+    }
+
+    #[test]
+    fn single_recipient_streams() {
+        let zf = Some("t3dvVE3SQEi7kqNzwrfNePxZ1d4hUyztBA1");
+        assert_eq!(
+            funding_stream(&MAINNET, 2_000_000, 15_625_000, zf),
+            Some("zcash_foundation")
+        );
+        let fpf = Some("t3cFfPt1Bcvgez9ZbMBFWeZsskxTkPzGCow");
+        assert_eq!(
+            funding_stream(&MAINNET, 2_726_400, 12_500_000, fpf),
+            Some("zcash_community_grants")
+        );
+        assert_eq!(funding_stream(&MAINNET, 2_726_399, 12_500_000, fpf), None);
+    }
+
+    #[test]
+    fn value_alone_is_not_enough() {
+        let miner = Some("t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs");
+        assert_eq!(funding_stream(&MAINNET, 1_046_400, 21_875_000, miner), None);
+        assert_eq!(funding_stream(&MAINNET, 1_046_400, 21_875_000, None), None);
+        let zf = Some("t3dvVE3SQEi7kqNzwrfNePxZ1d4hUyztBA1");
+        assert_eq!(funding_stream(&MAINNET, 1_046_400, 21_875_000, zf), None);
+        assert_eq!(funding_stream(&MAINNET, 1_000_000, 15_625_000, zf), None);
+    }
+}
@@ -0,0 +1,353 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use orchard::{
+    keys::{FullViewingKey, PreparedIncomingViewingKey as OrchardIvk},
+    note_encryption::{CompactAction, OrchardDomain},
+};
+use prost::Message;
+use sapling::{
+    note_encryption::{
+        CompactOutputDescription, PreparedIncomingViewingKey as SaplingIvk, SaplingDomain,
+        Zip212Enforcement,
+    },
+    NullifierDerivingKey,
+};
+use zcash_client_backend::{
+    keys::UnifiedFullViewingKey,
+    proto::compact_formats::{CompactBlock, CompactOrchardAction, CompactSaplingOutput},
+};
+use zcash_note_encryption::try_compact_note_decryption;
+use zcash_primitives::{
+    consensus::BlockHeight, transaction::components::sapling::zip212_enforcement, zip32::Scope,
+};
+
+use crate::{
+    decrypt::{KeyGeneration, TreePositions},
+    network::ChainParams,
+};
+
+/// A transaction in a compact block with at least one output that decrypts
+/// with one of the scanner's keys.
+#[derive(Debug, Clone)]
+pub struct CompactHit {
+    /// TXID in the usual (byte-reversed) display order, as the node RPC expects.
+    pub txid: String,
+    pub height: u32,
+    /// Where the transaction's outputs start in the commitment trees, if the
+    /// block carried chain metadata.
+    pub positions: Option<TreePositions>,
+}
+
+/// A note received by one of the scanner's keys, with the nullifier that will
+/// be revealed when it is spent.
+#[derive(Debug, Clone)]
+pub struct CompactNote {
+    pub txid: String,
+    pub height: u32,
+    pub account: u32,
+    pub protocol: &'static str,
+    pub value_zats: u64,
+    pub nullifier: [u8; 32],
+}
+
+struct SaplingKey {
+    generation: KeyGeneration,
+    ivk: SaplingIvk,
+    nk: NullifierDerivingKey,
+}
+
+struct OrchardKey {
+    generation: KeyGeneration,
+    ivk: OrchardIvk,
+    fvk: FullViewingKey,
+}
+
+/// Detects owned outputs from lightwalletd compact blocks (ZIP-307).
+///
+/// Only the 52-byte compact note plaintext is trial-decrypted, which is enough
+/// to tell whether an output belongs to a key but not to read its memo, so
+/// hits still have to be fetched in full and run through the `Decryptor`.
+pub struct CompactScanner {
+    network: ChainParams,
+    sapling_keys: Vec<SaplingKey>,
+    orchard_keys: Vec<OrchardKey>,
+}
+
+impl CompactScanner {
+    /// Prepare external and internal (change) IVKs for every key. Each of
+    /// `ufvks` is tried at the heights and reported under the account of the
+    /// generation at the same position, as in the `Decryptor`.
+    pub fn new(
+        network: ChainParams,
+        ufvks: &[UnifiedFullViewingKey],
+        generations: &[KeyGeneration],
+    ) -> Self {
+        let mut sapling_keys = Vec::new();
+        let mut orchard_keys = Vec::new();
+        for (ufvk, generation) in ufvks.iter().zip(generations) {
+            if let Some(dfvk) = ufvk.sapling() {
+                for scope in [Scope::External, Scope::Internal] {
+                    sapling_keys.push(SaplingKey {
+                        generation: generation.clone(),
+                        ivk: SaplingIvk::new(&dfvk.to_ivk(scope)),
+                        nk: dfvk.to_nk(scope),
+                    });
+                }
+            }
+            if let Some(fvk) = ufvk.orchard() {
+                for scope in [Scope::External, Scope::Internal] {
+                    orchard_keys.push(OrchardKey {
+                        generation: generation.clone(),
+                        ivk: OrchardIvk::new(&fvk.to_ivk(scope)),
+                        fvk: fvk.clone(),
+                    });
+                }
+            }
+        }
+
+        CompactScanner {
+            network,
+            sapling_keys,
+            orchard_keys,
+        }
+    }
+
+    /// Transactions in `block` with outputs for any of the scanner's keys.
+    /// Malformed compact outputs are skipped rather than failing the block.
+    pub fn scan_block(&self, block: &CompactBlock) -> Vec<CompactHit> {
+        let height = block.height();
+        let zip212 = zip212_enforcement(&self.network, height);
+
+        let mut positions = tree_starts(block).ok();
+        let mut hits = Vec::new();
+        for ctx in &block.vtx {
+            let hit = ctx
+                .outputs
+                .iter()
+                .any(|output| self.decrypt_sapling(height, zip212, output).is_some())
+                || ctx
+                    .actions
+                    .iter()
+                    .any(|action| self.decrypt_orchard(height, action).is_some());
+            if hit {
+                hits.push(CompactHit {
+                    txid: ctx.txid().to_string(),
+                    height: u32::from(height),
+                    positions,
+                });
+            }
+
+            if let Some(p) = positions.as_mut() {
+                p.sapling += ctx.outputs.len() as u64;
+                p.orchard += ctx.actions.len() as u64;
+            }
+        }
+        hits
+    }
+
+    /// Every note in `block` received by the scanner's keys, with nullifiers.
+    ///
+    /// Sapling nullifiers depend on the note's position in the commitment
+    /// tree, which is derived from the block's chain metadata, so blocks
+    /// without it are rejected.
+    pub fn scan_notes(&self, block: &CompactBlock) -> Result<Vec<CompactNote>> {
+        let height = block.height();
+        let zip212 = zip212_enforcement(&self.network, height);
+
+        let mut position = tree_starts(block)?.sapling;
+
+        let mut notes = Vec::new();
+        for ctx in &block.vtx {
+            let txid = ctx.txid().to_string();
+            for output in &ctx.outputs {
+                if let Some((key, note)) = self.decrypt_sapling(height, zip212, output) {
+                    notes.push(CompactNote {
+                        txid: txid.clone(),
+                        height: u32::from(height),
+                        account: key.generation.account,
+                        protocol: "Sapling",
+                        value_zats: note.value().inner(),
+                        nullifier: note.nf(&key.nk, position).0,
+                    });
+                }
+                position += 1;
+            }
+            for action in &ctx.actions {
+                if let Some((key, note)) = self.decrypt_orchard(height, action) {
+                    notes.push(CompactNote {
+                        txid: txid.clone(),
+                        height: u32::from(height),
+                        account: key.generation.account,
+                        protocol: "Orchard",
+                        value_zats: note.value().inner(),
+                        nullifier: note.nullifier(&key.fvk).to_bytes(),
+                    });
+                }
+            }
+        }
+        Ok(notes)
+    }
+
+    fn decrypt_sapling(
+        &self,
+        height: BlockHeight,
+        zip212: Zip212Enforcement,
+        output: &CompactSaplingOutput,
+    ) -> Option<(&SaplingKey, sapling::Note)> {
+        let output = CompactOutputDescription::try_from(output).ok()?;
+        let domain = SaplingDomain::new(zip212);
+        let height = u32::from(height);
+        self.sapling_keys
+            .iter()
+            .filter(|key| key.generation.covers(height))
+            .find_map(|key| {
+                try_compact_note_decryption(&domain, &key.ivk, &output).map(|(note, _)| (key, note))
+            })
+    }
+
+    fn decrypt_orchard(
+        &self,
+        height: BlockHeight,
+        action: &CompactOrchardAction,
+    ) -> Option<(&OrchardKey, orchard::Note)> {
+        let action = CompactAction::try_from(action).ok()?;
+        let domain = OrchardDomain::for_compact_action(&action);
+        let height = u32::from(height);
+        self.orchard_keys
+            .iter()
+            .filter(|key| key.generation.covers(height))
+            .find_map(|key| {
+                try_compact_note_decryption(&domain, &key.ivk, &action).map(|(note, _)| (key, note))
+            })
+    }
+}
+
+/// Commitment tree sizes before the first output of `block`, from the tree
+/// sizes its chain metadata records for the end of the block.
+fn tree_starts(block: &CompactBlock) -> Result<TreePositions> {
+    let metadata = block.chain_metadata.ok_or_else(|| {
+        anyhow!(
+            "Compact block {} has no chain metadata; note positions are unknown",
+            block.height
+        )
+    })?;
+    let (sapling, orchard) = block.vtx.iter().fold((0u64, 0u64), |(s, o), ctx| {
+        (s + ctx.outputs.len() as u64, o + ctx.actions.len() as u64)
+    });
+
+    let inconsistent = || {
+        anyhow!(
+            "Compact block {} has inconsistent chain metadata",
+            block.height
+        )
+    };
+    Ok(TreePositions {
+        sapling: u64::from(metadata.sapling_commitment_tree_size)
+            .checked_sub(sapling)
+            .ok_or_else(inconsistent)?,
+        orchard: u64::from(metadata.orchard_commitment_tree_size)
+            .checked_sub(orchard)
+            .ok_or_else(inconsistent)?,
+    })
+}
+
+/// Nullifiers revealed by spends in `block`, tagged with the spending TXID.
+pub fn spent_nullifiers(block: &CompactBlock) -> Vec<([u8; 32], String)> {
+    let mut spent = Vec::new();
+    for ctx in &block.vtx {
+        let nullifiers = ctx
+            .spends
+            .iter()
+            .map(|spend| &spend.nf)
+            .chain(ctx.actions.iter().map(|action| &action.nullifier));
+        for nf in nullifiers {
+            if let Ok(nf) = <[u8; 32]>::try_from(nf.as_slice()) {
+                spent.push((nf, ctx.txid().to_string()));
+            }
+        }
+    }
+    spent
+}
+
+/// Sapling outputs and Orchard actions across `blocks`.
+pub fn count_outputs(blocks: &[CompactBlock]) -> u64 {
+    blocks
+        .iter()
+        .flat_map(|block| &block.vtx)
+        .map(|ctx| (ctx.outputs.len() + ctx.actions.len()) as u64)
+        .sum()
+}
+
+/// Read a file of length-delimited `CompactBlock` protobuf messages, as
+/// streamed by lightwalletd's `GetBlockRange`.
+pub fn load_compact_blocks(path: &Path) -> Result<Vec<CompactBlock>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read compact blocks {}", path.display()))?;
+
+    let mut buf = data.as_slice();
+    let mut blocks = Vec::new();
+    while !buf.is_empty() {
+        let block = CompactBlock::decode_length_delimited(&mut buf).with_context(|| {
+            format!(
+                "Invalid compact block #{} in {}",
+                blocks.len(),
+                path.display()
+            )
+        })?;
+        // `CompactBlock::height` panics on heights that don't fit in a u32.
+        if u32::try_from(block.height).is_err() {
+            return Err(anyhow!(
+                "Compact block height {} is out of range",
+                block.height
+            ));
+        }
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_client_backend::proto::compact_formats::{ChainMetadata, CompactTx};
+
+    use super::*;
+
+    fn block(metadata: Option<(u32, u32)>, txs: &[(usize, usize)]) -> CompactBlock {
+        CompactBlock {
+            height: 2_000_000,
+            vtx: txs
+                .iter()
+                .map(|&(outputs, actions)| CompactTx {
+                    outputs: vec![CompactSaplingOutput::default(); outputs],
+                    actions: vec![CompactOrchardAction::default(); actions],
+                    ..CompactTx::default()
+                })
+                .collect(),
+            chain_metadata: metadata.map(|(sapling, orchard)| ChainMetadata {
+                sapling_commitment_tree_size: sapling,
+                orchard_commitment_tree_size: orchard,
+            }),
+            ..CompactBlock::default()
+        }
+    }
+
+    #[test]
+    fn tree_starts_subtract_the_block_outputs_from_its_end_sizes() {
+        let positions = tree_starts(&block(Some((100, 40)), &[(2, 0), (1, 3)])).unwrap();
+        assert_eq!(positions.sapling, 97);
+        assert_eq!(positions.orchard, 37);
+
+        let positions = tree_starts(&block(Some((5, 0)), &[])).unwrap();
+        assert_eq!((positions.sapling, positions.orchard), (5, 0));
+    }
+
+    #[test]
+    fn tree_starts_need_consistent_chain_metadata() {
+        let err = tree_starts(&block(None, &[(1, 0)])).unwrap_err();
+        assert!(err.to_string().contains("no chain metadata"));
+
+        let err = tree_starts(&block(Some((100, 2)), &[(0, 3)])).unwrap_err();
+        assert!(err.to_string().contains("inconsistent chain metadata"));
+    }
+}
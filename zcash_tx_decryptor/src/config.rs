@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::builder::OsStr;
+use serde::Deserialize;
+
+/// Settings read from `config.toml`, used as defaults for the matching CLI
+/// flags in every subcommand. Flags and `ZTD_*` environment variables take
+/// precedence, so keys and RPC credentials can stay out of shell history and
+/// process listings.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Default for --ufvk: one key or a list
+    ufvk: Option<OneOrMany>,
+    /// Default for --rpc-url: one endpoint or a list to fail over between
+    rpc_url: Option<OneOrMany>,
+    /// Default for --network
+    network: Option<String>,
+    /// Default for --network-params
+    network_params: Option<PathBuf>,
+    /// Default for --db
+    db: Option<PathBuf>,
+    /// Per-network settings, as `[networks.main]`, `[networks.test]` or
+    /// `[networks.regtest]`, for running one process against several networks
+    #[serde(default)]
+    networks: BTreeMap<String, NetworkSection>,
+}
+
+/// One `[networks.<name>]` section: the keys, node and database used for that
+/// network by `sync --networks` and `serve`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkSection {
+    ufvk: Option<OneOrMany>,
+    rpc_url: Option<OneOrMany>,
+    pub network_params: Option<PathBuf>,
+    pub db: Option<PathBuf>,
+    /// Height a new database starts syncing from
+    pub birthday: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn to_vec(values: &Option<OneOrMany>) -> Vec<String> {
+        match values {
+            Some(OneOrMany::One(value)) => vec![value.clone()],
+            Some(OneOrMany::Many(values)) => values.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file from `$ZTD_CONFIG`, else
+    /// `$XDG_CONFIG_HOME/zcash-tx-decryptor/config.toml`, else
+    /// `~/.config/zcash-tx-decryptor/config.toml`. A missing file at a default
+    /// location is not an error; a missing `$ZTD_CONFIG` is.
+    pub fn load() -> Result<Self> {
+        let (path, explicit) = match std::env::var_os("ZTD_CONFIG") {
+            Some(path) => (PathBuf::from(path), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        if !explicit && !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Install the configured values as defaults on `cmd` and all of its
+    /// subcommands, wherever an argument with the same name exists.
+    pub fn apply(&self, mut cmd: clap::Command) -> clap::Command {
+        let list = |values: &Option<OneOrMany>| -> Vec<OsStr> {
+            OneOrMany::to_vec(values).into_iter().map(Into::into).collect()
+        };
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| OsStr::from(p.display().to_string()));
+        let network_dbs = self
+            .networks
+            .iter()
+            .filter_map(|(name, section)| {
+                let db = section.db.as_ref()?;
+                Some(OsStr::from(format!("{}={}", name, db.display())))
+            })
+            .collect();
+        let defaults = [
+            ("ufvk", list(&self.ufvk)),
+            ("rpc_url", list(&self.rpc_url)),
+            ("network", self.network.iter().cloned().map(Into::into).collect()),
+            ("network_params", path(&self.network_params).into_iter().collect()),
+            ("db", path(&self.db).into_iter().collect()),
+            ("network_db", network_dbs),
+        ];
+
+        for (id, values) in defaults {
+            if values.is_empty() || !cmd.get_arguments().any(|arg| arg.get_id() == id) {
+                continue;
+            }
+            // A default satisfies the argument, so it no longer has to be
+            // passed explicitly.
+            cmd = cmd.mut_arg(id, |arg| arg.default_values(values).required(false));
+        }
+
+        let names: Vec<String> = cmd
+            .get_subcommands()
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in names {
+            cmd = cmd.mut_subcommand(name, |sub| self.apply(sub));
+        }
+        cmd
+    }
+
+    /// The `[networks.<name>]` section for `name`.
+    pub fn network(&self, name: &str) -> Result<&NetworkSection> {
+        self.networks.get(name).ok_or_else(|| {
+            anyhow!(
+                "No [networks.{}] section in the config file (has: {})",
+                name,
+                self.networks.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+}
+
+impl NetworkSection {
+    pub fn ufvks(&self) -> Vec<String> {
+        OneOrMany::to_vec(&self.ufvk)
+    }
+
+    pub fn rpc_urls(&self) -> Vec<String> {
+        OneOrMany::to_vec(&self.rpc_url)
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("zcash-tx-decryptor").join("config.toml"))
+}
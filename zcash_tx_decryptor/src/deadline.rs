@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+/// Point in time by which a request must finish; `Deadline::default()` never
+/// expires.
+///
+/// Work is checked between stages (fetch, parse, decrypt) rather than
+/// interrupted, so a single stage can overrun by its own duration.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// A deadline `timeout` from now, or none (also when `timeout` is too
+    /// far away for the clock to represent).
+    pub fn after(timeout: Option<Duration>) -> Self {
+        Deadline(timeout.and_then(|t| Instant::now().checked_add(t)))
+    }
+
+    pub fn expired(&self) -> bool {
+        self.0.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Time left before expiry, if there is a deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0.map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Fail if the deadline has passed before `stage` starts.
+    pub fn check(&self, stage: &str) -> Result<()> {
+        if self.expired() {
+            return Err(anyhow!("Deadline exceeded before {}", stage));
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `--deadline` value such as `30s`, `500ms` or `2m`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration '{}' (expected e.g. 30s)", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" | "" => Ok(Duration::from_secs(value)),
+        "m" | "min" => value
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration '{}' is too long", s)),
+        other => Err(format!("unknown duration unit '{}' (expected ms, s or m)", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_unit() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("2min"), Ok(Duration::from_secs(120)));
+        assert!(parse_duration("2h").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn overlong_durations_are_errors() {
+        let max = u64::MAX.to_string();
+        assert_eq!(parse_duration(&format!("{}s", max)), Ok(Duration::from_secs(u64::MAX)));
+        assert!(parse_duration(&format!("{}m", max)).is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX / 60 + 1)).is_err());
+        assert_eq!(
+            parse_duration(&format!("{}m", u64::MAX / 60)),
+            Ok(Duration::from_secs(u64::MAX / 60 * 60))
+        );
+        // Too many digits for a u64 at all
+        assert!(parse_duration("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn unreachable_deadlines_never_expire() {
+        let deadline = Deadline::after(Some(Duration::from_secs(u64::MAX)));
+        assert!(!deadline.expired());
+        assert!(Deadline::after(Some(Duration::ZERO)).expired());
+    }
+}
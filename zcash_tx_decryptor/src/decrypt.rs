@@ -0,0 +1,835 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, Context, Result};
+use blake2b_simd::Params;
+use chrono::Utc;
+use zcash_client_backend::{
+    address::{Address, UnifiedAddress},
+    data_api::DecryptedTransaction,
+    decrypt_transaction,
+    keys::UnifiedFullViewingKey,
+    TransferType,
+};
+use zcash_primitives::{
+    consensus::BlockHeight,
+    legacy::TransparentAddress,
+    transaction::Transaction,
+    zip32::{DiversifierIndex, Scope},
+};
+
+use crate::{
+    coinbase,
+    deadline::Deadline,
+    exit::ErrorKind,
+    models::*,
+    network::ChainParams,
+    parse::{self, BranchTable},
+    policy::OutputPolicy,
+    reference::MemoMatcher,
+    tags::AddressBook,
+};
+
+/// Size of each commitment tree before a transaction's first output, known
+/// when scanning blocks; lets decrypted outputs report their tree positions.
+#[derive(Debug, Clone, Copy)]
+pub struct TreePositions {
+    pub sapling: u64,
+    pub orchard: u64,
+}
+
+/// Per-call inputs to [`Decryptor::decrypt_with`] beyond the transaction.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DecryptOptions {
+    /// Where the transaction sits in the commitment trees, for a mined
+    /// transaction from a scanned block; gives outputs positions and Sapling
+    /// nullifiers.
+    pub positions: Option<TreePositions>,
+    /// Checked before parsing and before trial decryption.
+    pub deadline: Deadline,
+}
+
+/// How much of each decrypted output to report.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Detail {
+    /// Value, memo, addresses and note identifiers
+    #[default]
+    Standard,
+    /// Also the raw cryptographic fields of each output ([`OutputDetail`])
+    Full,
+}
+
+/// Identifies a viewing key without revealing it, as in audit attestations
+/// and idempotency keys.
+pub fn key_fingerprint(encoded_ufvk: &str) -> String {
+    Params::new()
+        .hash_length(32)
+        .personal(b"ZTxDecAuditUFVK_")
+        .hash(encoded_ufvk.as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+/// Parse a `--detail` value.
+pub fn parse_detail(s: &str) -> Result<Detail, String> {
+    match s {
+        "standard" => Ok(Detail::Standard),
+        "full" => Ok(Detail::Full),
+        other => Err(format!(
+            "unknown detail level '{}' (expected standard or full)",
+            other
+        )),
+    }
+}
+
+/// A transparent address whose outputs are reported alongside the shielded
+/// ones, e.g. a deposit address without a shielded key.
+#[derive(Debug, Clone)]
+pub struct WatchedAddress {
+    pub address: TransparentAddress,
+    /// Index on the external chain of the xpub it was derived from, if any
+    pub child_index: Option<u32>,
+}
+
+/// The account a viewing key reports under and the heights it is tried at.
+///
+/// After a key rotation an account has one generation per key, old and new,
+/// so outputs either key decrypts are reported under the same account.
+#[derive(Debug, Clone, Default)]
+pub struct KeyGeneration {
+    pub account: u32,
+    pub label: Option<String>,
+    /// First height the key is tried at
+    pub from_height: Option<u32>,
+    /// Last height the key is tried at
+    pub to_height: Option<u32>,
+}
+
+impl KeyGeneration {
+    /// Whether the key is tried for a transaction at `height`.
+    pub fn covers(&self, height: u32) -> bool {
+        self.from_height.is_none_or(|from| height >= from)
+            && self.to_height.is_none_or(|to| height <= to)
+    }
+}
+
+/// Everything needed to turn raw transaction bytes into a [`TxReport`]
+/// report for a fixed set of viewing keys.
+pub struct Decryptor {
+    network: ChainParams,
+    branch_table: BranchTable,
+    /// Keyed by position among the keys given, not by account
+    ufvks: HashMap<u32, UnifiedFullViewingKey>,
+    /// Account and validity of each key, by position
+    generations: Vec<KeyGeneration>,
+    labels: HashMap<u32, String>,
+    transparent: HashMap<TransparentAddress, WatchedAddress>,
+    tags: AddressBook,
+    memo_matcher: Option<MemoMatcher>,
+    /// Drop outputs without a payment reference
+    references_only: bool,
+    policy: OutputPolicy,
+    detail: Detail,
+    branch_id_override: Option<u32>,
+    verify_txid: bool,
+    trial_outputs: AtomicU64,
+}
+
+impl Decryptor {
+    /// `ufvks` are assigned account IDs in order, starting at 0, until
+    /// [`Decryptor::with_generations`] says otherwise.
+    pub fn new(
+        network: ChainParams,
+        ufvks: Vec<UnifiedFullViewingKey>,
+        branch_id_override: Option<u32>,
+        verify_txid: bool,
+    ) -> Self {
+        let generations = (0u32..)
+            .take(ufvks.len())
+            .map(|account| KeyGeneration {
+                account,
+                ..KeyGeneration::default()
+            })
+            .collect();
+        Decryptor {
+            branch_table: network.branch_table(),
+            network,
+            ufvks: (0u32..).zip(ufvks).collect(),
+            generations,
+            labels: HashMap::new(),
+            transparent: HashMap::new(),
+            tags: AddressBook::default(),
+            memo_matcher: None,
+            references_only: false,
+            policy: OutputPolicy::default(),
+            detail: Detail::Standard,
+            branch_id_override,
+            verify_txid,
+            trial_outputs: AtomicU64::new(0),
+        }
+    }
+
+    /// Assign the keys given to [`Decryptor::new`] to accounts, in the same
+    /// order. Labels are reported as `account_label`; an account's first
+    /// labelled key names it.
+    pub fn with_generations(mut self, generations: Vec<KeyGeneration>) -> Self {
+        self.labels = HashMap::new();
+        for generation in &generations {
+            if let Some(label) = &generation.label {
+                self.labels
+                    .entry(generation.account)
+                    .or_insert_with(|| label.clone());
+            }
+        }
+        self.generations = generations;
+        self
+    }
+
+    /// Also report transparent outputs paying `watched`, as received by one
+    /// more account numbered after the viewing keys.
+    pub fn with_transparent(mut self, watched: Vec<WatchedAddress>) -> Self {
+        self.transparent = watched
+            .into_iter()
+            .map(|watched| (watched.address, watched))
+            .collect();
+        self
+    }
+
+    /// Report each output's `counterparty` from `tags`.
+    pub fn with_tags(mut self, tags: AddressBook) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Report each output's `payment_reference` as found in its memo by
+    /// `matcher`. With `references_only`, outputs without one are left out
+    /// of reports altogether.
+    pub fn with_memo_matcher(
+        mut self,
+        matcher: Option<MemoMatcher>,
+        references_only: bool,
+    ) -> Self {
+        self.memo_matcher = matcher;
+        self.references_only = references_only;
+        self
+    }
+
+    /// Leave dust out of reports as `policy` says. Confirmations aren't known
+    /// here; callers that learn them apply
+    /// [`OutputPolicy::filter_unconfirmed`] themselves.
+    pub fn with_policy(mut self, policy: OutputPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Report outputs at `detail`.
+    pub fn with_detail(mut self, detail: Detail) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    /// Network the keys are for.
+    pub fn network(&self) -> ChainParams {
+        self.network
+    }
+
+    /// Account ID that watched transparent addresses are reported under.
+    pub fn transparent_account(&self) -> u32 {
+        self.generations
+            .iter()
+            .map(|generation| generation.account + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Names `account` in idempotency keys: its label, else the fingerprint of
+    /// its first viewing key, so reordering or adding keys leaves the keys of
+    /// outputs already seen alone. Watched transparent addresses have no
+    /// viewing key and are named `transparent`.
+    fn account_id(&self, account: u32) -> String {
+        if let Some(label) = self.labels.get(&account) {
+            return label.clone();
+        }
+        (0..self.ufvks.len() as u32)
+            .filter(|&key| self.account_of(key) == account)
+            .find_map(|key| self.ufvks.get(&key))
+            .map_or_else(
+                || "transparent".to_string(),
+                |ufvk| key_fingerprint(&ufvk.encode(&self.network)),
+            )
+    }
+
+    /// The account the key at `key` (its position) reports under.
+    fn account_of(&self, key: u32) -> u32 {
+        self.generations
+            .get(key as usize)
+            .map_or(key, |generation| generation.account)
+    }
+
+    /// The keys to try at `height`: all of them unless a generation is
+    /// limited to other heights.
+    fn keys_at(&self, height: u32) -> Cow<'_, HashMap<u32, UnifiedFullViewingKey>> {
+        let covers = |key: u32| {
+            self.generations
+                .get(key as usize)
+                .is_none_or(|generation| generation.covers(height))
+        };
+        if self.ufvks.keys().all(|&key| covers(key)) {
+            return Cow::Borrowed(&self.ufvks);
+        }
+        Cow::Owned(
+            self.ufvks
+                .iter()
+                .filter(|&(&key, _)| covers(key))
+                .map(|(&key, ufvk)| (key, ufvk.clone()))
+                .collect(),
+        )
+    }
+
+    /// What the viewing keys can decrypt, by account; an account with
+    /// several generations can decrypt what any of its keys can.
+    pub fn key_capabilities(&self) -> KeyCapabilities {
+        let mut components: HashMap<u32, Vec<String>> = HashMap::new();
+        for (&key, ufvk) in &self.ufvks {
+            components
+                .entry(self.account_of(key))
+                .or_default()
+                .extend(key_components(ufvk));
+        }
+        let mut accounts: Vec<AccountKey> = components
+            .into_iter()
+            .map(|(account, found)| AccountKey {
+                account,
+                account_label: self.labels.get(&account).cloned(),
+                components: COMPONENTS
+                    .into_iter()
+                    .filter(|component| found.iter().any(|c| c == component))
+                    .map(str::to_string)
+                    .collect(),
+            })
+            .collect();
+        accounts.sort_by_key(|key| key.account);
+        let viewable_pools = ["Orchard", "Sapling"]
+            .into_iter()
+            .filter(|pool| accounts.iter().any(|key| key.components.iter().any(|c| c == pool)))
+            .map(str::to_string)
+            .collect();
+        KeyCapabilities {
+            viewable_pools,
+            accounts,
+        }
+    }
+
+    /// Sapling outputs and Orchard actions trial-decrypted so far, across all
+    /// threads sharing this decryptor.
+    pub fn trial_outputs(&self) -> u64 {
+        self.trial_outputs.load(Ordering::Relaxed)
+    }
+
+    /// Parse, verify and decrypt a single raw transaction.
+    ///
+    /// `expected_txid` is checked against the TXID computed from `tx_bytes`; when
+    /// absent (e.g. binary mempool dumps) the computed TXID is reported instead.
+    pub fn decrypt(
+        &self,
+        height: BlockHeight,
+        expected_txid: Option<&str>,
+        tx_bytes: &[u8],
+    ) -> Result<TxReport> {
+        self.decrypt_with(height, expected_txid, tx_bytes, DecryptOptions::default())
+    }
+
+    /// As [`Decryptor::decrypt`], with tree positions and/or a deadline.
+    pub fn decrypt_with(
+        &self,
+        height: BlockHeight,
+        expected_txid: Option<&str>,
+        tx_bytes: &[u8],
+        options: DecryptOptions,
+    ) -> Result<TxReport> {
+        let _span =
+            tracing::debug_span!("decrypt", height = u32::from(height), txid = expected_txid)
+                .entered();
+        options.deadline.check("parsing")?;
+
+        // Parse transaction using correct consensus branch ID for the given height
+        let parsed = parse::parse_transaction(
+            tx_bytes,
+            &self.network,
+            height,
+            &self.branch_table,
+            self.branch_id_override,
+        )
+        .context(ErrorKind::Parse)?;
+        tracing::debug!(
+            txid = %parsed.txid,
+            version = ?parsed.tx.version(),
+            bytes = tx_bytes.len(),
+            "parsed transaction"
+        );
+        let tx = parsed.tx;
+        let tx_size_bytes = tx_bytes.len();
+
+        // Verify the TXID against the parsed transaction
+        let computed_txid = parsed.txid.to_string();
+        let mut warnings = Vec::new();
+        let txid = match expected_txid {
+            Some(txid) => {
+                // Validate TXID format
+                if txid.len() != 64 {
+                    return Err(anyhow!("TXID must be 64 hex characters, got {}", txid.len()));
+                }
+                hex::decode(txid).context("TXID is not valid hex")?;
+
+                if !computed_txid.eq_ignore_ascii_case(txid) {
+                    let msg = format!(
+                        "TXID mismatch: --txid is {} but the raw transaction hashes to {}",
+                        txid, computed_txid
+                    );
+                    if self.verify_txid {
+                        return Err(anyhow!("{} (pass --no-verify-txid to ignore)", msg));
+                    }
+                    tracing::warn!("{}", msg);
+                    warnings.push(Diagnostic {
+                        code: "txid_mismatch".to_string(),
+                        message: msg,
+                    });
+                }
+                txid.to_string()
+            }
+            None => computed_txid,
+        };
+
+        // Perform real decryption using librustzcash
+        options.deadline.check("trial decryption")?;
+        let keys = self.keys_at(u32::from(height));
+        let decrypted = decrypt_transaction(&self.network, height, &tx, &keys);
+        let shielded_outputs = tx.sapling_bundle().map_or(0, |b| b.shielded_outputs().len())
+            + tx.orchard_bundle().map_or(0, |b| b.actions().len());
+        self.trial_outputs
+            .fetch_add(shielded_outputs as u64, Ordering::Relaxed);
+
+        // Convert decrypted data into our human‑readable model
+        let mut report = build_report(
+            self,
+            &txid,
+            height,
+            tx_size_bytes,
+            &tx,
+            &decrypted,
+            options.positions,
+        )?;
+        report.warnings.extend(warnings);
+        tracing::debug!(
+            trial_outputs = shielded_outputs,
+            decrypted = report.outputs.len(),
+            "decrypted transaction"
+        );
+        Ok(report)
+    }
+}
+
+/// Collect the outputs of a decrypted transaction into a [`TxReport`].
+fn build_report(
+    decryptor: &Decryptor,
+    txid: &str,
+    height: BlockHeight,
+    tx_size_bytes: usize,
+    tx: &Transaction,
+    decrypted: &DecryptedTransaction<'_, u32>,
+    positions: Option<TreePositions>,
+) -> Result<TxReport> {
+    let (network, ufvks) = (&decryptor.network, &decryptor.ufvks);
+
+    // Collect outputs belonging to this UFVK
+    let mut outputs: Vec<OutputInfo> = Vec::new();
+
+    // Helper to classify transfer type
+    fn classify_transfer(t: &TransferType) -> (&'static str, &'static str) {
+        match t {
+            TransferType::Incoming => ("Incoming", "received"),
+            TransferType::WalletInternal => ("WalletInternal", "change"),
+            TransferType::Outgoing => ("Outgoing", "sent"),
+        }
+    }
+
+    // Sapling outputs
+    for out in decrypted.sapling_outputs() {
+        let value = u64::from(out.note_value());
+        let memo = String::from_utf8_lossy(out.memo().as_slice()).to_string();
+        let (tt_raw, direction) = classify_transfer(&out.transfer_type());
+
+        // Our own receiving address, or for outputs we sent the recipient's,
+        // which the OVK recovers along with the note
+        let encoded = Some(Address::Sapling(out.note().recipient()).encode(network));
+        let (address, recipient_address) = match out.transfer_type() {
+            TransferType::Outgoing => (None, encoded),
+            _ => (encoded, None),
+        };
+
+        let description = tx
+            .sapling_bundle()
+            .and_then(|bundle| bundle.shielded_outputs().get(out.index()));
+        let note_commitment = description.map(|output| hex::encode(output.cmu().to_bytes()));
+        let detail = description
+            .filter(|_| decryptor.detail == Detail::Full)
+            .map(|output| sapling_detail(out.index(), output, out.note()));
+        let position = positions.map(|p| p.sapling + out.index() as u64);
+
+        // The nullifier key depends on the scope the note was received on
+        let scope = match out.transfer_type() {
+            TransferType::Incoming => Some(Scope::External),
+            TransferType::WalletInternal => Some(Scope::Internal),
+            TransferType::Outgoing => None,
+        };
+        let dfvk = ufvks.get(out.account()).and_then(|ufvk| ufvk.sapling());
+        let nullifier = scope.zip(position).zip(dfvk).map(|((scope, position), dfvk)| {
+            hex::encode(out.note().nf(&dfvk.to_nk(scope), position).0)
+        });
+        let diversifier_index = scope
+            .and(dfvk)
+            .and_then(|dfvk| dfvk.decrypt_diversifier(&out.note().recipient()))
+            .and_then(|(index, _)| index_to_u64(&index));
+
+        let account = decryptor.account_of(*out.account());
+        outputs.push(OutputInfo {
+            protocol: "Sapling".to_string(),
+            amount_zats: value as i64,
+            index: out.index(),
+            account,
+            idempotency_key: String::new(),
+            account_label: decryptor.labels.get(&account).cloned(),
+            counterparty: None,
+            payment_reference: None,
+            transfer_type: tt_raw.to_string(),
+            direction: direction.to_string(),
+            source: None,
+            funding_stream: None,
+            memo,
+            address,
+            recipient_address,
+            diversifier_index,
+            note_commitment,
+            position,
+            nullifier,
+            detail,
+        });
+    }
+
+    // Orchard outputs
+    for out in decrypted.orchard_outputs() {
+        let value = u64::from(out.note_value());
+        let memo = String::from_utf8_lossy(out.memo().as_slice()).to_string();
+        let (tt_raw, direction) = classify_transfer(&out.transfer_type());
+
+        // Orchard receivers only have a unified encoding
+        let encoded = UnifiedAddress::from_receivers(Some(out.note().recipient()), None, None)
+            .map(|ua| ua.encode(network));
+        let (address, recipient_address) = match out.transfer_type() {
+            TransferType::Outgoing => (None, encoded),
+            _ => (encoded, None),
+        };
+
+        let action = tx
+            .orchard_bundle()
+            .and_then(|bundle| bundle.actions().get(out.index()));
+        let note_commitment = action.map(|action| hex::encode(action.cmx().to_bytes()));
+        let detail = action
+            .filter(|_| decryptor.detail == Detail::Full)
+            .map(|action| orchard_detail(out.index(), action, out.note()));
+        let position = positions.map(|p| p.orchard + out.index() as u64);
+
+        // Only our own notes have a nullifier and diversifier index we can derive
+        let scope = match out.transfer_type() {
+            TransferType::Incoming => Some(Scope::External),
+            TransferType::WalletInternal => Some(Scope::Internal),
+            TransferType::Outgoing => None,
+        };
+        let fvk = scope.and(ufvks.get(out.account()).and_then(|ufvk| ufvk.orchard()));
+        let nullifier = fvk.map(|fvk| hex::encode(out.note().nullifier(fvk).to_bytes()));
+        let diversifier_index = scope
+            .zip(fvk)
+            .and_then(|(scope, fvk)| fvk.to_ivk(scope).diversifier_index(&out.note().recipient()))
+            .and_then(|index| index_to_u64(&index));
+
+        let account = decryptor.account_of(*out.account());
+        outputs.push(OutputInfo {
+            protocol: "Orchard".to_string(),
+            amount_zats: value as i64,
+            index: out.index(),
+            account,
+            idempotency_key: String::new(),
+            account_label: decryptor.labels.get(&account).cloned(),
+            counterparty: None,
+            payment_reference: None,
+            transfer_type: tt_raw.to_string(),
+            direction: direction.to_string(),
+            source: None,
+            funding_stream: None,
+            memo,
+            address,
+            recipient_address,
+            diversifier_index,
+            note_commitment,
+            position,
+            nullifier,
+            detail,
+        });
+    }
+
+    // Transparent outputs are public; match their scripts against the
+    // watched addresses
+    let transparent_outputs = tx.transparent_bundle().into_iter().flat_map(|b| &b.vout);
+    for (index, out) in transparent_outputs.enumerate() {
+        let Some(watched) = out
+            .recipient_address()
+            .and_then(|addr| decryptor.transparent.get(&addr))
+        else {
+            continue;
+        };
+        let account = decryptor.transparent_account();
+        outputs.push(OutputInfo {
+            protocol: "Transparent".to_string(),
+            amount_zats: u64::from(out.value) as i64,
+            index,
+            account,
+            idempotency_key: String::new(),
+            account_label: decryptor.labels.get(&account).cloned(),
+            counterparty: None,
+            payment_reference: None,
+            transfer_type: "Incoming".to_string(),
+            direction: "received".to_string(),
+            source: None,
+            funding_stream: None,
+            memo: String::new(),
+            address: Some(Address::Transparent(watched.address).encode(network)),
+            recipient_address: None,
+            diversifier_index: watched.child_index.map(u64::from),
+            note_commitment: None,
+            position: None,
+            nullifier: None,
+            detail: None,
+        });
+    }
+
+    // Sprout notes can't be decrypted with a UFVK; list the JoinSplits so a
+    // historical transaction doesn't look empty
+    let opaque = tx
+        .sprout_bundle()
+        .into_iter()
+        .flat_map(|bundle| bundle.joinsplits.iter().enumerate())
+        .map(|(index, joinsplit)| OpaqueComponent {
+            protocol: "Sprout".to_string(),
+            kind: "JoinSplit".to_string(),
+            index,
+            net_value_zats: i64::from(joinsplit.net_value()),
+        })
+        .collect();
+
+    let sapling = tx.sapling_bundle();
+    let orchard = tx.orchard_bundle();
+    let mut sapling_anchors: Vec<String> = Vec::new();
+    for spend in sapling.iter().flat_map(|bundle| bundle.shielded_spends()) {
+        let anchor = hex::encode(spend.anchor().to_bytes());
+        if !sapling_anchors.contains(&anchor) {
+            sapling_anchors.push(anchor);
+        }
+    }
+    let spent_nullifiers = sapling
+        .iter()
+        .flat_map(|bundle| bundle.shielded_spends())
+        .map(|spend| spend.nullifier().0)
+        .chain(
+            orchard
+                .iter()
+                .flat_map(|bundle| bundle.actions())
+                .map(|action| action.nullifier().to_bytes()),
+        )
+        .collect();
+    let bundles = BundleStats {
+        transparent_inputs: tx.transparent_bundle().map_or(0, |bundle| bundle.vin.len()),
+        transparent_outputs: tx.transparent_bundle().map_or(0, |bundle| bundle.vout.len()),
+        sapling_spends: sapling.map_or(0, |bundle| bundle.shielded_spends().len()),
+        sapling_outputs: sapling.map_or(0, |bundle| bundle.shielded_outputs().len()),
+        sapling_value_balance_zats: sapling.map_or(0, |bundle| i64::from(*bundle.value_balance())),
+        sapling_anchors,
+        orchard_actions: orchard.map_or(0, |bundle| bundle.actions().len()),
+        orchard_value_balance_zats: orchard.map_or(0, |bundle| i64::from(*bundle.value_balance())),
+        orchard_anchor: orchard.map(|bundle| hex::encode(bundle.anchor().to_bytes())),
+    };
+    let transparent_output_zats = tx
+        .transparent_bundle()
+        .into_iter()
+        .flat_map(|bundle| &bundle.vout)
+        .map(|out| u64::from(out.value) as i64)
+        .sum();
+
+    // Mining rewards and funding streams aren't payments to us
+    let coinbase = tx.transparent_bundle().is_some_and(|bundle| bundle.is_coinbase());
+    for out in &mut outputs {
+        if coinbase {
+            out.source = Some("coinbase".to_string());
+            out.funding_stream = coinbase::funding_stream(
+                network,
+                u32::from(height),
+                out.amount_zats,
+                out.address.as_deref(),
+            )
+            .map(str::to_string);
+        }
+        let address = out.recipient_address.as_ref().or(out.address.as_ref());
+        out.counterparty = decryptor.tags.counterparty(address.map(String::as_str), &out.memo);
+        if let Some(matcher) = &decryptor.memo_matcher {
+            out.payment_reference = matcher.extract(&out.memo);
+        }
+        out.idempotency_key = idempotency_key(
+            txid,
+            &out.protocol,
+            out.index,
+            &decryptor.account_id(out.account),
+        );
+    }
+    if decryptor.references_only {
+        outputs.retain(|out| out.payment_reference.is_some());
+    }
+    let capabilities = decryptor.key_capabilities();
+    let warnings = unviewable_pools(&capabilities, &bundles);
+
+    let mut report = TxReport {
+        txid: txid.to_string(),
+        height: u32::from(height),
+        tx_size_bytes,
+        tx_version: tx.version().header() & 0x7FFF_FFFF,
+        decrypted_at: Utc::now(),
+        block_time: None,
+        block_hash: None,
+        network: Some(network.name()),
+        confirmations: None,
+        is_final: None,
+        outputs,
+        opaque,
+        bundles,
+        transparent_output_zats,
+        spent_nullifiers,
+        fiat: None,
+        filtered: None,
+        key_capabilities: Some(capabilities),
+        warnings,
+    };
+    decryptor.policy.filter_dust(&mut report);
+    Ok(report)
+}
+
+/// Every key component, in report order.
+const COMPONENTS: [&str; 3] = ["Orchard", "Sapling", "Transparent"];
+
+/// Protocols `ufvk` has a component for, in the order `check-key` lists them.
+/// The transparent component is only readable with the `cli` feature.
+fn key_components(ufvk: &UnifiedFullViewingKey) -> Vec<String> {
+    [
+        ("Orchard", ufvk.orchard().is_some()),
+        ("Sapling", ufvk.sapling().is_some()),
+        #[cfg(feature = "cli")]
+        ("Transparent", ufvk.transparent().is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, present)| *present)
+    .map(|(protocol, _)| protocol.to_string())
+    .collect()
+}
+
+/// A `pool_not_viewable` warning for each shielded pool the transaction has
+/// outputs in that some account's key has no component for. Without it, a
+/// Sapling-only key looks at an Orchard payment and reports nothing at all.
+fn unviewable_pools(capabilities: &KeyCapabilities, bundles: &BundleStats) -> Vec<Diagnostic> {
+    let pools = [
+        ("Orchard", bundles.orchard_actions, "action"),
+        ("Sapling", bundles.sapling_outputs, "output"),
+    ];
+    pools
+        .into_iter()
+        .filter(|(_, count, _)| *count > 0)
+        .filter_map(|(pool, count, kind)| {
+            let blind: Vec<String> = capabilities
+                .accounts
+                .iter()
+                .filter(|key| !key.components.iter().any(|c| c == pool))
+                .map(|key| key.account.to_string())
+                .collect();
+            if blind.is_empty() {
+                return None;
+            }
+            let reason = if blind.len() == capabilities.accounts.len() {
+                format!("no key includes {}", pool)
+            } else {
+                format!("the keys of account(s) {} don't include {}", blind.join(", "), pool)
+            };
+            Some(Diagnostic {
+                code: "pool_not_viewable".to_string(),
+                message: format!("{} {} {}(s) not viewable: {}", count, pool, kind, reason),
+            })
+        })
+        .collect()
+}
+
+fn sapling_detail(
+    index: usize,
+    output: &sapling::bundle::OutputDescription<sapling::bundle::GrothProofBytes>,
+    note: &sapling::Note,
+) -> OutputDetail {
+    let (rseed, zip212) = match note.rseed() {
+        sapling::Rseed::BeforeZip212(rcm) => (rcm.to_bytes(), false),
+        sapling::Rseed::AfterZip212(rseed) => (*rseed, true),
+    };
+    OutputDetail {
+        index_kind: "sapling_output".to_string(),
+        index,
+        cv: hex::encode(output.cv().to_bytes()),
+        cmu: Some(hex::encode(output.cmu().to_bytes())),
+        cmx: None,
+        ephemeral_key: hex::encode(output.ephemeral_key().as_ref()),
+        enc_ciphertext_len: output.enc_ciphertext().len(),
+        out_ciphertext_len: output.out_ciphertext().len(),
+        diversifier: hex::encode(note.recipient().diversifier().0),
+        rseed: hex::encode(rseed),
+        zip212,
+        rcm: Some(hex::encode(note.rcm().to_bytes())),
+        rho: None,
+    }
+}
+
+fn orchard_detail<A>(
+    index: usize,
+    action: &orchard::Action<A>,
+    note: &orchard::Note,
+) -> OutputDetail {
+    let ciphertext = action.encrypted_note();
+    OutputDetail {
+        index_kind: "orchard_action".to_string(),
+        index,
+        cv: hex::encode(action.cv_net().to_bytes()),
+        cmu: None,
+        cmx: Some(hex::encode(action.cmx().to_bytes())),
+        ephemeral_key: hex::encode(ciphertext.epk_bytes),
+        enc_ciphertext_len: ciphertext.enc_ciphertext.len(),
+        out_ciphertext_len: ciphertext.out_ciphertext.len(),
+        diversifier: hex::encode(note.recipient().diversifier().as_array()),
+        rseed: hex::encode(note.rseed().as_bytes()),
+        zip212: true,
+        rcm: None,
+        rho: Some(hex::encode(note.rho().to_bytes())),
+    }
+}
+
+/// A ZIP-32 diversifier index (88 bits, little-endian) as an integer, if it
+/// fits in 64 bits. Sequentially issued addresses always do.
+pub fn index_to_u64(index: &DiversifierIndex) -> Option<u64> {
+    let (low, high) = index.as_bytes().split_at(8);
+    if high.iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(low.try_into().ok()?))
+}
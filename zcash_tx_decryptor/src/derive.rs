@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use zcash_client_backend::keys::{Era, UnifiedFullViewingKey, UnifiedSpendingKey};
+use zcash_primitives::zip32::AccountId;
+use zcash_protocol::consensus::NetworkConstants;
+
+use crate::network::ChainParams;
+
+/// Derive the UFVK of ZIP-32 account `account` from a BIP-39 mnemonic and
+/// optional passphrase.
+pub fn ufvk_from_mnemonic(
+    network: &ChainParams,
+    phrase: &str,
+    passphrase: &str,
+    account: u32,
+) -> Result<UnifiedFullViewingKey> {
+    // Normalise whitespace so phrases pasted across lines still parse
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mnemonic = bip0039::Mnemonic::<bip0039::English>::from_phrase(phrase)
+        .map_err(|e| anyhow!("Invalid BIP-39 mnemonic: {}", e))?;
+    let seed = mnemonic.to_seed(passphrase);
+
+    let account = AccountId::try_from(account)
+        .map_err(|_| anyhow!("Account index {} is out of range", account))?;
+    let usk = UnifiedSpendingKey::from_seed(network, &seed, account)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(usk.to_unified_full_viewing_key())
+}
+
+/// UFVK of a Unified Spending Key in its binary wallet encoding (hex).
+pub fn ufvk_from_usk(usk_hex: &str) -> Result<UnifiedFullViewingKey> {
+    let bytes = hex::decode(usk_hex.trim())
+        .map_err(|_| anyhow!("Unified Spending Key must be hex-encoded"))?;
+    let usk = UnifiedSpendingKey::from_bytes(Era::Orchard, &bytes)
+        .map_err(|e| anyhow!("Invalid Unified Spending Key: {}", e))?;
+    Ok(usk.to_unified_full_viewing_key())
+}
+
+/// Account index of a ZIP-32 path such as `m/32'/133'/0'`. Only the account
+/// level of the Zcash purpose is supported, and the coin type must match
+/// `network`.
+pub fn account_from_path(network: &ChainParams, path: &str) -> Result<u32> {
+    let hardened = |part: &str| {
+        part.strip_suffix('\'')
+            .or_else(|| part.strip_suffix('h'))
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    let parts: Vec<&str> = path.trim().split('/').collect();
+    let (purpose, coin_type, account) = match parts.as_slice() {
+        ["m", purpose, coin_type, account] => {
+            (hardened(purpose), hardened(coin_type), hardened(account))
+        }
+        _ => (None, None, None),
+    };
+
+    match (purpose, coin_type, account) {
+        (Some(32), Some(coin_type), Some(account)) if coin_type == network.coin_type() => {
+            Ok(account)
+        }
+        (Some(32), Some(coin_type), Some(_)) => Err(anyhow!(
+            "ZIP-32 path {} has coin type {}, but this network uses {}",
+            path,
+            coin_type,
+            network.coin_type()
+        )),
+        _ => Err(anyhow!(
+            "Unsupported ZIP-32 path {} (expected m/32'/<coin_type>'/<account>')",
+            path
+        )),
+    }
+}
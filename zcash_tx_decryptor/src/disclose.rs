@@ -0,0 +1,266 @@
+//! Payment disclosures: one decrypted note, exported with its randomness seed
+//! so a third party holding the transaction can check its value, recipient
+//! and memo without the viewing key.
+//!
+//! The note commitment binds value, recipient and rseed, so recomputing it
+//! and finding it in the transaction proves the first two. Since ZIP 212 the
+//! ephemeral secret key derives from rseed too, which lets the verifier
+//! decrypt the output's ciphertext and compare the memo.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use orchard::{
+    note::{ExtractedNoteCommitment, RandomSeed, Rho},
+    note_encryption::OrchardDomain,
+    value::NoteValue as OrchardValue,
+};
+use sapling::{note_encryption::SaplingDomain, value::NoteValue as SaplingValue, Rseed};
+use zcash_client_backend::{
+    address::{Address, UnifiedAddress},
+    decrypt_transaction,
+    keys::UnifiedFullViewingKey,
+};
+use zcash_note_encryption::{try_output_recovery_with_pkd_esk, Domain};
+use zcash_primitives::{
+    consensus::{BlockHeight, Parameters},
+    transaction::{components::sapling::zip212_enforcement, Transaction},
+};
+
+use crate::{
+    models::PaymentDisclosure,
+    network::{self, ChainParams},
+};
+
+/// Current [`PaymentDisclosure::version`].
+const DISCLOSURE_VERSION: u32 = 1;
+
+/// Export output `index` of `protocol` ("Sapling" or "Orchard") from `tx`,
+/// which must decrypt with one of `ufvks`. Outputs we sent, recovered with
+/// the OVK, can be disclosed as well as ones we received.
+pub fn disclose(
+    network: &ChainParams,
+    height: BlockHeight,
+    tx: &Transaction,
+    txid: &str,
+    ufvks: &[UnifiedFullViewingKey],
+    protocol: &str,
+    index: usize,
+) -> Result<PaymentDisclosure> {
+    let keys: HashMap<u32, UnifiedFullViewingKey> = (0u32..).zip(ufvks.iter().cloned()).collect();
+    let decrypted = decrypt_transaction(network, height, tx, &keys);
+    let not_found = || {
+        anyhow!(
+            "{} output {} of {} did not decrypt with the given keys",
+            protocol,
+            index,
+            txid
+        )
+    };
+
+    let (value_zats, recipient, rseed, memo) = match protocol {
+        "Sapling" => {
+            let out = decrypted
+                .sapling_outputs()
+                .iter()
+                .find(|out| out.index() == index)
+                .ok_or_else(not_found)?;
+            let rseed = match out.note().rseed() {
+                Rseed::AfterZip212(rseed) => *rseed,
+                Rseed::BeforeZip212(_) => {
+                    return Err(anyhow!(
+                        "Notes created before ZIP 212 (Canopy) can't be disclosed"
+                    ))
+                }
+            };
+            let recipient = Address::Sapling(out.note().recipient()).encode(network);
+            (
+                out.note().value().inner(),
+                recipient,
+                rseed,
+                out.memo().as_slice().to_vec(),
+            )
+        }
+        "Orchard" => {
+            let out = decrypted
+                .orchard_outputs()
+                .iter()
+                .find(|out| out.index() == index)
+                .ok_or_else(not_found)?;
+            let recipient =
+                UnifiedAddress::from_receivers(Some(out.note().recipient()), None, None)
+                    .ok_or_else(|| anyhow!("Orchard receiver has no unified encoding"))?
+                    .encode(network);
+            (
+                out.note().value().inner(),
+                recipient,
+                *out.note().rseed().as_bytes(),
+                out.memo().as_slice().to_vec(),
+            )
+        }
+        other => {
+            return Err(anyhow!(
+                "Unknown protocol: {} (expected Sapling or Orchard)",
+                other
+            ))
+        }
+    };
+
+    Ok(PaymentDisclosure {
+        version: DISCLOSURE_VERSION,
+        network: network::network_name(network.network_type()).to_string(),
+        transaction_id: txid.to_string(),
+        block_height: u32::from(height),
+        protocol: protocol.to_string(),
+        index,
+        value_zats,
+        recipient,
+        rseed: hex::encode(rseed),
+        memo: hex::encode(trim_memo(&memo)),
+    })
+}
+
+/// Check `disclosure` against `tx` (whose TXID is `txid`): the output exists,
+/// its commitment matches the disclosed note, and its ciphertext decrypts to
+/// the disclosed memo. Fails with the first mismatch.
+pub fn verify(
+    network: &ChainParams,
+    tx: &Transaction,
+    txid: &str,
+    disclosure: &PaymentDisclosure,
+) -> Result<()> {
+    if disclosure.version != DISCLOSURE_VERSION {
+        return Err(anyhow!(
+            "Unsupported disclosure version {}",
+            disclosure.version
+        ));
+    }
+    if !disclosure.transaction_id.eq_ignore_ascii_case(txid) {
+        return Err(anyhow!(
+            "Disclosure is for transaction {}, but the raw transaction hashes to {}",
+            disclosure.transaction_id,
+            txid
+        ));
+    }
+    let rseed: [u8; 32] = hex::decode(&disclosure.rseed)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("rseed must be 32 bytes of hex"))?;
+    let memo = hex::decode(&disclosure.memo).map_err(|_| anyhow!("memo must be hex"))?;
+    let recipient = crate::address::decode(network, &disclosure.recipient)?;
+    let missing = || {
+        anyhow!(
+            "Transaction has no {} output {}",
+            disclosure.protocol,
+            disclosure.index
+        )
+    };
+
+    let recovered_memo = match disclosure.protocol.as_str() {
+        "Sapling" => {
+            let Address::Sapling(recipient) = recipient else {
+                return Err(anyhow!(
+                    "Recipient of a Sapling note must be a Sapling address"
+                ));
+            };
+            let output = tx
+                .sapling_bundle()
+                .and_then(|bundle| bundle.shielded_outputs().get(disclosure.index))
+                .ok_or_else(missing)?;
+            let note = sapling::Note::from_parts(
+                recipient,
+                SaplingValue::from_raw(disclosure.value_zats),
+                Rseed::AfterZip212(rseed),
+            );
+            if note.cmu().to_bytes() != output.cmu().to_bytes() {
+                return Err(commitment_mismatch());
+            }
+
+            let height = BlockHeight::from_u32(disclosure.block_height);
+            let domain = SaplingDomain::new(zip212_enforcement(network, height));
+            let esk = SaplingDomain::derive_esk(&note)
+                .ok_or_else(|| anyhow!("Note has no ZIP 212 ephemeral key"))?;
+            let pk_d = SaplingDomain::get_pk_d(&note);
+            try_output_recovery_with_pkd_esk(&domain, pk_d, esk, output).map(|(_, _, memo)| memo)
+        }
+        "Orchard" => {
+            let recipient = match recipient {
+                Address::Unified(ua) => ua.orchard().copied(),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow!("Recipient of an Orchard note must have an Orchard receiver"))?;
+            let action = tx
+                .orchard_bundle()
+                .and_then(|bundle| bundle.actions().get(disclosure.index))
+                .ok_or_else(missing)?;
+            // The note's rho is the nullifier revealed by the same action
+            let rho = Option::from(Rho::from_bytes(&action.nullifier().to_bytes()))
+                .ok_or_else(|| anyhow!("Action nullifier is not a valid rho"))?;
+            let rseed = Option::from(RandomSeed::from_bytes(rseed, &rho))
+                .ok_or_else(|| anyhow!("rseed is not valid for this note"))?;
+            let note: orchard::Note = Option::from(orchard::Note::from_parts(
+                recipient,
+                OrchardValue::from_raw(disclosure.value_zats),
+                rho,
+                rseed,
+            ))
+            .ok_or_else(|| anyhow!("Disclosed note is invalid"))?;
+            if ExtractedNoteCommitment::from(note.commitment()).to_bytes()
+                != action.cmx().to_bytes()
+            {
+                return Err(commitment_mismatch());
+            }
+
+            let domain = OrchardDomain::for_action(action);
+            let esk = OrchardDomain::derive_esk(&note)
+                .ok_or_else(|| anyhow!("Note has no ephemeral key"))?;
+            let pk_d = OrchardDomain::get_pk_d(&note);
+            try_output_recovery_with_pkd_esk(&domain, pk_d, esk, action).map(|(_, _, memo)| memo)
+        }
+        other => {
+            return Err(anyhow!(
+                "Unknown protocol: {} (expected Sapling or Orchard)",
+                other
+            ))
+        }
+    };
+
+    let recovered_memo = recovered_memo
+        .ok_or_else(|| anyhow!("Output ciphertext does not decrypt with the note"))?;
+    if trim_memo(&recovered_memo) != memo.as_slice() {
+        return Err(anyhow!("Memo does not match the output's ciphertext"));
+    }
+    Ok(())
+}
+
+/// Parse a `--protocol` value: sapling or orchard, in any case.
+pub fn parse_protocol(s: &str) -> Result<String, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "sapling" => Ok("Sapling".to_string()),
+        "orchard" => Ok("Orchard".to_string()),
+        _ => Err(format!("unknown protocol '{}' (expected sapling or orchard)", s)),
+    }
+}
+
+/// Human-readable memo: UTF-8 text, empty for "no memo" (0xF6), or hex for
+/// other binary memos.
+pub fn memo_text(disclosure: &PaymentDisclosure) -> String {
+    let bytes = hex::decode(&disclosure.memo).unwrap_or_default();
+    if bytes == [0xF6] {
+        return String::new();
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => format!("0x{}", disclosure.memo),
+    }
+}
+
+fn commitment_mismatch() -> anyhow::Error {
+    anyhow!("Note commitment does not match: value, recipient or rseed differ from the transaction")
+}
+
+/// Memo bytes without the zero padding to 512 bytes.
+fn trim_memo(memo: &[u8]) -> &[u8] {
+    let end = memo.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    &memo[..end]
+}
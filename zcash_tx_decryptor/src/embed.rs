@@ -0,0 +1,39 @@
+//! Single-call entry point shared by the wasm and C bindings.
+
+use anyhow::{anyhow, Context, Result};
+use zcash_client_backend::keys::UnifiedFullViewingKey;
+use zcash_primitives::consensus::BlockHeight;
+
+use crate::{
+    decrypt::Decryptor,
+    exit::ErrorKind,
+    models::{json, TransactionDetails},
+    network,
+};
+
+/// Decrypt a raw transaction with one UFVK, as if mined at `height` on
+/// `network` ("main", "test" or "regtest"), and render the `--format json`
+/// object. Failures are rendered in that shape too, with a `status` other
+/// than `decrypted`/`no_matches`.
+pub fn decrypt_tx_json(raw_tx_hex: &str, ufvk: &str, height: u32, network: &str) -> String {
+    let details = decrypt(raw_tx_hex, ufvk, height, network)
+        .unwrap_or_else(|e| json::failure("", height, &e));
+    serde_json::to_string(&details).expect("TransactionDetails always serializes")
+}
+
+fn decrypt(raw_tx_hex: &str, ufvk: &str, height: u32, network: &str) -> Result<TransactionDetails> {
+    let network_type = network::parse_network_type(network).map_err(|e| anyhow!(e))?;
+    let params = network::resolve_network(Some(network_type), None, ufvk)?;
+    let key = UnifiedFullViewingKey::decode(&params, ufvk)
+        .map_err(|e| anyhow!("Failed to decode UFVK: {}", e))?;
+
+    let tx_bytes = hex::decode(raw_tx_hex.trim())
+        .context("Raw transaction hex is invalid")
+        .context(ErrorKind::Parse)?;
+    let report = Decryptor::new(params, vec![key], None, true).decrypt(
+        BlockHeight::from_u32(height),
+        None,
+        &tx_bytes,
+    )?;
+    TransactionDetails::try_from(&report)
+}
@@ -0,0 +1,51 @@
+use std::{fmt, process::ExitCode};
+
+/// Exit code for errors not covered by an [`ErrorKind`]. Code 2 is left to
+/// clap, which uses it for invalid arguments.
+pub const FAILURE: u8 = 1;
+
+/// Exit code when the transaction(s) decoded but no output decrypted.
+pub const NO_MATCH: u8 = 3;
+
+/// Exit code for [`ErrorKind::Parse`].
+pub const PARSE_ERROR: u8 = 4;
+
+/// Exit code for [`ErrorKind::Network`].
+pub const NETWORK_ERROR: u8 = 5;
+
+/// Failure categories with their own exit code, attached to an error with
+/// `.context(ErrorKind::..)` where it arises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The raw transaction is not valid hex or does not parse
+    Parse,
+    /// A node or other remote endpoint could not be reached or returned an error
+    Network,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Parse => write!(f, "Transaction could not be parsed"),
+            ErrorKind::Network => write!(f, "Node request failed"),
+        }
+    }
+}
+
+/// Exit code for a run that finished, by whether any output decrypted.
+pub fn for_match(found: bool) -> ExitCode {
+    if found {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(NO_MATCH)
+    }
+}
+
+/// Exit code for a run that failed with `error`.
+pub fn for_error(error: &anyhow::Error) -> ExitCode {
+    match error.downcast_ref::<ErrorKind>() {
+        Some(ErrorKind::Parse) => ExitCode::from(PARSE_ERROR),
+        Some(ErrorKind::Network) => ExitCode::from(NETWORK_ERROR),
+        None => ExitCode::from(FAILURE),
+    }
+}
@@ -0,0 +1,247 @@
+//! Raw transaction fetch from a public block explorer (`--explorer`), for
+//! users with neither a node nor lightwalletd.
+//!
+//! The HTTP client is only built with the `explorer` feature. Without it,
+//! `--explorer` still parses but fails with a hint to rebuild, so scripts get
+//! a clear error instead of an unknown-argument one.
+
+use anyhow::Result;
+#[cfg(feature = "explorer")]
+use anyhow::{anyhow, Context};
+#[cfg(feature = "explorer")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "explorer")]
+use zcash_primitives::consensus::Parameters;
+#[cfg(feature = "explorer")]
+use zcash_protocol::consensus::NetworkType;
+
+#[cfg(feature = "explorer")]
+use crate::exit::ErrorKind;
+use crate::network::ChainParams;
+
+/// Public explorers that can resolve a TXID to raw transaction hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Explorer {
+    /// api.blockchair.com (mainnet only)
+    Blockchair,
+    /// zcashexplorer.app, through its Insight-compatible `/api/rawtx`
+    ZcashExplorer,
+}
+
+impl Explorer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Explorer::Blockchair => "blockchair",
+            Explorer::ZcashExplorer => "zcashexplorer",
+        }
+    }
+}
+
+/// Parse an `--explorer` value.
+pub fn parse_explorer(s: &str) -> Result<Explorer, String> {
+    match s {
+        "blockchair" => Ok(Explorer::Blockchair),
+        "zcashexplorer" => Ok(Explorer::ZcashExplorer),
+        other => Err(format!(
+            "unknown explorer '{}' (expected blockchair or zcashexplorer)",
+            other
+        )),
+    }
+}
+
+/// Shortest gap between two requests to the same explorer. Blockchair's free
+/// tier allows 30 requests a minute; the others are no more generous.
+#[cfg(feature = "explorer")]
+const MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Shortest gap between two requests with `--polite`, half the free tier.
+#[cfg(feature = "explorer")]
+const POLITE_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Attempts per TXID when the explorer answers HTTP 429 (Too Many Requests).
+#[cfg(feature = "explorer")]
+const ATTEMPTS: u32 = 3;
+
+/// Fetches raw transactions from one explorer, at most one request per
+/// [`MIN_INTERVAL`] ([`POLITE_INTERVAL`] with `--polite`), waiting out HTTP
+/// 429 replies.
+#[cfg(feature = "explorer")]
+pub struct ExplorerClient {
+    client: reqwest::Client,
+    explorer: Explorer,
+    base: String,
+    last_request: Option<Instant>,
+    polite: bool,
+}
+
+#[cfg(feature = "explorer")]
+impl ExplorerClient {
+    /// Client for `explorer` on `network`; `base_url` replaces the explorer's
+    /// own address, e.g. for a self-hosted Insight instance.
+    pub fn new(explorer: Explorer, network: &ChainParams, base_url: Option<&str>) -> Result<Self> {
+        let default = match (explorer, network.network_type()) {
+            (Explorer::Blockchair, NetworkType::Main) => "https://api.blockchair.com/zcash",
+            (Explorer::ZcashExplorer, NetworkType::Main) => "https://zcashexplorer.app",
+            (Explorer::ZcashExplorer, NetworkType::Test) => "https://testnet.zcashexplorer.app",
+            (_, network_type) if base_url.is_none() => {
+                return Err(anyhow!(
+                    "{} has no {} explorer; pass --explorer-url",
+                    explorer.name(),
+                    crate::network::network_name(network_type)
+                ))
+            }
+            _ => "",
+        };
+
+        Ok(ExplorerClient {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .user_agent(crate::rpc::USER_AGENT)
+                .build()?,
+            explorer,
+            base: base_url
+                .unwrap_or(default)
+                .trim_end_matches('/')
+                .to_string(),
+            last_request: None,
+            polite: false,
+        })
+    }
+
+    /// Space requests further apart and jitter retries (`--polite`).
+    pub fn with_polite(mut self, polite: bool) -> Self {
+        self.polite = polite;
+        self
+    }
+
+    /// Raw bytes of transaction `txid`.
+    pub async fn fetch_raw_tx(&mut self, txid: &str) -> Result<Vec<u8>> {
+        let (url, pointer) = match self.explorer {
+            Explorer::Blockchair => (
+                format!("{}/raw/transaction/{}", self.base, txid),
+                format!("/data/{}/raw_transaction", txid),
+            ),
+            Explorer::ZcashExplorer => (
+                format!("{}/api/rawtx/{}", self.base, txid),
+                "/rawtx".to_string(),
+            ),
+        };
+
+        let body = self.get(&url).await.context(ErrorKind::Network)?;
+        let hex = body
+            .pointer(&pointer)
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} has no raw transaction for {}",
+                    self.explorer.name(),
+                    txid
+                )
+            })
+            .context(ErrorKind::Network)?;
+        hex::decode(hex.trim())
+            .with_context(|| format!("{} returned invalid hex", self.explorer.name()))
+            .context(ErrorKind::Parse)
+    }
+
+    async fn get(&mut self, url: &str) -> Result<serde_json::Value> {
+        let interval = if self.polite {
+            POLITE_INTERVAL
+        } else {
+            MIN_INTERVAL
+        };
+        for attempt in 1..=ATTEMPTS {
+            if let Some(wait) = self
+                .last_request
+                .and_then(|last| interval.checked_sub(last.elapsed()))
+            {
+                tokio::time::sleep(wait).await;
+            }
+            self.last_request = Some(Instant::now());
+
+            let response = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .with_context(|| format!("{} request failed", self.explorer.name()))?;
+            tracing::debug!(url, attempt, status = %response.status(), "explorer responded");
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < ATTEMPTS {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()?.parse().ok())
+                    .map_or(interval * 2u32.pow(attempt), Duration::from_secs);
+                let retry_after = if self.polite {
+                    crate::rpc::jitter(retry_after)
+                } else {
+                    retry_after
+                };
+                tracing::warn!(
+                    "{} is rate limiting; retrying in {}s",
+                    self.explorer.name(),
+                    retry_after.as_secs()
+                );
+                tokio::time::sleep(retry_after).await;
+                continue;
+            }
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(anyhow!(
+                    "{} does not know this transaction",
+                    self.explorer.name()
+                ));
+            }
+
+            return response
+                .error_for_status()
+                .with_context(|| format!("{} request failed", self.explorer.name()))?
+                .json()
+                .await
+                .with_context(|| format!("{} returned an invalid response", self.explorer.name()));
+        }
+        unreachable!("the last attempt always returns")
+    }
+}
+
+/// Fetch `txid` from `explorer`, after warning on stderr (unless `quiet`)
+/// that the explorer learns which transaction is being looked at. `polite`
+/// throttles the client for shared infrastructure.
+#[cfg(feature = "explorer")]
+pub async fn fetch_raw_tx(
+    explorer: Explorer,
+    base_url: Option<&str>,
+    network: &ChainParams,
+    txid: &str,
+    quiet: bool,
+    polite: bool,
+) -> Result<Vec<u8>> {
+    if !quiet {
+        tracing::warn!(
+            "fetching {} from {} tells a third party, along with your IP address, \
+             that you are interested in this transaction. Use --raw-tx or your own node to \
+             avoid this.",
+            txid,
+            explorer.name()
+        );
+    }
+    ExplorerClient::new(explorer, network, base_url)?
+        .with_polite(polite)
+        .fetch_raw_tx(txid)
+        .await
+}
+
+#[cfg(not(feature = "explorer"))]
+pub async fn fetch_raw_tx(
+    explorer: Explorer,
+    _base_url: Option<&str>,
+    _network: &ChainParams,
+    _txid: &str,
+    _quiet: bool,
+    _polite: bool,
+) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!(
+        "--explorer {} needs a build with the `explorer` feature \
+         (cargo build --release --features explorer)",
+        explorer.name()
+    ))
+}
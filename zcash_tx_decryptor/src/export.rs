@@ -0,0 +1,388 @@
+//! `export`: ledger entries for accounting software from a `--db` result
+//! store, one per mined incoming or outgoing output. Change moves value
+//! within the wallet and mempool hits aren't final, so neither is exported.
+//!
+//! Amounts are in ZEC, or with `--fiat` in that currency at each entry's
+//! price. Fees aren't recorded in the store and so aren't booked.
+
+use std::{collections::HashMap, fmt::Write};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{
+    fiat::PriceFeed,
+    models::{StoredOutput, Zec},
+    store::{OutputFilter, ResultStore},
+};
+
+/// Ledger formats `export` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// OFX 1.0.2 bank statement, one per account
+    Ofx,
+    /// Quicken Interchange Format, one bank account section per account
+    Qif,
+    /// Beancount transactions with two postings each
+    Beancount,
+}
+
+/// Parse an `export --format` value.
+pub fn parse_format(s: &str) -> Result<Format, String> {
+    match s {
+        "ofx" => Ok(Format::Ofx),
+        "qif" => Ok(Format::Qif),
+        "beancount" => Ok(Format::Beancount),
+        other => Err(format!(
+            "unknown export format '{}' (expected ofx, qif or beancount)",
+            other
+        )),
+    }
+}
+
+/// Ledger account names entries are booked against.
+#[derive(Debug, Clone)]
+pub struct LedgerAccounts {
+    /// Where the wallet's ZEC is held, unless `names` has the account
+    pub asset: String,
+    /// Counter-account of incoming outputs
+    pub income: String,
+    /// Counter-account of outgoing outputs
+    pub expense: String,
+    /// Asset account of individual decryptor accounts (`--account-name`)
+    pub names: HashMap<u32, String>,
+}
+
+impl LedgerAccounts {
+    fn asset(&self, account: u32) -> &str {
+        self.names.get(&account).unwrap_or(&self.asset)
+    }
+}
+
+/// Parse an `--account-name` value, `<account>=<ledger account>`.
+pub fn parse_account_name(s: &str) -> Result<(u32, String), String> {
+    s.split_once('=')
+        .and_then(|(account, name)| Some((account.parse().ok()?, name.to_string())))
+        .filter(|(_, name)| !name.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "invalid account name '{}' (expected <account>=<ledger account>)",
+                s
+            )
+        })
+}
+
+/// Which outputs are exported; `None` means unbounded. Dates are UTC days,
+/// both ends included.
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub from_height: Option<u32>,
+    pub to_height: Option<u32>,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+    pub account: Option<u32>,
+    pub min_value_zats: Option<i64>,
+}
+
+/// One exported output.
+pub struct Entry {
+    /// Block time where known, otherwise when the output was first stored
+    pub time: DateTime<Utc>,
+    pub output: StoredOutput,
+    /// Value in the `--fiat` currency, if converting
+    pub fiat: Option<f64>,
+}
+
+impl Entry {
+    fn incoming(&self) -> bool {
+        self.output.transfer_type == "Incoming"
+    }
+
+    /// Unique and stable across exports, so re-importing doesn't duplicate.
+    fn id(&self) -> String {
+        let out = &self.output;
+        format!(
+            "{}-{}-{}-{}",
+            out.transaction_id,
+            out.protocol.to_ascii_lowercase(),
+            out.index,
+            out.account
+        )
+    }
+
+    /// Counterparty, else the account label.
+    fn payee(&self) -> Option<&str> {
+        let out = &self.output;
+        out.counterparty.as_deref().or(out.account_label.as_deref())
+    }
+
+    /// Payment reference and the memo's first line, whichever are set.
+    fn description(&self) -> String {
+        let out = &self.output;
+        let memo = out
+            .memo
+            .trim_end_matches('\0')
+            .lines()
+            .next()
+            .unwrap_or_default();
+        match out.payment_reference.as_deref() {
+            Some(reference) if !memo.is_empty() && memo != reference => {
+                format!("{}: {}", reference, memo)
+            }
+            Some(reference) => reference.to_string(),
+            None => memo.to_string(),
+        }
+    }
+
+    /// Signed amount as written: fiat to the cent, else ZEC to the zatoshi.
+    fn amount(&self) -> String {
+        let sign = if self.incoming() { "" } else { "-" };
+        match self.fiat {
+            Some(fiat) => format!("{}{:.2}", sign, fiat),
+            None => format!("{}{}", sign, zec(self.output.amount_zats)),
+        }
+    }
+}
+
+/// The mined incoming and outgoing outputs in `store` that `selection`
+/// covers, oldest first.
+pub fn entries(store: &ResultStore, selection: &Selection) -> Result<Vec<Entry>> {
+    let rows = store.query(&OutputFilter {
+        from_height: selection.from_height,
+        to_height: selection.to_height,
+        account: selection.account,
+        min_value_zats: selection.min_value_zats,
+    })?;
+    let entries = rows
+        .into_iter()
+        .filter(|row| row.confirmed && row.transfer_type != "WalletInternal")
+        .filter_map(|row| {
+            let time = row.block_time.as_deref().unwrap_or(&row.recorded_at);
+            let time = DateTime::parse_from_rfc3339(time).ok()?.with_timezone(&Utc);
+            Some(Entry {
+                time,
+                output: row,
+                fiat: None,
+            })
+        })
+        .filter(|entry| {
+            let date = entry.time.date_naive();
+            selection.from_date.is_none_or(|from| date >= from)
+                && selection.to_date.is_none_or(|to| date <= to)
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Price every entry with `prices`, at its time.
+pub async fn convert(entries: &mut [Entry], prices: &mut PriceFeed) -> Result<()> {
+    for entry in entries {
+        let price = prices.price_at(entry.time).await?;
+        let zec = entry.output.amount_zats as f64 / 100_000_000.0;
+        entry.fiat = Some((zec * price * 100.0).round() / 100.0);
+    }
+    Ok(())
+}
+
+/// `entries` in `format`. `currency` is the fiat currency the amounts were
+/// converted to, if any.
+pub fn render(
+    format: Format,
+    entries: &[Entry],
+    accounts: &LedgerAccounts,
+    currency: Option<&str>,
+) -> Result<String> {
+    let currency = currency.map(str::to_ascii_uppercase);
+    match format {
+        Format::Ofx => ofx(entries, accounts, currency.as_deref()),
+        Format::Qif => Ok(qif(entries, accounts)),
+        Format::Beancount => Ok(beancount(entries, accounts, currency.as_deref())),
+    }
+}
+
+/// Entries grouped by asset account, in order of first appearance.
+fn by_asset<'a>(
+    entries: &'a [Entry],
+    accounts: &'a LedgerAccounts,
+) -> Vec<(&'a str, Vec<&'a Entry>)> {
+    let mut groups: Vec<(&str, Vec<&Entry>)> = Vec::new();
+    for entry in entries {
+        let asset = accounts.asset(entry.output.account);
+        match groups.iter_mut().find(|(name, _)| *name == asset) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((asset, vec![entry])),
+        }
+    }
+    groups
+}
+
+/// OFX 1.0.2 (SGML): one bank statement per asset account. Amounts are fiat
+/// when converted; otherwise the statement currency is ZEC, which not every
+/// importer accepts.
+fn ofx(entries: &[Entry], accounts: &LedgerAccounts, currency: Option<&str>) -> Result<String> {
+    let first = entries
+        .first()
+        .ok_or_else(|| anyhow!("Nothing to export"))?;
+    let last = entries.last().unwrap_or(first);
+    let mut out = String::from(
+        "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\nSECURITY:NONE\nENCODING:USASCII\n\
+         CHARSET:1252\nCOMPRESSION:NONE\nOLDFILEUID:NONE\nNEWFILEUID:NONE\n\n",
+    );
+    let now = ofx_time(Utc::now());
+    writeln!(out, "<OFX>")?;
+    writeln!(out, "<SIGNONMSGSRSV1><SONRS>")?;
+    writeln!(out, "<STATUS><CODE>0<SEVERITY>INFO</STATUS>")?;
+    writeln!(out, "<DTSERVER>{}<LANGUAGE>ENG", now)?;
+    writeln!(out, "</SONRS></SIGNONMSGSRSV1>")?;
+    writeln!(out, "<BANKMSGSRSV1>")?;
+    for (i, (asset, group)) in by_asset(entries, accounts).into_iter().enumerate() {
+        let balance: f64 = group
+            .iter()
+            .map(|entry| entry.amount().parse::<f64>().unwrap_or_default())
+            .sum();
+        writeln!(out, "<STMTTRNRS><TRNUID>{}", i + 1)?;
+        writeln!(out, "<STATUS><CODE>0<SEVERITY>INFO</STATUS>")?;
+        writeln!(out, "<STMTRS><CURDEF>{}", currency.unwrap_or("ZEC"))?;
+        writeln!(
+            out,
+            "<BANKACCTFROM><BANKID>ZCASH<ACCTID>{}<ACCTTYPE>CHECKING</BANKACCTFROM>",
+            sgml(asset)
+        )?;
+        writeln!(
+            out,
+            "<BANKTRANLIST><DTSTART>{}<DTEND>{}",
+            ofx_time(first.time),
+            ofx_time(last.time)
+        )?;
+        for entry in group {
+            let kind = if entry.incoming() { "CREDIT" } else { "DEBIT" };
+            writeln!(out, "<STMTTRN><TRNTYPE>{}", kind)?;
+            writeln!(out, "<DTPOSTED>{}", ofx_time(entry.time))?;
+            writeln!(out, "<TRNAMT>{}", entry.amount())?;
+            writeln!(out, "<FITID>{}", entry.id())?;
+            if let Some(payee) = entry.payee() {
+                // NAME is limited to 32 characters
+                writeln!(
+                    out,
+                    "<NAME>{}",
+                    sgml(&payee.chars().take(32).collect::<String>())
+                )?;
+            }
+            let description = entry.description();
+            let memo = match entry.fiat {
+                Some(_) => format!("{} ZEC {}", zec(entry.output.amount_zats), description),
+                None => description,
+            };
+            if !memo.trim().is_empty() {
+                writeln!(out, "<MEMO>{}", sgml(memo.trim()))?;
+            }
+            writeln!(out, "</STMTTRN>")?;
+        }
+        writeln!(out, "</BANKTRANLIST>")?;
+        let decimals = if currency.is_some() { 2 } else { 8 };
+        writeln!(
+            out,
+            "<LEDGERBAL><BALAMT>{:.*}<DTASOF>{}</LEDGERBAL>",
+            decimals,
+            balance,
+            ofx_time(last.time)
+        )?;
+        writeln!(out, "</STMTRS></STMTTRNRS>")?;
+    }
+    writeln!(out, "</BANKMSGSRSV1>")?;
+    writeln!(out, "</OFX>")?;
+    Ok(out)
+}
+
+/// QIF: an `!Account` header per asset account followed by its entries,
+/// categorized to the income or expense account.
+fn qif(entries: &[Entry], accounts: &LedgerAccounts) -> String {
+    let mut out = String::new();
+    for (asset, group) in by_asset(entries, accounts) {
+        out.push_str(&format!(
+            "!Account\nN{}\nTBank\n^\n!Type:Bank\n",
+            qif_line(asset)
+        ));
+        for entry in group {
+            let category = if entry.incoming() {
+                &accounts.income
+            } else {
+                &accounts.expense
+            };
+            out.push_str(&format!("D{}\n", entry.time.format("%m/%d/%Y")));
+            out.push_str(&format!("T{}\n", entry.amount()));
+            out.push_str(&format!("N{}\n", entry.output.transaction_id));
+            if let Some(payee) = entry.payee() {
+                out.push_str(&format!("P{}\n", qif_line(payee)));
+            }
+            let description = entry.description();
+            if !description.is_empty() {
+                out.push_str(&format!("M{}\n", qif_line(&description)));
+            }
+            out.push_str(&format!("L{}\n^\n", qif_line(category)));
+        }
+    }
+    out
+}
+
+/// Beancount: one transaction per entry, with the TXID as metadata. With
+/// fiat, the ZEC posting carries its price and the other posting balances in
+/// the fiat currency.
+fn beancount(entries: &[Entry], accounts: &LedgerAccounts, currency: Option<&str>) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let (counter, sign) = if entry.incoming() {
+            (&accounts.income, "")
+        } else {
+            (&accounts.expense, "-")
+        };
+        out.push_str(&format!(
+            "{} * \"{}\" \"{}\"\n",
+            entry.time.format("%Y-%m-%d"),
+            quoted(entry.payee().unwrap_or("Zcash")),
+            quoted(&entry.description())
+        ));
+        out.push_str(&format!("  txid: \"{}\"\n", entry.output.transaction_id));
+        let amount = format!("{}{} ZEC", sign, zec(entry.output.amount_zats));
+        let price = match (entry.fiat, currency) {
+            (Some(fiat), Some(currency)) if entry.output.amount_zats > 0 => {
+                let zec = entry.output.amount_zats as f64 / 100_000_000.0;
+                format!(" @ {:.4} {}", fiat / zec, currency)
+            }
+            _ => String::new(),
+        };
+        out.push_str(&format!(
+            "  {}  {}{}\n",
+            accounts.asset(entry.output.account),
+            amount,
+            price
+        ));
+        out.push_str(&format!("  {}\n\n", counter));
+    }
+    out
+}
+
+fn zec(zats: i64) -> String {
+    Zec::from_zats(zats).to_string()
+}
+
+fn ofx_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%d%H%M%S").to_string()
+}
+
+/// Escape SGML markup characters.
+fn sgml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// QIF fields are one line each.
+fn qif_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+/// Escape a Beancount string.
+fn quoted(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
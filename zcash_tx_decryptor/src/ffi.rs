@@ -0,0 +1,74 @@
+//! C bindings (`--features ffi`). The header is generated with cbindgen:
+//! `cbindgen --config cbindgen.toml --output include/zcash_tx_decryptor.h`.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{embed, models::json};
+
+/// Decrypt a raw transaction with one UFVK, as if mined at `height` on
+/// `network` ("main", "test" or "regtest").
+///
+/// Returns the `--format json` object as a NUL-terminated UTF-8 string, which
+/// the caller must release with `ztd_string_free`. Errors are reported in the
+/// same JSON shape (see its `status` and `errors` fields), so the result is
+/// never NULL.
+///
+/// # Safety
+///
+/// Each argument must be NULL or point to a NUL-terminated string that stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn ztd_decrypt_tx(
+    raw_tx_hex: *const c_char,
+    ufvk: *const c_char,
+    height: u32,
+    network: *const c_char,
+) -> *mut c_char {
+    let result = panic::catch_unwind(|| {
+        let raw_tx_hex = read_str(raw_tx_hex, "raw_tx_hex")?;
+        let ufvk = read_str(ufvk, "ufvk")?;
+        let network = read_str(network, "network")?;
+        Ok(embed::decrypt_tx_json(raw_tx_hex, ufvk, height, network))
+    });
+    let rendered = match result {
+        Ok(Ok(rendered)) => rendered,
+        Ok(Err(e)) => failure(height, &e),
+        // Unwinding into C is undefined behaviour
+        Err(_) => failure(height, &anyhow!("Decryption panicked")),
+    };
+
+    // JSON escapes control characters, so there is no interior NUL
+    CString::new(rendered).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Release a string returned by `ztd_decrypt_tx`. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `s` must be NULL or a pointer returned by `ztd_decrypt_tx` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ztd_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+unsafe fn read_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(anyhow!("{} is NULL", name));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| anyhow!("{} is not valid UTF-8", name))
+}
+
+fn failure(height: u32, error: &anyhow::Error) -> String {
+    serde_json::to_string(&json::failure("", height, error))
+        .expect("TransactionDetails always serializes")
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::{
+    exit::ErrorKind,
+    models::{zats_to_zec, Diagnostic, FiatValue, TxReport},
+};
+
+/// Where ZEC prices come from for `--fiat`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceSource {
+    /// Daily historical prices from the CoinGecko API
+    CoinGecko,
+    /// One price for every transaction (`--price`), for offline use
+    Fixed(f64),
+}
+
+impl PriceSource {
+    fn name(&self) -> &'static str {
+        match self {
+            PriceSource::CoinGecko => "coingecko",
+            PriceSource::Fixed(_) => "fixed",
+        }
+    }
+}
+
+/// Parse a `--price-source` value.
+pub fn parse_price_source(s: &str) -> Result<PriceSource, String> {
+    match s {
+        "coingecko" => Ok(PriceSource::CoinGecko),
+        other => Err(format!("unknown price source '{}' (expected coingecko)", other)),
+    }
+}
+
+/// Converts report amounts to a fiat currency at the time of each
+/// transaction, caching one price per day.
+pub struct PriceFeed {
+    client: reqwest::Client,
+    currency: String,
+    source: PriceSource,
+    daily: HashMap<NaiveDate, f64>,
+}
+
+impl PriceFeed {
+    pub fn new(currency: &str, source: PriceSource) -> Self {
+        PriceFeed {
+            client: reqwest::Client::new(),
+            currency: currency.to_ascii_lowercase(),
+            source,
+            daily: HashMap::new(),
+        }
+    }
+
+    /// Currency prices are in, lowercase (e.g. usd).
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// Attach fiat amounts to `report`, priced at its block time or, when
+    /// that is unknown, at the time it was decrypted. A price that can't be
+    /// fetched becomes a warning on the report rather than an error.
+    pub async fn convert(&mut self, report: &mut TxReport) {
+        let at = report.block_time.unwrap_or(report.decrypted_at);
+        let price = match self.price_at(at).await {
+            Ok(price) => price,
+            Err(e) => {
+                report.warnings.push(Diagnostic {
+                    code: "price_unavailable".to_string(),
+                    message: format!("{:#}", e),
+                });
+                return;
+            }
+        };
+
+        let fiat = |zats: u64| (zats_to_zec(zats) * price * 100.0).round() / 100.0;
+        let incoming = report.total_zats("Incoming");
+        let change = report.total_zats("WalletInternal");
+        report.fiat = Some(FiatValue {
+            currency: self.currency.clone(),
+            price,
+            price_source: self.source.name().to_string(),
+            price_time: at,
+            amount: fiat(incoming.saturating_add(change)),
+            incoming: fiat(incoming),
+            change: fiat(change),
+            outgoing: fiat(report.total_zats("Outgoing")),
+        });
+    }
+
+    /// Price of 1 ZEC at `at`: the day's price, or the fixed one.
+    pub async fn price_at(&mut self, at: DateTime<Utc>) -> Result<f64> {
+        let day = at.date_naive();
+        match self.source {
+            PriceSource::Fixed(price) => Ok(price),
+            PriceSource::CoinGecko => {
+                if let Some(price) = self.daily.get(&day) {
+                    return Ok(*price);
+                }
+                let price = self.coingecko(day).await.context(ErrorKind::Network)?;
+                self.daily.insert(day, price);
+                Ok(price)
+            }
+        }
+    }
+
+    /// Price of ZEC on `day` (CoinGecko's daily snapshot, 00:00 UTC).
+    async fn coingecko(&self, day: NaiveDate) -> Result<f64> {
+        let date = day.format("%d-%m-%Y").to_string();
+        let body: Value = self
+            .client
+            .get("https://api.coingecko.com/api/v3/coins/zcash/history")
+            .query(&[("date", date.as_str()), ("localization", "false")])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("CoinGecko price request failed")?
+            .json()
+            .await
+            .context("CoinGecko returned an invalid response")?;
+
+        body["market_data"]["current_price"][&self.currency]
+            .as_f64()
+            .ok_or_else(|| {
+                anyhow!("CoinGecko has no {} price for ZEC on {}", self.currency, day)
+            })
+    }
+}
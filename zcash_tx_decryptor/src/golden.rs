@@ -0,0 +1,126 @@
+//! Golden fixtures: recorded transactions with throwaway viewing keys and the
+//! exact `--format json` report each must produce. Run by `tests/golden.rs`
+//! and, from the copies embedded here, by `zcash-tx-decryptor --self-test`.
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::embed;
+
+/// Every file in `fixtures/`, by name. `tests/golden.rs` fails if a file is
+/// missing from this list.
+pub const EMBEDDED: &[(&str, &str)] = &[
+    (
+        "synthetic-v5-orchard",
+        include_str!("../fixtures/synthetic-v5-orchard.json"),
+    ),
+    (
+        "synthetic-v5-sapling",
+        include_str!("../fixtures/synthetic-v5-sapling.json"),
+    ),
+    (
+        "testnet-v4-sprout",
+        include_str!("../fixtures/testnet-v4-sprout.json"),
+    ),
+    (
+        "zip244-v5-orchard",
+        include_str!("../fixtures/zip244-v5-orchard.json"),
+    ),
+    (
+        "zip244-v5-sapling-orchard",
+        include_str!("../fixtures/zip244-v5-sapling-orchard.json"),
+    ),
+    (
+        "zip244-v5-shielded",
+        include_str!("../fixtures/zip244-v5-shielded.json"),
+    ),
+    (
+        "zip244-v5-transparent",
+        include_str!("../fixtures/zip244-v5-transparent.json"),
+    ),
+];
+
+/// Report fields that differ from run to run and are not compared.
+const VOLATILE: &[&str] = &["timestamp"];
+
+/// One fixture file.
+#[derive(Debug, Deserialize)]
+pub struct Fixture {
+    pub name: String,
+
+    /// Where the transaction comes from and what it exercises
+    pub description: String,
+
+    /// "main", "test" or "regtest"
+    pub network: String,
+
+    /// Height to decrypt at (the block it was mined in)
+    pub height: u32,
+
+    /// Throwaway viewing key; never a key that has held funds
+    pub ufvk: String,
+
+    /// Raw transaction hex
+    pub raw_tx: String,
+
+    /// The JSON report, without the [`VOLATILE`] fields
+    pub expected: Map<String, Value>,
+}
+
+impl Fixture {
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Invalid fixture")
+    }
+}
+
+/// The fixtures compiled into the library.
+pub fn embedded() -> Result<Vec<Fixture>> {
+    EMBEDDED
+        .iter()
+        .map(|(name, json)| {
+            Fixture::parse(json).with_context(|| format!("Embedded fixture {}", name))
+        })
+        .collect()
+}
+
+/// Decrypt `fixture` and compare the report with its `expected` object,
+/// field by field. The error lists every field that differs.
+pub fn check(fixture: &Fixture) -> Result<()> {
+    let json = embed::decrypt_tx_json(
+        &fixture.raw_tx,
+        &fixture.ufvk,
+        fixture.height,
+        &fixture.network,
+    );
+    let mut actual: Map<String, Value> =
+        serde_json::from_str(&json).context("Report is not a JSON object")?;
+    for field in VOLATILE {
+        actual.remove(*field);
+    }
+
+    let mut fields: Vec<&String> = fixture.expected.keys().chain(actual.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    let mismatches: Vec<String> = fields
+        .into_iter()
+        .filter(|field| fixture.expected.get(*field) != actual.get(*field))
+        .map(|field| {
+            let show =
+                |value: Option<&Value>| value.map_or("(missing)".to_string(), Value::to_string);
+            format!(
+                "{}: expected {}, got {}",
+                field,
+                show(fixture.expected.get(field)),
+                show(actual.get(field))
+            )
+        })
+        .collect();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("{}", mismatches.join("\n")))
+    }
+}
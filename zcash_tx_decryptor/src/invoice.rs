@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use zcash_client_backend::{
+    address::{Address, UnifiedAddress},
+    zip321::{Payment, TransactionRequest},
+};
+
+use crate::{models::TxReport, network::ChainParams};
+
+/// Outcome of matching one payment of a ZIP-321 request.
+#[derive(Debug)]
+pub struct PaymentCheck {
+    /// Payment index within the request
+    pub index: usize,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Parse a ZIP-321 `zcash:` payment URI.
+pub fn parse_request(uri: &str) -> Result<TransactionRequest> {
+    TransactionRequest::from_uri(uri).map_err(|e| anyhow!("Invalid ZIP-321 payment URI: {}", e))
+}
+
+/// Check each payment of `request` against the outputs we received in
+/// `report`. A payment is satisfied by a distinct received output to one of
+/// its address's shielded receivers, worth at least the requested amount and,
+/// if the request has a memo, carrying exactly that memo.
+pub fn check(
+    network: &ChainParams,
+    request: &TransactionRequest,
+    report: &TxReport,
+) -> Vec<PaymentCheck> {
+    let mut used = HashSet::new();
+
+    request
+        .payments()
+        .iter()
+        .map(|(&index, payment)| {
+            let receivers = match shielded_receivers(network, payment) {
+                Ok(receivers) => receivers,
+                Err(detail) => {
+                    return PaymentCheck {
+                        index,
+                        passed: false,
+                        detail,
+                    }
+                }
+            };
+            let amount = i64::try_from(u64::from(payment.amount())).unwrap_or(i64::MAX);
+            let memo = payment
+                .memo()
+                .map(|memo| String::from_utf8_lossy(memo.as_slice()).to_string());
+
+            let candidates: Vec<usize> = report
+                .outputs
+                .iter()
+                .enumerate()
+                .filter(|(i, out)| {
+                    !used.contains(i)
+                        && out.transfer_type != "Outgoing"
+                        && out
+                            .address
+                            .as_ref()
+                            .is_some_and(|a| receivers.contains(a))
+                })
+                .map(|(i, _)| i)
+                .collect();
+
+            let matched = candidates.iter().copied().find(|&i| {
+                let out = &report.outputs[i];
+                out.amount_zats >= amount && memo.as_ref().is_none_or(|m| &out.memo == m)
+            });
+
+            match matched {
+                Some(i) => {
+                    used.insert(i);
+                    let out = &report.outputs[i];
+                    PaymentCheck {
+                        index,
+                        passed: true,
+                        detail: format!(
+                            "{} output #{} pays {} zats",
+                            out.protocol, out.index, out.amount_zats
+                        ),
+                    }
+                }
+                None if candidates.is_empty() => PaymentCheck {
+                    index,
+                    passed: false,
+                    detail: "no output to the requested address".to_string(),
+                },
+                None => PaymentCheck {
+                    index,
+                    passed: false,
+                    detail: format!(
+                        "output(s) to the requested address, but none for at least {} zats{}",
+                        amount,
+                        if memo.is_some() {
+                            " with the requested memo"
+                        } else {
+                            ""
+                        }
+                    ),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Encodings of the payment address's shielded receivers, in the same form
+/// as `OutputInfo::address`.
+fn shielded_receivers(network: &ChainParams, payment: &Payment) -> Result<HashSet<String>, String> {
+    let encoded = payment.recipient_address().encode();
+    let address = Address::decode(network, &encoded)
+        .ok_or_else(|| format!("address {} is not valid on this network", encoded))?;
+
+    let mut receivers = HashSet::new();
+    match address {
+        Address::Sapling(pa) => {
+            receivers.insert(Address::Sapling(pa).encode(network));
+        }
+        Address::Unified(ua) => {
+            if let Some(pa) = ua.sapling() {
+                receivers.insert(Address::Sapling(*pa).encode(network));
+            }
+            if let Some(orchard) = ua.orchard() {
+                if let Some(ua) = UnifiedAddress::from_receivers(Some(*orchard), None, None) {
+                    receivers.insert(ua.encode(network));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if receivers.is_empty() {
+        return Err(format!(
+            "address {} has no shielded receiver; transparent payments can't be verified",
+            encoded
+        ));
+    }
+    Ok(receivers)
+}
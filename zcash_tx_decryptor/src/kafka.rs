@@ -0,0 +1,97 @@
+//! `--sink kafka://<broker>[,<broker>...]/<topic>`: publish events to a Kafka
+//! topic. The producer is idempotent and waits for all in-sync replicas, so an
+//! event counts as delivered only once a broker failover can't lose it.
+//! Events are keyed by TXID, keeping a transaction's events on one partition
+//! and in order; output events carry their idempotency key in an
+//! `idempotency_key` header.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use rdkafka::{
+    config::ClientConfig,
+    error::{KafkaError, RDKafkaErrorCode},
+    message::{Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+};
+use serde_json::Value;
+
+use crate::pipeline::{self, DeliveryError, ResultSink};
+
+/// How long a send may wait for room in the producer's local queue
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaSink {
+    /// Producer for a `--sink kafka://...` URL. Brokers are contacted on the
+    /// first delivery.
+    pub fn new(url: &str) -> Result<Self> {
+        let (brokers, topic) = url
+            .strip_prefix("kafka://")
+            .and_then(|rest| rest.split_once('/'))
+            .filter(|(brokers, topic)| {
+                !brokers.is_empty() && !topic.is_empty() && !topic.contains('/')
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid Kafka sink '{}' (expected kafka://<broker>[,<broker>...]/<topic>)",
+                    url
+                )
+            })?;
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("enable.idempotence", "true")
+            .set("acks", "all")
+            .create()
+            .context("Failed to create the Kafka producer")?;
+        Ok(KafkaSink {
+            producer,
+            topic: topic.to_string(),
+        })
+    }
+}
+
+impl ResultSink for KafkaSink {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn deliver(&mut self, event: &str) -> Result<(), DeliveryError> {
+        // Reorg events have no TXID and go to any partition
+        let key = serde_json::from_str::<Value>(event)
+            .ok()
+            .and_then(|event| Some(event.get("transaction_id")?.as_str()?.to_string()));
+        let idempotency_key = pipeline::idempotency_key(event);
+        let mut record = FutureRecord::<str, str>::to(&self.topic).payload(event);
+        if let Some(key) = key.as_deref() {
+            record = record.key(key);
+        }
+        if let Some(idempotency_key) = idempotency_key.as_deref() {
+            record = record.headers(OwnedHeaders::new().insert(Header {
+                key: "idempotency_key",
+                value: Some(idempotency_key),
+            }));
+        }
+
+        match self
+            .producer
+            .send(record, Timeout::After(QUEUE_TIMEOUT))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err((e, _)) => Err(DeliveryError {
+                // Only an oversized event is rejected for good
+                retry: !matches!(
+                    e,
+                    KafkaError::MessageProduction(RDKafkaErrorCode::MessageSizeTooLarge)
+                ),
+                error: anyhow!("Kafka did not take the event: {}", e),
+            }),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! `check-key`: decode a viewing key and report what it can see, without a
+//! transaction to test it on.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use zcash_client_backend::{
+    address::Address,
+    encoding::decode_extended_full_viewing_key,
+    keys::{UnifiedFullViewingKey, UnifiedIncomingViewingKey},
+};
+use zcash_primitives::consensus::{BlockHeight, NetworkUpgrade, Parameters};
+use zcash_protocol::consensus::{NetworkConstants, NetworkType};
+
+use crate::{
+    decrypt::index_to_u64,
+    models::{Diagnostic, KeyInfo},
+    network::{self, ChainParams},
+};
+
+/// Key encodings by human-readable prefix, longest prefixes first since
+/// e.g. "uviewtest1" also starts with "uview".
+const PREFIXES: &[(&str, &str, NetworkType)] = &[
+    ("uviewregtest1", "ufvk", NetworkType::Regtest),
+    ("uviewtest1", "ufvk", NetworkType::Test),
+    ("uview1", "ufvk", NetworkType::Main),
+    ("uivkregtest1", "uivk", NetworkType::Regtest),
+    ("uivktest1", "uivk", NetworkType::Test),
+    ("uivk1", "uivk", NetworkType::Main),
+    (
+        "zxviewregtestsapling1",
+        "sapling_efvk",
+        NetworkType::Regtest,
+    ),
+    ("zxviewtestsapling1", "sapling_efvk", NetworkType::Test),
+    ("zxviews1", "sapling_efvk", NetworkType::Main),
+];
+
+/// Decode `key` (UFVK, UIVK or Sapling EFVK) on the network from its prefix,
+/// or from `network`/`params_file`, which must agree with it. With `height`,
+/// also check that the key's protocols are active there.
+pub fn check(
+    key: &str,
+    network: Option<NetworkType>,
+    params_file: Option<&Path>,
+    height: Option<u32>,
+) -> Result<KeyInfo> {
+    let key = key.trim();
+    let (_, kind, key_network) = PREFIXES
+        .iter()
+        .find(|(prefix, _, _)| key.starts_with(prefix))
+        .copied()
+        .ok_or_else(|| {
+            anyhow!(
+                "Unrecognized key: expected a UFVK (uview...), UIVK (uivk...) or Sapling \
+                 extended full viewing key (zxview...)"
+            )
+        })?;
+    let params = network::select_network(network, params_file, key_network)?;
+    if params.network_type() != key_network {
+        return Err(anyhow!(
+            "Key is for {:?} but the selected network is {:?}",
+            key_network,
+            params.network_type()
+        ));
+    }
+
+    let mut info = match kind {
+        "ufvk" => {
+            let ufvk = UnifiedFullViewingKey::decode(&params, key)
+                .map_err(|e| anyhow!("Invalid UFVK: {}", e))?;
+            let uivk = ufvk.to_unified_incoming_viewing_key();
+            let mut info = unified_info(&params, kind, &uivk);
+            info.views_outgoing = true;
+            info
+        }
+        "uivk" => {
+            let uivk = UnifiedIncomingViewingKey::decode(&params, key)
+                .map_err(|e| anyhow!("Invalid UIVK: {}", e))?;
+            let mut info = unified_info(&params, kind, &uivk);
+            info.warnings.push(Diagnostic {
+                code: "incoming_only".to_string(),
+                message: "An incoming viewing key can't recover sent outputs, and this tool's \
+                          decryption commands take a UFVK"
+                    .to_string(),
+            });
+            info
+        }
+        _ => {
+            let hrp = key_network.hrp_sapling_extended_full_viewing_key();
+            let efvk = decode_extended_full_viewing_key(hrp, key)
+                .map_err(|e| anyhow!("Invalid Sapling extended full viewing key: {}", e))?;
+            let (index, address) = efvk.default_address();
+            let ufvk = UnifiedFullViewingKey::from_sapling_extended_full_viewing_key(efvk)
+                .map_err(|e| anyhow!("Failed to re-encode as a UFVK: {:?}", e))?;
+            KeyInfo {
+                kind: kind.to_string(),
+                network: network::network_name(key_network).to_string(),
+                components: vec!["Sapling".to_string()],
+                views_outgoing: true,
+                default_address: Some(Address::Sapling(address).encode(&params)),
+                default_address_index: index_to_u64(&index),
+                ufvk: Some(ufvk.encode(&params)),
+                height: None,
+                consensus_branch_id: None,
+                warnings: Vec::new(),
+            }
+        }
+    };
+
+    if let Some(height) = height {
+        check_height(&params, &mut info, height)?;
+    }
+    Ok(info)
+}
+
+/// Components and default address of a unified key.
+fn unified_info(params: &ChainParams, kind: &str, uivk: &UnifiedIncomingViewingKey) -> KeyInfo {
+    let mut components = Vec::new();
+    if uivk.orchard().is_some() {
+        components.push("Orchard".to_string());
+    }
+    if uivk.sapling().is_some() {
+        components.push("Sapling".to_string());
+    }
+    if uivk.transparent().is_some() {
+        components.push("Transparent".to_string());
+    }
+
+    let mut warnings = Vec::new();
+    let default = uivk
+        .to_address_request()
+        .ok_or_else(|| "The key has no shielded component to decrypt with".to_string())
+        .and_then(|request| uivk.default_address(request).map_err(|e| e.to_string()));
+    let (default_address, default_address_index) = match default {
+        Ok((ua, index)) => (Some(ua.encode(params)), index_to_u64(&index)),
+        Err(e) => {
+            warnings.push(Diagnostic {
+                code: "no_default_address".to_string(),
+                message: e,
+            });
+            (None, None)
+        }
+    };
+
+    KeyInfo {
+        kind: kind.to_string(),
+        network: network::network_name(params.network_type()).to_string(),
+        components,
+        views_outgoing: false,
+        default_address,
+        default_address_index,
+        ufvk: None,
+        height: None,
+        consensus_branch_id: None,
+        warnings,
+    }
+}
+
+/// Fail if nothing shielded exists yet at `height`; warn about an Orchard
+/// component before NU5.
+fn check_height(params: &ChainParams, info: &mut KeyInfo, height: u32) -> Result<()> {
+    let at = BlockHeight::from_u32(height);
+    let active = |upgrade| params.is_nu_active(upgrade, at);
+    if !active(NetworkUpgrade::Sapling) {
+        return Err(anyhow!(
+            "Height {} is before Sapling activation on {}; no output there can be \
+             decrypted with a viewing key",
+            height,
+            info.network
+        ));
+    }
+
+    let has_orchard = info.components.iter().any(|c| c == "Orchard");
+    if has_orchard && !active(NetworkUpgrade::Nu5) {
+        let nu5 = params.activation_height(NetworkUpgrade::Nu5);
+        info.warnings.push(Diagnostic {
+            code: "protocol_inactive".to_string(),
+            message: match nu5 {
+                Some(nu5) => format!(
+                    "Orchard activates at height {} (NU5); the Orchard component sees nothing \
+                     at {}",
+                    nu5, height
+                ),
+                None => "Orchard (NU5) never activates on this network".to_string(),
+            },
+        });
+    }
+
+    let branch_id = params.branch_table().branch_id_for_height(params, at);
+    info.height = Some(height);
+    info.consensus_branch_id = Some(format!("{:08x}", branch_id));
+    Ok(())
+}
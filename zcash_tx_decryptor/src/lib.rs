@@ -0,0 +1,25 @@
+//! Decryption core of `zcash-tx-decryptor`: transaction parsing, trial
+//! decryption against UFVKs and the report views.
+//!
+//! Nothing here depends on tokio, clap or other CLI-only crates, so the core
+//! also builds for `wasm32-unknown-unknown` (see the `wasm` feature) and as a
+//! C library (the `ffi` feature).
+
+pub mod coinbase;
+pub mod deadline;
+pub mod decrypt;
+pub mod embed;
+pub mod exit;
+pub mod golden;
+pub mod models;
+pub mod network;
+pub mod parse;
+pub mod policy;
+pub mod reference;
+pub mod tags;
+pub mod txid;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,49 @@
+//! Diagnostics on stderr (`-v`, `--log-format`): warnings, progress and, when
+//! asked for, what the fetch, parse and decrypt stages are doing. Reports and
+//! events never go through here, so stdout stays machine-readable at any
+//! verbosity.
+
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// How log lines are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One human-readable line per event
+    #[default]
+    Text,
+    /// One JSON object per event, with the fields of the spans it happened in
+    Json,
+}
+
+/// Parse a `--log-format` value.
+pub fn parse_log_format(s: &str) -> Result<LogFormat, String> {
+    match s {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        other => Err(format!(
+            "unknown log format '{}' (expected text or json)",
+            other
+        )),
+    }
+}
+
+/// Install the global subscriber. `verbose` counts `-v` flags: warnings and
+/// progress by default, per-stage detail with `-v`, everything with `-vv`.
+/// `RUST_LOG`, when set, takes precedence.
+pub fn init(verbose: u8, format: LogFormat) {
+    let level = match verbose {
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("warn,zcash_tx_decryptor={}", level)));
+    let builder = fmt().with_env_filter(filter).with_writer(std::io::stderr);
+
+    match format {
+        // Bare lines by default; timestamps and targets once -v asks for detail
+        LogFormat::Text if verbose == 0 => builder.without_time().with_target(false).init(),
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_span_list(true).init(),
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use zcash_primitives::consensus::BlockHeight;
+
+use crate::{network::ChainParams, parse};
+
+/// Serialization version written by `mempool.dat` dumps.
+const MEMPOOL_DUMP_VERSION: u64 = 1;
+
+/// One pending transaction taken from a mempool dump.
+pub struct MempoolEntry {
+    /// TXID as recorded in the dump, if the format carries one
+    pub txid: Option<String>,
+
+    /// Raw transaction bytes
+    pub tx_bytes: Vec<u8>,
+}
+
+/// `{"txid": ..., "hex": ...}` as returned by `getrawtransaction <txid> 1`.
+/// Other fields of the verbose RPC result are ignored.
+#[derive(Deserialize)]
+struct RawTxEntry {
+    txid: String,
+    hex: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonDump {
+    /// `[{"txid": ..., "hex": ...}, ...]`
+    List(Vec<RawTxEntry>),
+    /// `{"<txid>": "<hex>", ...}`
+    Map(BTreeMap<String, String>),
+}
+
+/// Load every transaction from a mempool dump.
+///
+/// Two formats are accepted, detected from the first non-whitespace byte:
+///
+/// * JSON, built from `getrawmempool` + `getrawtransaction`: either a list of
+///   `{"txid", "hex"}` objects or an object mapping TXID to raw hex.
+/// * A binary `mempool.dat`-style file: `u64` version (1), `u64` count, then per
+///   entry the serialized transaction followed by `i64` time and `i64` fee delta.
+///   Trailing data (the fee-delta map) is ignored.
+///
+/// Pre-v5 transactions in a binary dump are parsed with the branch ID in effect
+/// at `height`, since their length is only known once they have been parsed.
+pub fn load_mempool_dump(
+    path: &Path,
+    params: &ChainParams,
+    height: BlockHeight,
+    branch_id_override: Option<u32>,
+) -> Result<Vec<MempoolEntry>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read mempool dump {}", path.display()))?;
+
+    match data.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'[') | Some(b'{') => parse_json_dump(&data),
+        Some(_) => parse_binary_dump(&data, params, height, branch_id_override),
+        None => Err(anyhow!("Mempool dump {} is empty", path.display())),
+    }
+}
+
+fn parse_json_dump(data: &[u8]) -> Result<Vec<MempoolEntry>> {
+    let dump: JsonDump = serde_json::from_slice(data).context("Invalid JSON mempool dump")?;
+    let pairs: Vec<(String, String)> = match dump {
+        JsonDump::List(entries) => entries.into_iter().map(|e| (e.txid, e.hex)).collect(),
+        JsonDump::Map(map) => map.into_iter().collect(),
+    };
+
+    pairs
+        .into_iter()
+        .map(|(txid, hex)| {
+            let tx_bytes = hex::decode(hex.trim())
+                .with_context(|| format!("Raw transaction hex for {} is invalid", txid))?;
+            Ok(MempoolEntry {
+                txid: Some(txid),
+                tx_bytes,
+            })
+        })
+        .collect()
+}
+
+fn parse_binary_dump(
+    data: &[u8],
+    params: &ChainParams,
+    height: BlockHeight,
+    branch_id_override: Option<u32>,
+) -> Result<Vec<MempoolEntry>> {
+    let read_u64 = |pos: usize| -> Result<u64> {
+        data.get(pos..pos + 8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| anyhow!("Mempool dump truncated at byte {}", pos))
+    };
+
+    let version = read_u64(0)?;
+    if version != MEMPOOL_DUMP_VERSION {
+        return Err(anyhow!(
+            "Unsupported mempool dump version {} (expected {})",
+            version,
+            MEMPOOL_DUMP_VERSION
+        ));
+    }
+    let count = read_u64(8)?;
+
+    let table = params.branch_table();
+    let mut pos = 16;
+    let mut entries = Vec::new();
+    for i in 0..count {
+        let parsed = parse::parse_transaction(&data[pos..], params, height, &table, branch_id_override)
+            .with_context(|| format!("Failed to parse mempool entry {} at byte {}", i, pos))?;
+        entries.push(MempoolEntry {
+            txid: None,
+            tx_bytes: data[pos..pos + parsed.size].to_vec(),
+        });
+
+        // Skip nTime and nFeeDelta
+        pos += parsed.size;
+        read_u64(pos + 8)?;
+        pos += 16;
+    }
+
+    Ok(entries)
+}
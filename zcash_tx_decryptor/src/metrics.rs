@@ -0,0 +1,182 @@
+//! Prometheus metrics for the long-running modes (`watch`, `scan-compact`,
+//! `sync`), served in the text exposition format on `--metrics-addr`.
+
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use axum::{routing::get, Router};
+
+/// Process-wide metrics. Counters are updated whether or not they are served.
+pub static METRICS: Metrics = Metrics::new();
+
+/// Upper bounds of the RPC latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub struct Metrics {
+    /// Blocks scanned (`scan-compact`, `sync`)
+    pub blocks_scanned: AtomicU64,
+    /// Full transactions run through trial decryption
+    pub transactions_decrypted: AtomicU64,
+    /// Outputs that decrypted with one of our keys
+    pub outputs_found: AtomicU64,
+    /// Transactions that failed to parse or decrypt
+    pub decrypt_errors: AtomicU64,
+    /// Chain tip height last reported by the node
+    pub chain_tip_height: AtomicU64,
+    /// Highest block height scanned so far
+    pub scanned_height: AtomicU64,
+    /// Per-method RPC latency
+    rpc_latency: Mutex<Vec<(String, Histogram)>>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Metrics {
+            blocks_scanned: AtomicU64::new(0),
+            transactions_decrypted: AtomicU64::new(0),
+            outputs_found: AtomicU64::new(0),
+            decrypt_errors: AtomicU64::new(0),
+            chain_tip_height: AtomicU64::new(0),
+            scanned_height: AtomicU64::new(0),
+            rpc_latency: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record how long an RPC call to `method` took, successful or not.
+    pub fn observe_rpc(&self, method: &str, elapsed: Duration) {
+        let Ok(mut latency) = self.rpc_latency.lock() else {
+            return;
+        };
+        let index = match latency.iter().position(|(m, _)| m == method) {
+            Some(index) => index,
+            None => {
+                latency.push((method.to_string(), Histogram::default()));
+                latency.len() - 1
+            }
+        };
+
+        let histogram = &mut latency[index].1;
+        let secs = elapsed.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| secs <= le) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.count += 1;
+        histogram.sum += secs;
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "ztd_blocks_scanned_total",
+                "counter",
+                "Blocks scanned",
+                &self.blocks_scanned,
+            ),
+            (
+                "ztd_transactions_decrypted_total",
+                "counter",
+                "Transactions trial-decrypted",
+                &self.transactions_decrypted,
+            ),
+            (
+                "ztd_outputs_found_total",
+                "counter",
+                "Outputs decrypted with our keys",
+                &self.outputs_found,
+            ),
+            (
+                "ztd_decrypt_errors_total",
+                "counter",
+                "Transactions that failed to parse or decrypt",
+                &self.decrypt_errors,
+            ),
+            (
+                "ztd_chain_tip_height",
+                "gauge",
+                "Chain tip height reported by the node",
+                &self.chain_tip_height,
+            ),
+            (
+                "ztd_scanned_height",
+                "gauge",
+                "Highest block height scanned",
+                &self.scanned_height,
+            ),
+        ];
+        for (name, kind, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+
+        let name = "ztd_rpc_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Node RPC latency by method", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        if let Ok(latency) = self.rpc_latency.lock() {
+            for (method, histogram) in latency.iter() {
+                let mut cumulative = 0;
+                for (le, n) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                    cumulative += n;
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                        name, method, le, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                    name, method, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_sum{{method=\"{}\"}} {}",
+                    name, method, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_count{{method=\"{}\"}} {}",
+                    name, method, histogram.count
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Bind `addr` and serve `GET /metrics` in the background for the rest of
+/// the process.
+pub async fn spawn_server(addr: SocketAddr) -> Result<()> {
+    let app = Router::new().route("/metrics", get(|| async { METRICS.render() }));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics address {}", addr))?;
+    tracing::info!("Serving metrics on http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::warn!("metrics server stopped: {:#}", e);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! The `--format csv` view: one row per decrypted output.
+
+use super::TxReport;
+
+/// Column names, in row order.
+pub const HEADER: &str = "txid,height,account,protocol,index,transfer_type,direction,\
+amount_zats,memo,address,note_commitment,position,nullifier,recipient_address,account_label,\
+diversifier_index,counterparty,payment_reference,source,funding_stream,network,idempotency_key";
+
+/// CSV rows (without the header) for every output of `report`.
+pub fn rows(report: &TxReport) -> Vec<String> {
+    report
+        .outputs
+        .iter()
+        .map(|out| {
+            [
+                report.txid.clone(),
+                report.height.to_string(),
+                out.account.to_string(),
+                out.protocol.clone(),
+                out.index.to_string(),
+                out.transfer_type.clone(),
+                out.direction.clone(),
+                out.amount_zats.to_string(),
+                out.memo.trim_end_matches('\0').to_string(),
+                out.address.clone().unwrap_or_default(),
+                out.note_commitment.clone().unwrap_or_default(),
+                out.position.map(|p| p.to_string()).unwrap_or_default(),
+                out.nullifier.clone().unwrap_or_default(),
+                out.recipient_address.clone().unwrap_or_default(),
+                out.account_label.clone().unwrap_or_default(),
+                out.diversifier_index.map(|i| i.to_string()).unwrap_or_default(),
+                out.counterparty.clone().unwrap_or_default(),
+                out.payment_reference.clone().unwrap_or_default(),
+                out.source.clone().unwrap_or_default(),
+                out.funding_stream.clone().unwrap_or_default(),
+                report.network.unwrap_or_default().to_string(),
+                out.idempotency_key.clone(),
+            ]
+            .iter()
+            .map(|field| escape(field))
+            .collect::<Vec<_>>()
+            .join(",")
+        })
+        .collect()
+}
+
+/// Quote a field if it contains a delimiter, quote or line break (RFC 4180).
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,49 @@
+//! The `--format esplora` view: the transaction shape of the Esplora REST API
+//! (`GET /tx/:txid`), for tooling built around block explorers.
+//!
+//! Only decrypted shielded outputs appear in `vout`, typed `sapling` or
+//! `orchard`. `status` is omitted because the decryptor doesn't know whether
+//! the transaction is mined.
+
+use serde::Serialize;
+
+use super::TxReport;
+
+#[derive(Debug, Serialize)]
+pub struct EsploraTx {
+    pub txid: String,
+    pub size: usize,
+    pub vout: Vec<EsploraVout>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EsploraVout {
+    pub scriptpubkey_type: String,
+    pub scriptpubkey_address: Option<String>,
+    pub value: i64,
+    /// Not part of Esplora; shielded outputs carry a memo
+    pub memo: String,
+}
+
+impl From<&TxReport> for EsploraTx {
+    fn from(report: &TxReport) -> Self {
+        EsploraTx {
+            txid: report.txid.clone(),
+            size: report.tx_size_bytes,
+            vout: report
+                .outputs
+                .iter()
+                .map(|out| EsploraVout {
+                    scriptpubkey_type: out.protocol.to_lowercase(),
+                    // The address paid: ours, or the recipient's for outputs we sent
+                    scriptpubkey_address: out
+                        .address
+                        .clone()
+                        .or_else(|| out.recipient_address.clone()),
+                    value: out.amount_zats,
+                    memo: out.memo.trim_end_matches('\0').to_string(),
+                })
+                .collect(),
+        }
+    }
+}
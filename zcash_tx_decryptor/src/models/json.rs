@@ -0,0 +1,191 @@
+//! The `--format json` / HTTP API view: [`TransactionDetails`].
+
+use anyhow::{anyhow, Error, Result};
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+use super::{BundleStats, DecryptStatus, Diagnostic, TransactionDetails, TxReport, Zec};
+use crate::exit::ErrorKind;
+
+impl TryFrom<&TxReport> for TransactionDetails {
+    type Error = Error;
+
+    fn try_from(report: &TxReport) -> Result<Self> {
+        let txid = &report.txid;
+
+        let incoming_zats = report.total_zats("Incoming");
+        let change_zats = report.total_zats("WalletInternal");
+        let outgoing_zats = report.total_zats("Outgoing");
+        let total_received_zats = incoming_zats.saturating_add(change_zats);
+
+        let to_i64 = |zats: u64, what: &str| {
+            i64::try_from(zats).map_err(|_| anyhow!("{} amount exceeds i64 range", what))
+        };
+        let amount_zats = to_i64(total_received_zats, "Total received")?;
+        let incoming_zats = to_i64(incoming_zats, "Incoming")?;
+        let change_zats = to_i64(change_zats, "Change")?;
+        let outgoing_zats = to_i64(outgoing_zats, "Outgoing")?;
+
+        Ok(TransactionDetails {
+            transaction_id: txid.clone(),
+            transaction_hash: short_hash(txid),
+            amount_zats,
+            amount_zec: Zec::from_zats(amount_zats),
+            incoming_zats,
+            incoming_zec: Zec::from_zats(incoming_zats),
+            change_zats,
+            change_zec: Zec::from_zats(change_zats),
+            outgoing_zats,
+            outgoing_zec: Zec::from_zats(outgoing_zats),
+            // Fee calculation requires wallet context; we leave it as zero for now.
+            fee_zats: 0,
+            fee_zec: Zec::default(),
+            // The processing time only stands in when the block time is unknown
+            timestamp: report.block_time.unwrap_or(report.decrypted_at),
+            block_height: report.height,
+            network: report.network.map(str::to_string),
+            block_time: report.block_time,
+            block_hash: report.block_hash.clone(),
+            confirmations: report.confirmations,
+            is_final: report.is_final,
+            outputs: report.outputs.clone(),
+            tx_size_bytes: report.tx_size_bytes,
+            tx_version: report.tx_version,
+            opaque_components: report.opaque.clone(),
+            bundles: report.bundles.clone(),
+            fiat: report.fiat.clone(),
+            filtered: report.filtered.clone(),
+            key_capabilities: report.key_capabilities.clone(),
+            status: if report.outputs.is_empty() {
+                DecryptStatus::NoMatches
+            } else {
+                DecryptStatus::Decrypted
+            },
+            errors: Vec::new(),
+            warnings: report.warnings.clone(),
+        })
+    }
+}
+
+/// Report for a transaction that could not be processed, so JSON consumers
+/// get the failure in the same shape as a result.
+pub fn failure(txid: &str, height: u32, error: &Error) -> TransactionDetails {
+    let (status, code) = match error.downcast_ref::<ErrorKind>() {
+        Some(ErrorKind::Parse) => (DecryptStatus::ParseError, "parse_error"),
+        Some(ErrorKind::Network) => (DecryptStatus::NetworkError, "network_error"),
+        None => (DecryptStatus::Error, "error"),
+    };
+
+    TransactionDetails {
+        transaction_id: txid.to_string(),
+        transaction_hash: short_hash(txid),
+        amount_zats: 0,
+        amount_zec: Zec::default(),
+        incoming_zats: 0,
+        incoming_zec: Zec::default(),
+        change_zats: 0,
+        change_zec: Zec::default(),
+        outgoing_zats: 0,
+        outgoing_zec: Zec::default(),
+        fee_zats: 0,
+        fee_zec: Zec::default(),
+        timestamp: Utc::now(),
+        block_height: height,
+        network: None,
+        block_time: None,
+        block_hash: None,
+        confirmations: None,
+        is_final: None,
+        outputs: Vec::new(),
+        tx_size_bytes: 0,
+        tx_version: 0,
+        opaque_components: Vec::new(),
+        bundles: BundleStats::default(),
+        fiat: None,
+        filtered: None,
+        key_capabilities: None,
+        status,
+        errors: vec![Diagnostic {
+            code: code.to_string(),
+            message: format!("{:#}", error),
+        }],
+        warnings: Vec::new(),
+    }
+}
+
+/// `details` as `--canonical` prints it, the same bytes for the same
+/// transaction on every run: outputs sorted by pool and index, object keys
+/// sorted, amounts as strings of zatoshis without the `_zec` copies,
+/// and no processing time.
+pub fn canonical(details: &TransactionDetails) -> Result<Value> {
+    let mut details = details.clone();
+    details.outputs.sort_by(|a, b| {
+        (&a.protocol, a.index, a.account).cmp(&(&b.protocol, b.index, b.account))
+    });
+    let mut value = serde_json::to_value(&details)?;
+    if let Value::Object(map) = &mut value {
+        // The processing time unless the block time is known, which is also
+        // in `block_time`
+        map.remove("timestamp");
+        if let Some(Value::Object(fiat)) = map.get_mut("fiat") {
+            fiat.remove("price_time");
+        }
+    }
+    Ok(canonicalize(value))
+}
+
+/// `value` with its `*_zec` amounts as JSON numbers, the report shape from
+/// before amounts became exact strings (`--legacy-amounts`).
+pub fn legacy_amounts(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| match value {
+                    Value::String(text) if key.ends_with("_zec") => {
+                        let value = match text.parse::<Zec>() {
+                            Ok(zec) => serde_json::Number::from_f64(zec.as_f64())
+                                .map_or(Value::String(text), Value::Number),
+                            Err(_) => Value::String(text),
+                        };
+                        (key, value)
+                    }
+                    value => (key, legacy_amounts(value)),
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(legacy_amounts).collect()),
+        other => other,
+    }
+}
+
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<(String, Value)> = map
+                .into_iter()
+                .filter(|(key, _)| !key.ends_with("_zec"))
+                .map(|(key, value)| match value {
+                    Value::Number(zats) if key.ends_with("_zats") => {
+                        (key, Value::String(zats.to_string()))
+                    }
+                    value => (key, canonicalize(value)),
+                })
+                .collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(fields.into_iter().collect::<Map<_, _>>())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        // Fiat amounts and prices, in their shortest exact form
+        Value::Number(number) if number.is_f64() => Value::String(number.to_string()),
+        other => other,
+    }
+}
+
+/// First and last 16 characters of a TXID; shorter strings (e.g. a malformed
+/// --txid in a failure report) are kept whole.
+fn short_hash(txid: &str) -> String {
+    match (txid.get(0..16), txid.get(txid.len().saturating_sub(16)..)) {
+        (Some(head), Some(tail)) if txid.len() > 32 => format!("{}...{}", head, tail),
+        _ => txid.to_string(),
+    }
+}
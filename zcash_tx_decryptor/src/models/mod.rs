@@ -0,0 +1,105 @@
+//! The core decryption model and its per-format views.
+//!
+//! [`TxReport`] is what the decryptor produces. Each output format converts
+//! from it in its own submodule, so a field added for one format doesn't
+//! touch the others. The JSON shapes live in `zcash-decryptor-types` so
+//! consumers can share them.
+
+use chrono::{DateTime, Utc};
+
+pub mod csv;
+pub mod esplora;
+pub mod json;
+pub mod pretty;
+pub mod protobuf;
+
+pub use zcash_decryptor_types::{
+    AccountFlow, AccountKey, AddressInfo, AddressReceiver, AuditedNote, Balance, BundleStats,
+    DecryptStatus, DetectedSpend, Diagnostic, FiatValue, FilteredEvent, FilteredOutputs,
+    FlowReport, KeyCapabilities, KeyInfo, OpaqueComponent, OutputDetail, OutputInfo,
+    PaymentDisclosure, PaymentEvent, ProgressEvent, ReceiverOwner, ReorgEvent, SignedAttestation,
+    StoredOutput, TransactionDetails, UnspentAttestation, Zec,
+};
+pub use zcash_decryptor_types::idempotency_key;
+
+/// Outputs of one transaction that decrypted with our keys.
+#[derive(Debug, Clone)]
+pub struct TxReport {
+    pub txid: String,
+
+    /// Block height (a best-effort hint unless the transaction came from a
+    /// scanned block)
+    pub height: u32,
+
+    pub tx_size_bytes: usize,
+
+    /// Transaction format version (1-5)
+    pub tx_version: u32,
+
+    /// When this tool decrypted the transaction
+    pub decrypted_at: DateTime<Utc>,
+
+    /// Block time, when known from the block itself or the node
+    pub block_time: Option<DateTime<Utc>>,
+
+    /// Hash of the block the transaction was mined in, when known
+    pub block_hash: Option<String>,
+
+    /// Network the transaction was decrypted on ("main", "test" or
+    /// "regtest"); None for synthetic reports
+    pub network: Option<&'static str>,
+
+    /// Confirmations and finality, when a node was asked (`--rpc-url`)
+    pub confirmations: Option<u32>,
+    pub is_final: Option<bool>,
+
+    pub outputs: Vec<OutputInfo>,
+
+    /// Components listed but not decrypted (Sprout JoinSplits)
+    pub opaque: Vec<OpaqueComponent>,
+
+    /// Structure of the transaction's bundles
+    pub bundles: BundleStats,
+
+    /// Total value of the transparent outputs, which with the pools' value
+    /// balances gives the fee of a transaction without transparent inputs
+    pub transparent_output_zats: i64,
+
+    /// Nullifiers revealed by the transaction's Sapling spends and Orchard
+    /// actions, whoever's notes they spend
+    pub spent_nullifiers: Vec<[u8; 32]>,
+
+    /// Fiat equivalents of the amounts (`--fiat`)
+    pub fiat: Option<FiatValue>,
+
+    /// What the output policy left out (`--summarize-filtered`)
+    pub filtered: Option<FilteredOutputs>,
+
+    /// What the viewing keys can decrypt; None for synthetic reports
+    pub key_capabilities: Option<KeyCapabilities>,
+
+    /// Problems that did not stop decryption
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl TxReport {
+    /// Total value of outputs with the given transfer type (`Incoming`,
+    /// `WalletInternal` or `Outgoing`).
+    pub fn total_zats(&self, transfer_type: &str) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|out| out.transfer_type == transfer_type)
+            .map(|out| out.amount_zats.unsigned_abs())
+            .fold(0, u64::saturating_add)
+    }
+}
+
+/// Zatoshis as exact ZEC, for display.
+pub fn zec(zats: u64) -> Zec {
+    Zec::from_zats(i64::try_from(zats).unwrap_or(i64::MAX))
+}
+
+/// Zatoshis as a float of ZEC, for price arithmetic only.
+pub fn zats_to_zec(zats: u64) -> f64 {
+    zats as f64 / 100_000_000.0
+}
@@ -0,0 +1,190 @@
+//! The `--format pretty` view: a boxed, human-readable summary.
+
+use std::fmt;
+
+use super::{zec, TxReport, Zec};
+
+/// Renders a [`TxReport`] for the terminal via `Display`.
+pub struct PrettyReport<'a>(pub &'a TxReport);
+
+impl fmt::Display for PrettyReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.0;
+        let txid = &report.txid;
+        let incoming_zats = report.total_zats("Incoming");
+        let change_zats = report.total_zats("WalletInternal");
+        let outgoing_zats = report.total_zats("Outgoing");
+        let received_zats = incoming_zats.saturating_add(change_zats);
+
+        writeln!(f, "\n╔════════════════════════════════════════════════════════════════╗")?;
+        writeln!(f, "║         ZCASH TRANSACTION ANALYSIS                            ║")?;
+        writeln!(f, "╚════════════════════════════════════════════════════════════════╝\n")?;
+
+        writeln!(f, "Transaction Information:")?;
+        writeln!(f, "  ID (TXID):              {}", txid)?;
+        writeln!(f, "  Hash:                   {}...{}", &txid[0..16], &txid[txid.len() - 16..])?;
+        writeln!(f, "  Size:                   {} bytes", report.tx_size_bytes)?;
+        writeln!(f, "  Version:                {}", report.tx_version)?;
+        if !report.opaque.is_empty() {
+            writeln!(
+                f,
+                "  Sprout JoinSplits:      {} (not decryptable with a UFVK)",
+                report.opaque.len()
+            )?;
+        }
+
+        let bundles = &report.bundles;
+        writeln!(f, "\nBundles:")?;
+        writeln!(
+            f,
+            "  Transparent:            {} in, {} out",
+            bundles.transparent_inputs, bundles.transparent_outputs
+        )?;
+        writeln!(
+            f,
+            "  Sapling:                {} spends, {} outputs, value balance {} zats",
+            bundles.sapling_spends, bundles.sapling_outputs, bundles.sapling_value_balance_zats
+        )?;
+        writeln!(
+            f,
+            "  Orchard:                {} actions, value balance {} zats",
+            bundles.orchard_actions, bundles.orchard_value_balance_zats
+        )?;
+
+        if let Some(capabilities) = &report.key_capabilities {
+            writeln!(f, "\nKey Capabilities:")?;
+            writeln!(
+                f,
+                "  Viewable pools:         {}",
+                capabilities.viewable_pools.join(", ")
+            )?;
+            for key in &capabilities.accounts {
+                let account = match &key.account_label {
+                    Some(label) => format!("{} ({}):", key.account, label),
+                    None => format!("{}:", key.account),
+                };
+                writeln!(f, "  Account {:<15} {}", account, key.components.join(", "))?;
+            }
+            for note in report.warnings.iter().filter(|w| w.code == "pool_not_viewable") {
+                writeln!(f, "  Note:                   {}", note.message)?;
+            }
+        }
+
+        writeln!(f, "\nAmount (UFVK-related outputs):")?;
+        writeln!(f, "  Total received:         {} ZEC", zec(received_zats))?;
+        writeln!(f, "  Total received:         {} zats", received_zats)?;
+        writeln!(f, "  Incoming (external):    {} ZEC", zec(incoming_zats))?;
+        writeln!(f, "  Incoming (external):    {} zats", incoming_zats)?;
+        writeln!(f, "  Change (internal):      {} ZEC", zec(change_zats))?;
+        writeln!(f, "  Change (internal):      {} zats", change_zats)?;
+        writeln!(f, "  Outgoing (OVK view):    {} ZEC", zec(outgoing_zats))?;
+        writeln!(f, "  Outgoing (OVK view):    {} zats", outgoing_zats)?;
+
+        if let Some(fiat) = &report.fiat {
+            let currency = fiat.currency.to_uppercase();
+            writeln!(
+                f,
+                "\nFiat ({} at {} per ZEC, {}, {}):",
+                currency,
+                fiat.price,
+                fiat.price_source,
+                fiat.price_time.date_naive()
+            )?;
+            writeln!(f, "  Total received:         {:.2} {}", fiat.amount, currency)?;
+            writeln!(f, "  Incoming (external):    {:.2} {}", fiat.incoming, currency)?;
+            writeln!(f, "  Change (internal):      {:.2} {}", fiat.change, currency)?;
+            writeln!(f, "  Outgoing (OVK view):    {:.2} {}", fiat.outgoing, currency)?;
+        }
+
+        writeln!(f, "\nFees (not computed – view-only context):")?;
+        writeln!(f, "  Fee:                    0 ZEC")?;
+        writeln!(f, "  Fee:                    0 zats")?;
+
+        writeln!(f, "\nTiming:")?;
+        writeln!(f, "  Timestamp (local run):  {}", report.decrypted_at)?;
+        writeln!(f, "  Block Height (hint):    {}", report.height)?;
+        if let Some(block_time) = report.block_time {
+            writeln!(f, "  Block Time:             {}", block_time)?;
+        }
+        if let Some(block_hash) = &report.block_hash {
+            writeln!(f, "  Block Hash:             {}", block_hash)?;
+        }
+        if let Some(network) = report.network {
+            writeln!(f, "  Network:                {}", network)?;
+        }
+        if let Some(confirmations) = report.confirmations {
+            writeln!(f, "  Confirmations:          {}", confirmations)?;
+        }
+        if let Some(is_final) = report.is_final {
+            writeln!(f, "  Final:                  {}", if is_final { "yes" } else { "no" })?;
+        }
+
+        if let Some(filtered) = &report.filtered {
+            writeln!(f, "\nFiltered (not listed):")?;
+            if filtered.dust_outputs > 0 {
+                writeln!(
+                    f,
+                    "  Below minimum value:    {} output(s), {} zats",
+                    filtered.dust_outputs, filtered.dust_zats
+                )?;
+            }
+            if filtered.unconfirmed_outputs > 0 {
+                writeln!(
+                    f,
+                    "  Too few confirmations:  {} output(s), {} zats",
+                    filtered.unconfirmed_outputs, filtered.unconfirmed_zats
+                )?;
+            }
+        }
+
+        if !report.outputs.is_empty() {
+            writeln!(f, "\nDecrypted Outputs ({}):", report.outputs.len())?;
+            for (idx, output) in report.outputs.iter().enumerate() {
+                writeln!(f, "  Output #{}:", idx + 1)?;
+                writeln!(f, "    Protocol:           {}", output.protocol)?;
+                if let Some(label) = &output.account_label {
+                    writeln!(f, "    Account:            {} ({})", label, output.account)?;
+                }
+                writeln!(f, "    Transfer Type:      {}", output.transfer_type)?;
+                writeln!(f, "    Direction:          {}", output.direction)?;
+                writeln!(f, "    Index:              {}", output.index)?;
+                if let Some(recipient) = &output.recipient_address {
+                    writeln!(f, "    Recipient:          {}", recipient)?;
+                }
+                if let Some(counterparty) = &output.counterparty {
+                    writeln!(f, "    Counterparty:       {}", counterparty)?;
+                }
+                if let Some(reference) = &output.payment_reference {
+                    writeln!(f, "    Payment Reference:  {}", reference)?;
+                }
+                if let Some(source) = &output.source {
+                    match &output.funding_stream {
+                        Some(stream) => {
+                            writeln!(f, "    Source:             {} ({})", source, stream)?
+                        }
+                        None => writeln!(f, "    Source:             {}", source)?,
+                    }
+                }
+                if let Some(index) = output.diversifier_index {
+                    writeln!(f, "    Address Index:      {}", index)?;
+                }
+                writeln!(f, "    Amount:             {} zats", output.amount_zats)?;
+                writeln!(
+                    f,
+                    "    Amount:             {} ZEC",
+                    Zec::from_zats(output.amount_zats)
+                )?;
+                if !output.memo.is_empty() {
+                    writeln!(f, "    Memo:               {}", output.memo)?;
+                }
+            }
+        } else {
+            writeln!(
+                f,
+                "\nNo outputs in this transaction could be decrypted with the provided UFVK."
+            )?;
+        }
+
+        writeln!(f, "\n╚════════════════════════════════════════════════════════════════╝\n")
+    }
+}
@@ -0,0 +1,102 @@
+//! The `--format protobuf` view: length-delimited protobuf messages, one per
+//! transaction, for consumers that want a compact binary stream.
+
+use prost::Message;
+
+use super::TxReport;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TxReportProto {
+    #[prost(string, tag = "1")]
+    pub txid: String,
+    #[prost(uint32, tag = "2")]
+    pub height: u32,
+    #[prost(uint64, tag = "3")]
+    pub tx_size_bytes: u64,
+    /// Unix seconds
+    #[prost(int64, tag = "4")]
+    pub decrypted_at: i64,
+    #[prost(message, repeated, tag = "5")]
+    pub outputs: Vec<OutputProto>,
+    #[prost(uint32, tag = "6")]
+    pub tx_version: u32,
+    /// "main", "test" or "regtest"
+    #[prost(string, optional, tag = "7")]
+    pub network: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct OutputProto {
+    #[prost(string, tag = "1")]
+    pub protocol: String,
+    #[prost(int64, tag = "2")]
+    pub amount_zats: i64,
+    #[prost(uint64, tag = "3")]
+    pub index: u64,
+    #[prost(uint32, tag = "4")]
+    pub account: u32,
+    #[prost(string, tag = "5")]
+    pub transfer_type: String,
+    #[prost(string, tag = "6")]
+    pub memo: String,
+    #[prost(string, optional, tag = "7")]
+    pub address: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub note_commitment: Option<String>,
+    #[prost(uint64, optional, tag = "9")]
+    pub position: Option<u64>,
+    #[prost(string, optional, tag = "10")]
+    pub nullifier: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub recipient_address: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub account_label: Option<String>,
+    #[prost(uint64, optional, tag = "13")]
+    pub diversifier_index: Option<u64>,
+    #[prost(string, optional, tag = "14")]
+    pub counterparty: Option<String>,
+    #[prost(string, optional, tag = "15")]
+    pub payment_reference: Option<String>,
+    #[prost(string, tag = "16")]
+    pub idempotency_key: String,
+}
+
+impl From<&TxReport> for TxReportProto {
+    fn from(report: &TxReport) -> Self {
+        TxReportProto {
+            txid: report.txid.clone(),
+            height: report.height,
+            tx_size_bytes: report.tx_size_bytes as u64,
+            decrypted_at: report.decrypted_at.timestamp(),
+            tx_version: report.tx_version,
+            network: report.network.map(str::to_string),
+            outputs: report
+                .outputs
+                .iter()
+                .map(|out| OutputProto {
+                    protocol: out.protocol.clone(),
+                    amount_zats: out.amount_zats,
+                    index: out.index as u64,
+                    account: out.account,
+                    transfer_type: out.transfer_type.clone(),
+                    memo: out.memo.clone(),
+                    address: out.address.clone(),
+                    note_commitment: out.note_commitment.clone(),
+                    position: out.position,
+                    nullifier: out.nullifier.clone(),
+                    recipient_address: out.recipient_address.clone(),
+                    account_label: out.account_label.clone(),
+                    diversifier_index: out.diversifier_index,
+                    counterparty: out.counterparty.clone(),
+                    payment_reference: out.payment_reference.clone(),
+                    idempotency_key: out.idempotency_key.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Encode `report` as a length-delimited `TxReportProto`.
+pub fn encode(report: &TxReport) -> Vec<u8> {
+    TxReportProto::from(report).encode_length_delimited_to_vec()
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use zcash_primitives::consensus::BlockHeight;
+use zcash_protocol::{
+    consensus::{Network, NetworkType, NetworkUpgrade, Parameters},
+    local_consensus::LocalNetwork,
+};
+
+use crate::parse::{BranchEntry, BranchTable};
+
+/// Consensus parameters for the chain a transaction belongs to.
+///
+/// Mainnet and testnet use librustzcash's built-in parameters. Regtest and
+/// private testnets carry their own activation heights, loaded from a
+/// network parameters file (or zcashd's regtest defaults).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainParams {
+    Standard(Network),
+    Custom {
+        network_type: NetworkType,
+        heights: LocalNetwork,
+        nu6_1: Option<BlockHeight>,
+    },
+}
+
+impl Parameters for ChainParams {
+    fn network_type(&self) -> NetworkType {
+        match self {
+            ChainParams::Standard(network) => network.network_type(),
+            ChainParams::Custom { network_type, .. } => *network_type,
+        }
+    }
+
+    fn activation_height(&self, nu: NetworkUpgrade) -> Option<BlockHeight> {
+        match self {
+            ChainParams::Standard(network) => network.activation_height(nu),
+            ChainParams::Custom { heights, .. } => heights.activation_height(nu),
+        }
+    }
+}
+
+impl ChainParams {
+    /// Regtest with every upgrade active from height 1, as zcashd does when
+    /// started with `-nuparams` for all upgrades.
+    pub fn regtest() -> Self {
+        let h = Some(BlockHeight::from_u32(1));
+        ChainParams::Custom {
+            network_type: NetworkType::Regtest,
+            heights: LocalNetwork {
+                overwinter: h,
+                sapling: h,
+                blossom: h,
+                heartwood: h,
+                canopy: h,
+                nu5: h,
+                nu6: h,
+            },
+            nu6_1: h,
+        }
+    }
+
+    /// Name of the network type, as in reports and `--network`.
+    pub fn name(&self) -> &'static str {
+        network_name(self.network_type())
+    }
+
+    /// Upgrades this network knows about that librustzcash does not.
+    pub fn branch_table(&self) -> BranchTable {
+        let nu6_1 = match self {
+            ChainParams::Standard(Network::MainNetwork) => Some(3_146_400),
+            ChainParams::Standard(Network::TestNetwork) => Some(2_976_640),
+            ChainParams::Custom { nu6_1, .. } => nu6_1.map(u32::from),
+        };
+
+        let mut table = BranchTable::empty();
+        table.insert(BranchEntry::nu6_1(nu6_1));
+        table
+    }
+}
+
+/// Network type implied by a UFVK's human-readable prefix.
+pub fn network_type_from_ufvk(ufvk: &str) -> Option<NetworkType> {
+    // Longest prefixes first: "uviewtest1" also starts with "uview".
+    if ufvk.starts_with("uviewregtest1") {
+        Some(NetworkType::Regtest)
+    } else if ufvk.starts_with("uviewtest1") {
+        Some(NetworkType::Test)
+    } else if ufvk.starts_with("uview1") {
+        Some(NetworkType::Main)
+    } else {
+        None
+    }
+}
+
+/// Parse a `--network` value.
+pub fn parse_network_type(s: &str) -> Result<NetworkType, String> {
+    match s {
+        "main" | "mainnet" => Ok(NetworkType::Main),
+        "test" | "testnet" => Ok(NetworkType::Test),
+        "regtest" => Ok(NetworkType::Regtest),
+        other => Err(format!(
+            "unknown network '{}' (expected main, test or regtest)",
+            other
+        )),
+    }
+}
+
+/// Name of a network as accepted by `--network`.
+pub fn network_name(network_type: NetworkType) -> &'static str {
+    match network_type {
+        NetworkType::Main => "main",
+        NetworkType::Test => "test",
+        NetworkType::Regtest => "regtest",
+    }
+}
+
+/// Contents of a `--network-params` TOML file.
+///
+/// Address and key encodings (HRPs, Base58 prefixes) and the ZIP-32 coin type
+/// are fixed by librustzcash per network type, so they follow `network` rather
+/// than being configurable individually.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkParamsFile {
+    /// Base network type: "main", "test" or "regtest"
+    network: String,
+
+    /// Network upgrade activation heights; omitted upgrades never activate
+    #[serde(default)]
+    activation_heights: ActivationHeights,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ActivationHeights {
+    overwinter: Option<u32>,
+    sapling: Option<u32>,
+    blossom: Option<u32>,
+    heartwood: Option<u32>,
+    canopy: Option<u32>,
+    nu5: Option<u32>,
+    nu6: Option<u32>,
+    nu6_1: Option<u32>,
+}
+
+/// Load custom consensus parameters from a TOML file.
+pub fn load_network_params(path: &Path) -> Result<ChainParams> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read network parameters file {}", path.display()))?;
+    let file: NetworkParamsFile = toml::from_str(&text)
+        .with_context(|| format!("Invalid network parameters file {}", path.display()))?;
+
+    let network_type = parse_network_type(&file.network).map_err(|e| anyhow!(e))?;
+    let h = &file.activation_heights;
+    let height = |v: Option<u32>| v.map(BlockHeight::from_u32);
+
+    Ok(ChainParams::Custom {
+        network_type,
+        heights: LocalNetwork {
+            overwinter: height(h.overwinter),
+            sapling: height(h.sapling),
+            blossom: height(h.blossom),
+            heartwood: height(h.heartwood),
+            canopy: height(h.canopy),
+            nu5: height(h.nu5),
+            nu6: height(h.nu6),
+        },
+        nu6_1: height(h.nu6_1),
+    })
+}
+
+/// Resolve the chain parameters from `--network`, `--network-params` and the
+/// UFVK prefix. Explicit choices must agree with the key's network.
+pub fn resolve_network(
+    network: Option<NetworkType>,
+    params_file: Option<&Path>,
+    ufvk: &str,
+) -> Result<ChainParams> {
+    let key_network = network_type_from_ufvk(ufvk).ok_or_else(|| {
+        anyhow!(
+            "Invalid UFVK format. Expected to start with 'uview1' (mainnet), \
+             'uviewtest1' (testnet) or 'uviewregtest1' (regtest)"
+        )
+    })?;
+
+    let params = select_network(network, params_file, key_network)?;
+    if params.network_type() != key_network {
+        return Err(anyhow!(
+            "UFVK is for {:?} but the selected network is {:?}",
+            key_network,
+            params.network_type()
+        ));
+    }
+
+    Ok(params)
+}
+
+/// Chain parameters from a --network-params file if given, otherwise for
+/// `network` (or `default`). Fails if both are given and disagree.
+pub fn select_network(
+    network: Option<NetworkType>,
+    params_file: Option<&Path>,
+    default: NetworkType,
+) -> Result<ChainParams> {
+    let params = match params_file {
+        Some(path) => load_network_params(path)?,
+        None => match network.unwrap_or(default) {
+            NetworkType::Main => ChainParams::Standard(Network::MainNetwork),
+            NetworkType::Test => ChainParams::Standard(Network::TestNetwork),
+            NetworkType::Regtest => ChainParams::regtest(),
+        },
+    };
+
+    if let Some(requested) = network {
+        if requested != params.network_type() {
+            return Err(anyhow!(
+                "--network {:?} conflicts with network parameters file ({:?})",
+                requested,
+                params.network_type()
+            ));
+        }
+    }
+
+    Ok(params)
+}
@@ -0,0 +1,395 @@
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context, Result};
+use zcash_primitives::{
+    consensus::BlockHeight,
+    transaction::{Transaction, TxId},
+};
+use zcash_protocol::consensus::{BranchId, NetworkUpgrade, Parameters};
+
+use crate::{exit::ErrorKind, network::ChainParams, txid};
+
+/// Header word of a v5 (ZIP 225) transaction: version 5 with the fOverwintered bit set.
+const V5_TX_HEADER: u32 = 0x8000_0005;
+
+/// Header words of v3 (Overwinter) and v4 (Sapling) transactions.
+const V3_TX_HEADER: u32 = 0x8000_0003;
+const V4_TX_HEADER: u32 = 0x8000_0004;
+
+/// Blocks between creation and nExpiryHeight that zcashd and the
+/// librustzcash builder give a transaction by default.
+const EXPIRY_DELTA: u32 = 40;
+
+/// A network upgrade that librustzcash does not know about yet.
+///
+/// Upgrades listed here keep a transaction format that librustzcash can already
+/// read, so their transactions are parsed as if they carried `parse_as`, while
+/// the TXID is still computed with the real `branch_id`.
+#[derive(Debug, Clone)]
+pub struct BranchEntry {
+    /// Consensus branch ID as committed to on chain
+    pub branch_id: u32,
+
+    /// Known branch whose transaction format this upgrade shares
+    pub parse_as: BranchId,
+
+    /// Activation height on this network (None if not scheduled)
+    pub activation_height: Option<u32>,
+}
+
+impl BranchEntry {
+    /// NU6.1: same transaction format as NU6, new branch ID for replay protection.
+    pub fn nu6_1(activation_height: Option<u32>) -> Self {
+        BranchEntry {
+            branch_id: 0x4dec_4df0,
+            parse_as: BranchId::Nu6,
+            activation_height,
+        }
+    }
+}
+
+/// Branch-ID / activation-height table for one network, consulted on top of
+/// librustzcash's own. See [`ChainParams::branch_table`].
+#[derive(Debug, Clone, Default)]
+pub struct BranchTable {
+    entries: Vec<BranchEntry>,
+}
+
+impl BranchTable {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Add an upgrade to the table, replacing any entry with the same branch ID.
+    pub fn insert(&mut self, entry: BranchEntry) {
+        self.entries.retain(|e| e.branch_id != entry.branch_id);
+        self.entries.push(entry);
+    }
+
+    /// Look up an entry by its on-chain branch ID.
+    pub fn get(&self, branch_id: u32) -> Option<&BranchEntry> {
+        self.entries.iter().find(|e| e.branch_id == branch_id)
+    }
+
+    /// Consensus branch ID in effect at `height`, preferring the most recent
+    /// table entry that has activated over librustzcash's answer.
+    pub fn branch_id_for_height(&self, params: &ChainParams, height: BlockHeight) -> u32 {
+        self.entries
+            .iter()
+            .filter_map(|e| e.activation_height.map(|h| (h, e.branch_id)))
+            .filter(|(h, _)| u32::from(height) >= *h)
+            .max_by_key(|(h, _)| *h)
+            .map(|(_, id)| id)
+            .unwrap_or_else(|| u32::from(BranchId::for_height(params, height)))
+    }
+
+    /// Map a branch ID to one librustzcash can parse with.
+    fn resolve(&self, branch_id: u32) -> Option<BranchId> {
+        BranchId::try_from(branch_id)
+            .ok()
+            .or_else(|| self.get(branch_id).map(|e| e.parse_as))
+    }
+}
+
+/// A parsed transaction together with the consensus data it was committed under.
+pub struct ParsedTransaction {
+    /// The parsed transaction. For upgrades handled through [`BranchTable`] its
+    /// `consensus_branch_id()` is the substituted branch, not the on-chain one.
+    pub tx: Transaction,
+
+    /// TXID computed from the transaction bytes
+    pub txid: TxId,
+
+    /// Number of bytes consumed from the input
+    pub size: usize,
+}
+
+/// Parse a raw transaction without modifying its bytes.
+///
+/// v5 transactions carry their branch ID in bytes 8..12 of the header. When
+/// `branch_id_override` is given, or the embedded ID is unknown to
+/// librustzcash and the table supplies a format-compatible branch, only the
+/// reader sees the substituted header; the TXID still commits to the embedded
+/// ID. Pre-v5 transactions do not encode a branch ID, so it is derived from
+/// `height` unless overridden.
+///
+/// Only the leading transaction is read; any bytes after it are left alone and
+/// reported through [`ParsedTransaction::size`].
+pub fn parse_transaction(
+    tx_bytes: &[u8],
+    params: &ChainParams,
+    height: BlockHeight,
+    table: &BranchTable,
+    branch_id_override: Option<u32>,
+) -> Result<ParsedTransaction> {
+    let header = tx_bytes
+        .get(0..4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Transaction is too short to contain a header"))?;
+
+    if header == V5_TX_HEADER && tx_bytes.len() >= 12 {
+        let on_chain = u32::from_le_bytes(tx_bytes[8..12].try_into().unwrap());
+        let parse_as = match branch_id_override {
+            Some(id) => table
+                .resolve(id)
+                .ok_or_else(|| anyhow!("Unknown consensus branch ID 0x{:08x}", id))?,
+            None => table.resolve(on_chain).ok_or_else(|| {
+                anyhow!(
+                    "Unknown consensus branch ID 0x{:08x}; pass --branch-id with a \
+                     format-compatible branch to parse it anyway",
+                    on_chain
+                )
+            })?,
+        };
+
+        let mut patched_header = tx_bytes[0..12].to_vec();
+        patched_header[8..12].copy_from_slice(&u32::from(parse_as).to_le_bytes());
+        let mut body = Cursor::new(&tx_bytes[12..]);
+        let reader = Cursor::new(patched_header).chain(&mut body);
+
+        let tx = Transaction::read(reader, parse_as)
+            .with_context(|| format!("Failed to parse {} transaction", format_name(header)))?;
+        let txid = txid::compute_txid(&tx, on_chain);
+        let size = 12 + body.position() as usize;
+
+        return Ok(ParsedTransaction { tx, txid, size });
+    }
+
+    let branch_id =
+        branch_id_override.unwrap_or_else(|| table.branch_id_for_height(params, height));
+    let parse_as = table
+        .resolve(branch_id)
+        .ok_or_else(|| anyhow!("Unknown consensus branch ID 0x{:08x}", branch_id))?;
+
+    // v1-v4 formats don't depend on the branch ID, so a failure here means
+    // the bytes themselves are malformed; name the format they claim to be.
+    let mut reader = Cursor::new(tx_bytes);
+    let tx = Transaction::read(&mut reader, parse_as)
+        .with_context(|| format!("Failed to parse {} transaction", format_name(header)))?;
+    let txid = tx.txid();
+    let size = reader.position() as usize;
+
+    Ok(ParsedTransaction { tx, txid, size })
+}
+
+/// Where a transaction's own fields place it on the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightEstimate {
+    /// Most likely mined height
+    pub height: u32,
+
+    /// Lowest height the transaction's consensus branch allows
+    pub earliest: u32,
+
+    /// Highest height its branch or nExpiryHeight allows, if bounded
+    pub latest: Option<u32>,
+}
+
+impl HeightEstimate {
+    /// Whether the transaction could have been mined at `height`.
+    pub fn allows(&self, height: u32) -> bool {
+        height >= self.earliest && self.latest.is_none_or(|latest| height <= latest)
+    }
+}
+
+/// Estimate a transaction's mined height without a node: its consensus branch
+/// (embedded in v5, implied by the format in v3 and v4) bounds the range, and
+/// the default expiry delta below nExpiryHeight places it within.
+///
+/// None for v1 and v2 transactions, and for v3 and v4 transactions without an
+/// expiry height, which give too little to go on.
+pub fn estimate_height(
+    tx_bytes: &[u8],
+    params: &ChainParams,
+    table: &BranchTable,
+) -> Option<HeightEstimate> {
+    let word = |at: usize| {
+        tx_bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    let activation = |nu| params.activation_height(nu).map(u32::from);
+    let header = word(0)?;
+
+    let (earliest, latest) = match header {
+        V5_TX_HEADER => {
+            let on_chain = word(8)?;
+            match BranchId::try_from(on_chain) {
+                Ok(branch) => {
+                    let (lower, upper) = branch.height_bounds(params)?;
+                    (u32::from(lower), upper.map(|upper| u32::from(upper) - 1))
+                }
+                Err(_) => (table.get(on_chain)?.activation_height?, None),
+            }
+        }
+        V4_TX_HEADER => (activation(NetworkUpgrade::Sapling)?, None),
+        V3_TX_HEADER => (
+            activation(NetworkUpgrade::Overwinter)?,
+            Some(activation(NetworkUpgrade::Sapling)? - 1),
+        ),
+        _ => return None,
+    };
+
+    let parsed =
+        parse_transaction(tx_bytes, params, BlockHeight::from_u32(earliest), table, None).ok()?;
+    let (height, latest) = match u32::from(parsed.tx.expiry_height()) {
+        // v5 has no pre-Canopy rules to get wrong, so the earliest will do
+        0 if header == V5_TX_HEADER => (earliest, latest),
+        0 => return None,
+        expiry => (
+            expiry.saturating_sub(EXPIRY_DELTA),
+            Some(latest.map_or(expiry, |latest| latest.min(expiry))),
+        ),
+    };
+    let height = height.max(earliest);
+    Some(HeightEstimate {
+        height: latest.map_or(height, |latest| height.min(latest)),
+        earliest,
+        latest,
+    })
+}
+
+/// Transaction format named by a header word, e.g. "v4 (Sapling)".
+pub fn format_name(header: u32) -> String {
+    let overwintered = header >> 31 == 1;
+    let version = header & 0x7FFF_FFFF;
+    let era = match (overwintered, version) {
+        (false, 1) => "Sprout, transparent only",
+        (false, 2) => "Sprout",
+        (true, 3) => "Overwinter",
+        (true, 4) => "Sapling",
+        (true, 5) => "NU5",
+        _ => "unknown format",
+    };
+    format!("v{} ({})", version, era)
+}
+
+/// Parse a `--branch-id` value such as `0x4dec4df0` or `4dec4df0`.
+pub fn parse_branch_id(s: &str) -> Result<u32, String> {
+    let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+    u32::from_str_radix(digits, 16).map_err(|e| format!("invalid branch ID '{}': {}", s, e))
+}
+
+/// Transaction bytes from `--raw-tx` or `--raw-tx-file`, where `--raw-tx -`
+/// reads stdin.
+///
+/// Hex given inline is decoded as before. Data from a file or stdin may be hex
+/// or the raw binary transaction, told apart by [`is_hex_text`].
+pub fn read_raw_tx(raw_tx: Option<&str>, file: Option<&Path>) -> Result<Vec<u8>> {
+    let data = match (raw_tx, file) {
+        (_, Some(path)) => std::fs::read(path)
+            .with_context(|| format!("Failed to read raw transaction {}", path.display()))?,
+        (Some("-"), None) => {
+            let mut data = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .context("Failed to read raw transaction from stdin")?;
+            data
+        }
+        (Some(hex), None) => hex.trim().as_bytes().to_vec(),
+        (None, None) => return Err(anyhow!("No raw transaction given")),
+    };
+
+    let inline = file.is_none() && raw_tx != Some("-");
+    let tx_bytes = if inline || is_hex_text(&data) {
+        let hex: Vec<u8> = data.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        hex::decode(hex)
+            .context("Raw transaction hex is invalid (not hex or empty)")
+            .context(ErrorKind::Parse)?
+    } else {
+        data
+    };
+
+    if tx_bytes.is_empty() {
+        return Err(anyhow!("Transaction data is empty").context(ErrorKind::Parse));
+    }
+    Ok(tx_bytes)
+}
+
+/// Whether `data` is hex text (hex digits and whitespace) rather than a binary
+/// transaction or block, which never is: both start with a little-endian
+/// version whose first byte is below 0x10.
+pub fn is_hex_text(data: &[u8]) -> bool {
+    data.iter().all(|b| b.is_ascii_hexdigit() || b.is_ascii_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_protocol::consensus::Network;
+
+    use super::*;
+    use crate::golden::{self, Fixture};
+
+    fn fixture_tx(name: &str) -> Vec<u8> {
+        let (_, json) = golden::EMBEDDED
+            .iter()
+            .find(|(embedded, _)| *embedded == name)
+            .expect("fixture is embedded");
+        hex::decode(Fixture::parse(json).unwrap().raw_tx).unwrap()
+    }
+
+    #[test]
+    fn v5_height_comes_from_expiry_within_its_branch() {
+        let params = ChainParams::Standard(Network::TestNetwork);
+        // NU5 branch, nExpiryHeight 2_000_040
+        let tx = fixture_tx("synthetic-v5-orchard");
+        let estimate = estimate_height(&tx, &params, &BranchTable::empty()).unwrap();
+
+        assert_eq!(estimate.height, 2_000_000);
+        assert_eq!(estimate.earliest, 1_842_420);
+        assert_eq!(estimate.latest, Some(2_000_040));
+        assert!(estimate.allows(2_000_040));
+        assert!(!estimate.allows(2_000_041));
+        assert!(!estimate.allows(1_842_419));
+    }
+
+    #[test]
+    fn v4_estimate_is_clamped_to_sapling_activation() {
+        let params = ChainParams::Standard(Network::TestNetwork);
+        // Mined at 280_003 with nExpiryHeight 280_023, closer to Sapling
+        // activation than the default expiry delta
+        let tx = fixture_tx("testnet-v4-sprout");
+        let estimate = estimate_height(&tx, &params, &BranchTable::empty()).unwrap();
+
+        assert_eq!(estimate.height, 280_000);
+        assert_eq!(estimate.earliest, 280_000);
+        assert_eq!(estimate.latest, Some(280_023));
+        assert!(estimate.allows(280_003));
+    }
+
+    #[test]
+    fn branch_id_override_applies_to_known_v5_branches() {
+        let params = ChainParams::Standard(Network::TestNetwork);
+        let table = BranchTable::empty();
+        let tx = fixture_tx("synthetic-v5-orchard");
+        let height = BlockHeight::from_u32(2_000_000);
+
+        let embedded = parse_transaction(&tx, &params, height, &table, None).unwrap();
+        assert_eq!(embedded.tx.consensus_branch_id(), BranchId::Nu5);
+
+        let nu6 = u32::from(BranchId::Nu6);
+        let parsed = parse_transaction(&tx, &params, height, &table, Some(nu6)).unwrap();
+        assert_eq!(parsed.tx.consensus_branch_id(), BranchId::Nu6);
+        // The TXID still commits to the branch in the transaction
+        assert_eq!(parsed.txid, embedded.txid);
+
+        let err = parse_transaction(&tx, &params, height, &table, Some(0x1234_5678))
+            .err()
+            .expect("unknown override is rejected");
+        assert!(err.to_string().contains("0x12345678"));
+    }
+
+    #[test]
+    fn unreadable_transactions_have_no_estimate() {
+        let params = ChainParams::Standard(Network::TestNetwork);
+        let table = BranchTable::empty();
+        assert!(estimate_height(&[], &params, &table).is_none());
+        assert!(estimate_height(&1u32.to_le_bytes(), &params, &table).is_none());
+
+        let tx = fixture_tx("synthetic-v5-sapling");
+        assert!(estimate_height(&tx[..tx.len() / 2], &params, &table).is_none());
+    }
+}
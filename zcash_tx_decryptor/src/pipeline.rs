@@ -0,0 +1,395 @@
+//! Pushing events to other systems as they are emitted: `--webhook` and the
+//! message-bus sinks (`--sink kafka://...`, `--sink amqp://...`).
+//!
+//! Each destination implements [`ResultSink`] and runs behind a [`Forwarder`]:
+//! a background task fed by an unbounded queue, delivering in the order the
+//! events were emitted. A delivery that fails in a way a retry could fix is
+//! retried with exponential backoff before the next event is sent.
+//!
+//! With `--spill-dir`, every event is appended to the destination's spill file
+//! before it is queued, and the file is emptied whenever everything in it has
+//! been accepted, or rewritten without the accepted events every
+//! [`COMPACT_AFTER`] acknowledgements if it never empties. Events still in it
+//! after a crash, or after exiting while the destination was down, are sent
+//! first on the next start. Delivery is then at least once: an event accepted
+//! shortly before a crash arrives again.
+
+use std::{
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::IgnoredAny, Deserialize};
+use sha2::{Digest, Sha256};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Delivery attempts per event before it is dropped, without a spill file.
+/// With one, transient failures are retried until the destination is back.
+const ATTEMPTS: u32 = 10;
+
+/// Wait before the first retry; doubled after each failure up to
+/// [`MAX_BACKOFF`].
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Acknowledged events after which a spill file that never empties is
+/// rewritten with only the pending ones.
+const COMPACT_AFTER: usize = 1_000;
+
+/// A failed delivery, and whether trying again could succeed (false when the
+/// destination rejected the event itself).
+#[derive(Debug)]
+pub struct DeliveryError {
+    pub error: anyhow::Error,
+    pub retry: bool,
+}
+
+impl DeliveryError {
+    /// A failure that may clear up, e.g. an unreachable broker.
+    pub fn transient(error: impl Into<anyhow::Error>) -> Self {
+        DeliveryError {
+            error: error.into(),
+            retry: true,
+        }
+    }
+}
+
+/// A destination for emitted events.
+pub trait ResultSink: Send + 'static {
+    /// What log lines call the destination, e.g. "webhook"
+    fn name(&self) -> &'static str;
+
+    /// Deliver one event (a JSON object), returning once the destination has
+    /// taken responsibility for it.
+    fn deliver(&mut self, event: &str) -> impl Future<Output = Result<(), DeliveryError>> + Send;
+}
+
+/// Open the message-bus sink for a `--sink` URL, spilling to `spill_dir` if
+/// given.
+pub fn open(url: &str, spill_dir: Option<&Path>) -> Result<Forwarder> {
+    let spill = |name| spill_dir.map(|dir| spill_file(dir, name, url));
+    match url.split_once("://").map(|(scheme, _)| scheme) {
+        Some("kafka") => kafka(url, spill("kafka")),
+        Some("amqp" | "amqps") => amqp(url, spill("amqp")),
+        _ => Err(anyhow!(
+            "Unsupported --sink (expected kafka://<brokers>/<topic> or amqp://<host>/<vhost>)"
+        )),
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn kafka(url: &str, spill: Option<PathBuf>) -> Result<Forwarder> {
+    Forwarder::spawn(crate::kafka::KafkaSink::new(url)?, spill)
+}
+
+#[cfg(not(feature = "kafka"))]
+fn kafka(_url: &str, _spill: Option<PathBuf>) -> Result<Forwarder> {
+    Err(anyhow!(
+        "--sink kafka://... needs a build with the `kafka` feature \
+         (cargo build --release --features kafka)"
+    ))
+}
+
+#[cfg(feature = "amqp")]
+fn amqp(url: &str, spill: Option<PathBuf>) -> Result<Forwarder> {
+    Forwarder::spawn(crate::amqp::AmqpSink::new(url)?, spill)
+}
+
+#[cfg(not(feature = "amqp"))]
+fn amqp(_url: &str, _spill: Option<PathBuf>) -> Result<Forwarder> {
+    Err(anyhow!(
+        "--sink amqp://... needs a build with the `amqp` feature \
+         (cargo build --release --features amqp)"
+    ))
+}
+
+/// Spill file for the `name` destination at `url`. Named after a hash of the
+/// URL, so each destination keeps its own and credentials in the URL don't
+/// end up in file names.
+pub fn spill_file(dir: &Path, name: &str, url: &str) -> PathBuf {
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    dir.join(format!("{}-{}.ndjson", name, &digest[..16]))
+}
+
+/// The `idempotency_key` of an output event, for destinations that can carry
+/// it outside the body: the webhook header, the Kafka message key, the AMQP
+/// message ID. Reorg and filtered-output events have none.
+pub fn idempotency_key(event: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Keyed {
+        idempotency_key: Option<String>,
+    }
+
+    let keyed: Keyed = serde_json::from_str(event).ok()?;
+    keyed.idempotency_key.filter(|key| !key.is_empty())
+}
+
+/// Queues events for in-order delivery to one [`ResultSink`].
+pub struct Forwarder {
+    name: &'static str,
+    queue: mpsc::UnboundedSender<String>,
+    spill: Option<Arc<Spill>>,
+    closing: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl Forwarder {
+    /// Start delivering to `sink`, first whatever an earlier run left in
+    /// `spill`. Must be called within the tokio runtime.
+    pub fn spawn<S: ResultSink>(mut sink: S, spill: Option<PathBuf>) -> Result<Self> {
+        let name = sink.name();
+        let (queue, mut events) = mpsc::unbounded_channel::<String>();
+        let spill = match spill {
+            Some(path) => {
+                let (spill, left) = Spill::open(path)?;
+                if !left.is_empty() {
+                    tracing::info!("resending {} undelivered {} event(s)", left.len(), name);
+                }
+                for event in left {
+                    let _ = queue.send(event);
+                }
+                Some(Arc::new(spill))
+            }
+            None => None,
+        };
+        let closing = Arc::new(AtomicBool::new(false));
+
+        let task = {
+            let spill = spill.clone();
+            let closing = Arc::clone(&closing);
+            tokio::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    if !deliver(&mut sink, &event, spill.is_some(), &closing).await {
+                        break;
+                    }
+                    if let Some(Err(e)) = spill.as_ref().map(|spill| spill.ack()) {
+                        tracing::warn!("{} spill file not updated: {:#}", name, e);
+                    }
+                }
+            })
+        };
+        Ok(Forwarder {
+            name,
+            queue,
+            spill,
+            closing,
+            task,
+        })
+    }
+
+    /// Queue `event` (one JSON object) for delivery. Fails only if it can't
+    /// be written to the spill file.
+    pub fn send(&self, event: &str) -> Result<()> {
+        if let Some(spill) = &self.spill {
+            spill.push(event)?;
+        }
+        // The task only stops once the queue is closed in `finish`
+        let _ = self.queue.send(event.to_string());
+        Ok(())
+    }
+
+    /// Wait until every queued event has been delivered or given up on. With
+    /// a spill file, events the destination won't take right now are left in
+    /// it for the next run instead.
+    pub async fn finish(self) {
+        self.closing.store(true, Ordering::Relaxed);
+        drop(self.queue);
+        if let Err(e) = self.task.await {
+            tracing::warn!("{} delivery stopped: {}", self.name, e);
+        }
+    }
+}
+
+/// Deliver `event`, retrying transient failures with backoff. Returns false
+/// if it was left in the spill file for the next run instead.
+async fn deliver<S: ResultSink>(
+    sink: &mut S,
+    event: &str,
+    spilled: bool,
+    closing: &AtomicBool,
+) -> bool {
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match sink.deliver(event).await {
+            Ok(()) => return true,
+            Err(e) if e.retry && (spilled || attempt < ATTEMPTS) => e.error,
+            Err(e) => {
+                tracing::warn!(
+                    "dropping {} event after {} attempt(s): {:#}",
+                    sink.name(),
+                    attempt,
+                    e.error
+                );
+                return true;
+            }
+        };
+        if spilled && closing.load(Ordering::Relaxed) {
+            tracing::warn!(
+                "{} delivery failed ({:#}); undelivered events stay in the spill file",
+                sink.name(),
+                error
+            );
+            return false;
+        }
+        tracing::warn!(
+            "{} delivery failed ({:#}); retrying in {}s",
+            sink.name(),
+            error,
+            backoff.as_secs()
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Events queued for one destination and not yet accepted, on disk.
+struct Spill {
+    path: PathBuf,
+    state: Mutex<SpillState>,
+}
+
+struct SpillState {
+    file: File,
+    /// Lines at the start of the file already acknowledged
+    acked: usize,
+    /// Lines in the file not yet acknowledged
+    pending: usize,
+}
+
+impl Spill {
+    /// Open the spill file at `path`, returning the events an earlier run
+    /// left in it. A line cut short by a crash is skipped.
+    fn open(path: PathBuf) -> Result<(Self, Vec<String>)> {
+        let left: Vec<String> = match std::fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .filter(|line| serde_json::from_str::<IgnoredAny>(line).is_ok())
+                .map(str::to_string)
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read spill file {}", path.display()))
+            }
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create spill directory {}", dir.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open spill file {}", path.display()))?;
+        // Rewrite without any torn line, so it can't swallow the next event
+        file.set_len(0)?;
+        for event in &left {
+            file.write_all(format!("{}\n", event).as_bytes())?;
+        }
+
+        let state = SpillState {
+            file,
+            acked: 0,
+            pending: left.len(),
+        };
+        let spill = Spill {
+            path,
+            state: Mutex::new(state),
+        };
+        Ok((spill, left))
+    }
+
+    fn push(&self, event: &str) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("Spill lock poisoned"))?;
+        // One write per line, as for --output
+        state
+            .file
+            .write_all(format!("{}\n", event).as_bytes())
+            .with_context(|| format!("Failed to write to spill file {}", self.path.display()))?;
+        state.pending += 1;
+        Ok(())
+    }
+
+    /// The oldest pending event was accepted; empty the file once none are
+    /// left, and drop what was accepted every [`COMPACT_AFTER`] events so a
+    /// destination that is never quite caught up doesn't grow it for ever.
+    fn ack(&self) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("Spill lock poisoned"))?;
+        state.pending = state.pending.saturating_sub(1);
+        state.acked += 1;
+        if state.pending == 0 {
+            state.file.set_len(0)?;
+            state.acked = 0;
+        } else if state.acked >= COMPACT_AFTER {
+            self.compact(&mut state)
+                .with_context(|| format!("Failed to compact spill file {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the file with one holding only the pending events. The new file
+    /// is written beside it and renamed over it, so a crash leaves one or the
+    /// other.
+    fn compact(&self, state: &mut SpillState) -> Result<()> {
+        let text = std::fs::read_to_string(&self.path)?;
+        let mut rest = String::new();
+        for line in text.lines().skip(state.acked) {
+            rest.push_str(line);
+            rest.push('\n');
+        }
+        let tmp = self.path.with_extension("compact");
+        std::fs::write(&tmp, rest)?;
+        std::fs::rename(&tmp, &self.path)?;
+        state.file = OpenOptions::new().append(true).open(&self.path)?;
+        state.acked = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spill_file_shrinks_while_events_are_pending() {
+        let dir = std::env::temp_dir().join(format!("ztd-spill-{}", std::process::id()));
+        let path = dir.join("sink.ndjson");
+        let event = |i: usize| format!("{{\"event\":\"block_output\",\"n\":{}}}", i);
+
+        let (spill, left) = Spill::open(path.clone()).unwrap();
+        assert!(left.is_empty());
+        for i in 0..COMPACT_AFTER + 10 {
+            spill.push(&event(i)).unwrap();
+        }
+        let full = std::fs::metadata(&path).unwrap().len();
+        for _ in 0..COMPACT_AFTER {
+            spill.ack().unwrap();
+        }
+        assert!(std::fs::metadata(&path).unwrap().len() < full / 10);
+
+        // Appends go to the rewritten file, and a restart resends only what
+        // is still pending
+        spill.push(&event(COMPACT_AFTER + 10)).unwrap();
+        drop(spill);
+        let (_, left) = Spill::open(path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let expected: Vec<String> = (COMPACT_AFTER..=COMPACT_AFTER + 10).map(event).collect();
+        assert_eq!(left, expected);
+    }
+}
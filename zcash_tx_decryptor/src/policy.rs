@@ -0,0 +1,67 @@
+//! Output policy (`--min-value-zats`, `--min-confirmations`): which decrypted
+//! outputs are reported at all, so dust spam doesn't flood reports, events
+//! and the result store.
+
+use crate::models::{FilteredOutputs, OutputInfo, TxReport};
+
+/// Thresholds an output must meet to be reported; `None` means no threshold.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputPolicy {
+    /// Leave out outputs worth less than this
+    pub min_value_zats: Option<i64>,
+
+    /// Leave out every output of a transaction with fewer confirmations
+    pub min_confirmations: Option<u32>,
+
+    /// Count what was left out in the report's `filtered` summary
+    pub summarize: bool,
+}
+
+impl OutputPolicy {
+    /// Drop `report`'s outputs worth less than `min_value_zats`.
+    pub fn filter_dust(&self, report: &mut TxReport) {
+        let Some(min) = self.min_value_zats else {
+            return;
+        };
+        let (kept, dust): (Vec<_>, Vec<_>) = std::mem::take(&mut report.outputs)
+            .into_iter()
+            .partition(|out| out.amount_zats >= min);
+        report.outputs = kept;
+        if let Some(filtered) = self.summary(report, &dust) {
+            filtered.dust_outputs += dust.len() as u32;
+            filtered.dust_zats += total_zats(&dust);
+        }
+    }
+
+    /// Drop all of `report`'s outputs if its transaction has fewer than
+    /// `min_confirmations`.
+    pub fn filter_unconfirmed(&self, report: &mut TxReport, confirmations: u32) {
+        if self
+            .min_confirmations
+            .is_none_or(|min| confirmations >= min)
+        {
+            return;
+        }
+        let unconfirmed = std::mem::take(&mut report.outputs);
+        if let Some(filtered) = self.summary(report, &unconfirmed) {
+            filtered.unconfirmed_outputs += unconfirmed.len() as u32;
+            filtered.unconfirmed_zats += total_zats(&unconfirmed);
+        }
+    }
+
+    /// The summary to count `dropped` in, if one is kept and anything was.
+    fn summary<'r>(
+        &self,
+        report: &'r mut TxReport,
+        dropped: &[OutputInfo],
+    ) -> Option<&'r mut FilteredOutputs> {
+        if !self.summarize || dropped.is_empty() {
+            return None;
+        }
+        Some(report.filtered.get_or_insert_with(FilteredOutputs::default))
+    }
+}
+
+fn total_zats(outputs: &[OutputInfo]) -> i64 {
+    outputs.iter().map(|out| out.amount_zats).sum()
+}
@@ -0,0 +1,157 @@
+//! `--progress-fd`: machine-readable progress of `sync` and `scan-compact`,
+//! one JSON [`ProgressEvent`] line per update on a file descriptor the caller
+//! opened, e.g. `--progress-fd 3` with `3>progress.ndjson` or a pipe from an
+//! orchestrator. Lines are written whole and at most once a second, plus one
+//! when the scan reaches its target.
+
+use std::{
+    fs::File,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::models::ProgressEvent;
+
+/// Shortest gap between two progress lines, except the last of a scan.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where progress goes, if anywhere.
+pub struct Progress {
+    /// A duplicate of the descriptor passed, so closing it leaves the
+    /// caller's (and other jobs') open
+    out: Option<File>,
+    mode: &'static str,
+    network: Option<&'static str>,
+    /// Transactions with outputs for our keys, over the whole run
+    hits: u64,
+    last: Option<Instant>,
+}
+
+impl Progress {
+    /// Progress of `mode` on `network`, written to descriptor `fd` if given.
+    pub fn open(fd: Option<i32>, mode: &'static str, network: Option<&'static str>) -> Result<Self> {
+        let out = fd.map(from_fd).transpose()?;
+        Ok(Progress {
+            out,
+            mode,
+            network,
+            hits: 0,
+            last: None,
+        })
+    }
+
+    /// Count a transaction with outputs for our keys.
+    pub fn hit(&mut self) {
+        self.hits += 1;
+    }
+
+    /// Report `done` of `total` units of work started at `started`, having
+    /// handled blocks up to `height` of `target_height`. A descriptor that
+    /// can't be written to any more is dropped with a warning; the scan
+    /// carries on.
+    pub fn report(
+        &mut self,
+        started: Instant,
+        (done, total): (u64, u64),
+        (height, target_height): (u32, u32),
+    ) {
+        let Some(out) = self.out.as_mut() else {
+            return;
+        };
+        let finished = done >= total;
+        if !finished && self.last.is_some_and(|last| last.elapsed() < MIN_INTERVAL) {
+            return;
+        }
+        self.last = Some(Instant::now());
+
+        let eta_secs = (done > 0).then(|| {
+            let per_unit = started.elapsed().as_secs_f64() / done as f64;
+            (per_unit * total.saturating_sub(done) as f64).round() as u64
+        });
+        let event = ProgressEvent {
+            event: "progress".to_string(),
+            mode: self.mode.to_string(),
+            at: Utc::now(),
+            network: self.network.map(str::to_string),
+            height,
+            target_height,
+            percent: match total {
+                0 => 100.0,
+                _ => (done.min(total) as f64 * 10_000.0 / total as f64).round() / 100.0,
+            },
+            hits: self.hits,
+            eta_secs,
+        };
+        let mut line = serde_json::to_string(&event).unwrap_or_default();
+        line.push('\n');
+        if let Err(e) = out.write_all(line.as_bytes()) {
+            tracing::warn!("Progress descriptor not writable, no more progress: {}", e);
+            self.out = None;
+        }
+    }
+}
+
+/// A duplicate of descriptor `fd`, failing if it isn't open.
+#[cfg(unix)]
+fn from_fd(fd: i32) -> Result<File> {
+    use std::os::fd::BorrowedFd;
+
+    if fd < 0 {
+        return Err(anyhow::anyhow!("--progress-fd {} is not a file descriptor", fd));
+    }
+    // SAFETY: only borrowed for the dup(2); an fd that isn't open fails there
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let owned = borrowed
+        .try_clone_to_owned()
+        .map_err(|e| anyhow::anyhow!("--progress-fd {} is not open: {}", fd, e))?;
+    Ok(File::from(owned))
+}
+
+#[cfg(not(unix))]
+fn from_fd(_fd: i32) -> Result<File> {
+    Err(anyhow::anyhow!("--progress-fd is only supported on Unix"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{io::Read, os::fd::AsRawFd};
+
+    use super::*;
+
+    #[test]
+    fn reports_throttled_lines_with_eta() {
+        let path = std::env::temp_dir().join(format!("ztd-progress-{}", std::process::id()));
+        let file = File::create(&path).unwrap();
+        let mut progress = Progress::open(Some(file.as_raw_fd()), "sync", Some("main")).unwrap();
+        drop(file);
+
+        let started = Instant::now() - Duration::from_secs(10);
+        progress.hit();
+        progress.report(started, (1, 4), (100, 103));
+        // Within a second of the last line, only the final one is written
+        progress.report(started, (2, 4), (101, 103));
+        progress.report(started, (4, 4), (103, 103));
+        drop(progress);
+
+        let mut text = String::new();
+        File::open(&path).unwrap().read_to_string(&mut text).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let events: Vec<ProgressEvent> =
+            text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].height, events[0].target_height), (100, 103));
+        assert_eq!((events[0].percent, events[0].hits), (25.0, 1));
+        assert_eq!(events[0].eta_secs, Some(30));
+        assert_eq!((events[1].percent, events[1].eta_secs), (100.0, Some(0)));
+        assert_eq!(events[1].network.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn closed_descriptors_are_refused() {
+        assert!(Progress::open(Some(-1), "sync", None).is_err());
+        assert!(Progress::open(Some(1_000_000), "sync", None).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! `report`: per-account totals (received, change, sent, net flow) over the
+//! outputs in a `--db` result store or in NDJSON event files, so consumers
+//! don't each re-aggregate per-transaction output.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{
+    models::{csv::escape, AccountFlow, FlowReport, PaymentEvent, ReorgEvent},
+    store::{OutputFilter, ResultStore},
+};
+
+/// Column names of the CSV view, in row order.
+const HEADER: &str = "account,account_label,transactions,outputs,received_zats,change_zats,\
+sent_zats,fee_zats,net_zats,pending_zats";
+
+/// Transfer type of the entries standing for fees an account paid.
+const FEE: &str = "Fee";
+
+/// Which outputs a report covers; `None` means unbounded. Dates are UTC days,
+/// both ends included. Outputs worth less than `min_value_zats` (dust) are
+/// left out.
+#[derive(Debug, Default)]
+pub struct Range {
+    pub from_height: Option<u32>,
+    pub to_height: Option<u32>,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+    pub min_value_zats: Option<i64>,
+}
+
+impl Range {
+    fn contains(&self, entry: &Entry) -> bool {
+        let date = entry.time.map(|time| time.date_naive());
+        self.from_height.is_none_or(|from| entry.height >= from)
+            && self.to_height.is_none_or(|to| entry.height <= to)
+            && self
+                .from_date
+                .is_none_or(|from| date.is_some_and(|date| date >= from))
+            && self
+                .to_date
+                .is_none_or(|to| date.is_some_and(|date| date <= to))
+            && (entry.transfer_type == FEE
+                || self
+                    .min_value_zats
+                    .is_none_or(|min| entry.amount_zats >= min))
+    }
+}
+
+/// Parse a `--from-date` / `--to-date` value.
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}' (expected YYYY-MM-DD)", s))
+}
+
+/// One output as counted, from either source.
+struct Entry {
+    txid: String,
+    height: u32,
+    confirmed: bool,
+    account: u32,
+    account_label: Option<String>,
+    transfer_type: String,
+    amount_zats: i64,
+    /// Block time where known, otherwise when the output was first seen
+    time: Option<DateTime<Utc>>,
+}
+
+/// Totals over the outputs stored in `store`.
+pub fn from_store(store: &ResultStore, range: &Range) -> Result<FlowReport> {
+    let rows = store.query(&OutputFilter {
+        from_height: range.from_height,
+        to_height: range.to_height,
+        min_value_zats: range.min_value_zats,
+        ..OutputFilter::default()
+    })?;
+    let entries = rows.into_iter().map(|row| Entry {
+        time: DateTime::parse_from_rfc3339(row.block_time.as_deref().unwrap_or(&row.recorded_at))
+            .ok()
+            .map(|time| time.with_timezone(&Utc)),
+        txid: row.transaction_id,
+        height: row.block_height,
+        confirmed: row.confirmed,
+        account: row.account,
+        account_label: row.account_label,
+        transfer_type: row.transfer_type,
+        amount_zats: row.amount_zats,
+    });
+    let fees = store
+        .fees(range.from_height, range.to_height)?
+        .into_iter()
+        .map(|fee| Entry {
+            time: DateTime::parse_from_rfc3339(&fee.time)
+                .ok()
+                .map(|time| time.with_timezone(&Utc)),
+            txid: fee.txid,
+            height: fee.height,
+            confirmed: true,
+            account: fee.account,
+            account_label: None,
+            transfer_type: FEE.to_string(),
+            amount_zats: fee.fee_zats,
+        });
+    Ok(aggregate(entries.chain(fees), range, true))
+}
+
+/// Totals over the events in `paths`, read in order. An output seen more than
+/// once (in the mempool, then mined) counts once, as mined; outputs listed by
+/// a later `reorg` event are dropped; simulated payments are ignored. Events
+/// don't carry value balances, so fees are left unknown.
+pub fn from_events(paths: &[PathBuf], range: &Range) -> Result<FlowReport> {
+    // Latest state of each output, keyed as the result store keys them
+    let mut outputs: HashMap<(String, String, usize, u32), Entry> = HashMap::new();
+    for path in paths {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read events {}", path.display()))?;
+        for (number, line) in (1..).zip(text.lines()) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let at = || format!("{}:{}", path.display(), number);
+            let event: serde_json::Value = serde_json::from_str(line).with_context(|| {
+                format!(
+                    "{}: not a JSON event (run decrypt-report on sealed files)",
+                    at()
+                )
+            })?;
+
+            match event.get("event").and_then(|kind| kind.as_str()) {
+                Some("reorg") => {
+                    let reorg: ReorgEvent = serde_json::from_value(event).with_context(at)?;
+                    for row in reorg.rolled_back {
+                        outputs.remove(&(row.transaction_id, row.protocol, row.index, row.account));
+                    }
+                }
+                Some("simulated_output" | "filtered_outputs") => {}
+                Some(_) => {
+                    let event: PaymentEvent = serde_json::from_value(event).with_context(at)?;
+                    let confirmed = event.event != "mempool_output";
+                    let out = event.output;
+                    let key = (
+                        event.transaction_id.clone(),
+                        out.protocol,
+                        out.index,
+                        out.account,
+                    );
+                    if !confirmed && outputs.get(&key).is_some_and(|entry| entry.confirmed) {
+                        continue;
+                    }
+                    outputs.insert(
+                        key,
+                        Entry {
+                            txid: event.transaction_id,
+                            height: event.expected_height,
+                            confirmed,
+                            account: out.account,
+                            account_label: out.account_label,
+                            transfer_type: out.transfer_type,
+                            amount_zats: out.amount_zats,
+                            time: Some(event.detected_at),
+                        },
+                    );
+                }
+                None => return Err(anyhow!("{}: line has no \"event\" field", at())),
+            }
+        }
+    }
+    Ok(aggregate(outputs.into_values(), range, false))
+}
+
+/// Totals over `entries`; `fees_known` when fee entries were derived, so that
+/// an account without any reports a zero fee rather than an unknown one.
+fn aggregate(
+    entries: impl IntoIterator<Item = Entry>,
+    range: &Range,
+    fees_known: bool,
+) -> FlowReport {
+    let mut accounts: BTreeMap<u32, (AccountFlow, HashSet<String>)> = BTreeMap::new();
+    for entry in entries.into_iter().filter(|entry| range.contains(entry)) {
+        let (flow, txids) = accounts.entry(entry.account).or_insert_with(|| {
+            let flow = AccountFlow {
+                account: entry.account,
+                account_label: None,
+                transactions: 0,
+                outputs: 0,
+                received_zats: 0,
+                change_zats: 0,
+                sent_zats: 0,
+                fee_zats: fees_known.then_some(0),
+                net_zats: 0,
+                pending_zats: 0,
+            };
+            (flow, HashSet::new())
+        });
+        if flow.account_label.is_none() {
+            flow.account_label = entry.account_label;
+        }
+
+        if !entry.confirmed {
+            if entry.transfer_type == "Incoming" {
+                flow.pending_zats += entry.amount_zats;
+            }
+            continue;
+        }
+        txids.insert(entry.txid);
+        if entry.transfer_type == FEE {
+            *flow.fee_zats.get_or_insert(0) += entry.amount_zats;
+            continue;
+        }
+        flow.outputs += 1;
+        match entry.transfer_type.as_str() {
+            "Incoming" => flow.received_zats += entry.amount_zats,
+            "WalletInternal" => flow.change_zats += entry.amount_zats,
+            "Outgoing" => flow.sent_zats += entry.amount_zats,
+            _ => {}
+        }
+    }
+
+    FlowReport {
+        from_height: range.from_height,
+        to_height: range.to_height,
+        from_date: range.from_date.map(|date| date.to_string()),
+        to_date: range.to_date.map(|date| date.to_string()),
+        accounts: accounts
+            .into_values()
+            .map(|(mut flow, txids)| {
+                flow.transactions = txids.len() as u64;
+                flow.net_zats = flow.received_zats - flow.sent_zats - flow.fee_zats.unwrap_or(0);
+                flow
+            })
+            .collect(),
+    }
+}
+
+/// `report` as CSV, header included, one row per account.
+pub fn csv(report: &FlowReport) -> String {
+    let mut out = format!("{}\n", HEADER);
+    for flow in &report.accounts {
+        let row = [
+            flow.account.to_string(),
+            escape(flow.account_label.as_deref().unwrap_or_default()),
+            flow.transactions.to_string(),
+            flow.outputs.to_string(),
+            flow.received_zats.to_string(),
+            flow.change_zats.to_string(),
+            flow.sent_zats.to_string(),
+            flow.fee_zats.map(|fee| fee.to_string()).unwrap_or_default(),
+            flow.net_zats.to_string(),
+            flow.pending_zats.to_string(),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
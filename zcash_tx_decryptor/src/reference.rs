@@ -0,0 +1,48 @@
+//! Payment references in memos (`--memo-regex`, `--memo-prefix`): the order
+//! or invoice ID a payer put in the memo, reported as each output's
+//! `payment_reference`.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// How a payment reference is found in a memo.
+#[derive(Debug, Clone)]
+pub enum MemoMatcher {
+    /// The `ref` capture group if the pattern names one, else the first
+    /// capture group, else the whole match
+    Regex(Regex),
+    /// Memos starting with the prefix; the reference is the text after it, up
+    /// to the first whitespace
+    Prefix(String),
+}
+
+impl MemoMatcher {
+    /// Compile a `--memo-regex` pattern.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(MemoMatcher::Regex)
+            .with_context(|| format!("Invalid memo regex '{}'", pattern))
+    }
+
+    /// Reference in `memo`, or None if the memo doesn't match or the
+    /// reference would be empty. Trailing NUL padding and surrounding
+    /// whitespace are ignored.
+    pub fn extract(&self, memo: &str) -> Option<String> {
+        let memo = memo.trim_end_matches('\0').trim();
+        let reference = match self {
+            MemoMatcher::Regex(regex) => {
+                let captures = regex.captures(memo)?;
+                captures
+                    .name("ref")
+                    .or_else(|| captures.get(1))
+                    .or_else(|| captures.get(0))?
+                    .as_str()
+            }
+            MemoMatcher::Prefix(prefix) => memo
+                .strip_prefix(prefix.as_str())?
+                .split_whitespace()
+                .next()?,
+        };
+        (!reference.is_empty()).then(|| reference.to_string())
+    }
+}
@@ -0,0 +1,142 @@
+//! Reports encrypted to age recipients (`--encrypt-to`), so decrypted
+//! amounts and memos are never written to disk in the clear, and
+//! `decrypt-report` to read them back.
+//!
+//! A whole report is one ASCII-armored age message. NDJSON event streams are
+//! sealed a line at a time instead, each line the base64 of its own age
+//! message, so a tailing reader still gets one event per line.
+
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    x25519,
+};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Encrypts output to a fixed set of age recipients.
+pub struct Sealer {
+    recipients: Vec<x25519::Recipient>,
+}
+
+impl Sealer {
+    /// Sealer for `recipients` (`age1...` public keys), or None when there
+    /// are none and output stays in the clear.
+    pub fn new(recipients: &[String]) -> Result<Option<Self>> {
+        if recipients.is_empty() {
+            return Ok(None);
+        }
+        let recipients = recipients
+            .iter()
+            .map(|recipient| {
+                recipient
+                    .trim()
+                    .parse::<x25519::Recipient>()
+                    .map_err(|e| anyhow!("Invalid age recipient '{}': {}", recipient, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(Sealer { recipients }))
+    }
+
+    /// `plaintext` as an ASCII-armored age message.
+    pub fn armor(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut sealed = Vec::new();
+        let armor = ArmoredWriter::wrap_output(&mut sealed, Format::AsciiArmor)?;
+        self.encrypt_into(armor, plaintext)?.finish()?;
+        Ok(sealed)
+    }
+
+    /// `line` as a single line: the base64 of a binary age message.
+    pub fn line(&self, line: &str) -> Result<String> {
+        let sealed = self.encrypt_into(Vec::new(), line.as_bytes())?;
+        Ok(STANDARD.encode(sealed))
+    }
+
+    fn encrypt_into<W: Write>(&self, output: W, plaintext: &[u8]) -> Result<W> {
+        let recipients = self
+            .recipients
+            .iter()
+            .map(|recipient| Box::new(recipient.clone()) as Box<dyn age::Recipient + Send>)
+            .collect();
+        let encryptor =
+            age::Encryptor::with_recipients(recipients).expect("at least one recipient");
+        let mut writer = encryptor
+            .wrap_output(output)
+            .context("Failed to encrypt output")?;
+        writer.write_all(plaintext)?;
+        Ok(writer.finish()?)
+    }
+}
+
+/// Read the `AGE-SECRET-KEY-1...` identities in `path`, as written by
+/// `age-keygen`; comment lines are skipped.
+pub fn read_identities(path: &Path) -> Result<Vec<x25519::Identity>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read identity file {}", path.display()))?;
+    let identities = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.parse::<x25519::Identity>()
+                .map_err(|e| anyhow!("Invalid identity in {}: {}", path.display(), e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if identities.is_empty() {
+        return Err(anyhow!("No identities in {}", path.display()));
+    }
+    Ok(identities)
+}
+
+/// Decrypt `input`, either one age message (armored or binary) or an NDJSON
+/// stream sealed line by line, back to the plaintext that was sealed.
+pub fn open(input: &[u8], identities: &[x25519::Identity]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(input).unwrap_or_default();
+    if !input.starts_with(b"age-encryption.org/") && !text.trim_start().starts_with("-----BEGIN") {
+        let mut plaintext = Vec::new();
+        for (number, line) in (1..).zip(text.lines()) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let sealed = STANDARD
+                .decode(line.trim())
+                .with_context(|| format!("Line {} is not a sealed event", number))?;
+            plaintext
+                .extend(decrypt(&sealed, identities).with_context(|| format!("Line {}", number))?);
+            plaintext.push(b'\n');
+        }
+        return Ok(plaintext);
+    }
+    let start = input
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(input.len());
+    decrypt(&input[start..], identities)
+}
+
+fn decrypt(sealed: &[u8], identities: &[x25519::Identity]) -> Result<Vec<u8>> {
+    let decryptor = match age::Decryptor::new(ArmoredReader::new(sealed))
+        .context("Not an age-encrypted report")?
+    {
+        age::Decryptor::Recipients(decryptor) => decryptor,
+        age::Decryptor::Passphrase(_) => {
+            return Err(anyhow!(
+                "Report is passphrase-encrypted; expected age recipients"
+            ))
+        }
+    };
+    let mut reader = decryptor
+        .decrypt(
+            identities
+                .iter()
+                .map(|identity| identity as &dyn age::Identity),
+        )
+        .context("None of the identities can decrypt this report")?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use zcash_client_backend::keys::UnifiedFullViewingKey;
+use zcash_primitives::consensus::BlockHeight;
+use zcash_protocol::consensus::NetworkType;
+
+use crate::{
+    deadline::Deadline,
+    decrypt::{DecryptOptions, Decryptor},
+    models::{Balance, TransactionDetails, TxReport},
+    network,
+    shutdown::Shutdown,
+    store::ResultStore,
+};
+
+type SharedStore = Arc<Mutex<ResultStore>>;
+
+/// Result stores balances are read from: the `--db` one, and one per network
+/// given with `--network-db`.
+#[derive(Clone)]
+pub struct Stores {
+    default: Option<SharedStore>,
+    by_network: BTreeMap<&'static str, SharedStore>,
+}
+
+impl Stores {
+    pub fn open(db: Option<&Path>, network_dbs: &[(NetworkType, PathBuf)]) -> Result<Self> {
+        let shared = |store: ResultStore| Arc::new(Mutex::new(store));
+        let mut stores = Stores {
+            default: db.map(ResultStore::open).transpose()?.map(shared),
+            by_network: BTreeMap::new(),
+        };
+        for (network_type, path) in network_dbs {
+            let name = network::network_name(*network_type);
+            let store = ResultStore::open_for(path, name)?;
+            if stores.by_network.insert(name, shared(store)).is_some() {
+                return Err(anyhow!("--network-db is given twice for {}", name));
+            }
+        }
+        Ok(stores)
+    }
+
+    /// The store for `network`, or without one the `--db` store, else the
+    /// only network store.
+    fn get(&self, network: Option<&str>) -> Result<(Option<&'static str>, SharedStore)> {
+        if let Some(name) = network {
+            let network_type = network::parse_network_type(name).map_err(|e| anyhow!(e))?;
+            let name = network::network_name(network_type);
+            return self
+                .by_network
+                .get(name)
+                .map(|store| (Some(name), store.clone()))
+                .ok_or_else(|| anyhow!("No result database for {} (serve --network-db)", name));
+        }
+        if let Some(store) = &self.default {
+            return Ok((None, store.clone()));
+        }
+        match self.by_network.iter().next() {
+            Some((name, store)) if self.by_network.len() == 1 => Ok((Some(*name), store.clone())),
+            Some(_) => Err(anyhow!(
+                "Balances are served for several networks; pass ?network=<{}>",
+                self.by_network.keys().cloned().collect::<Vec<_>>().join("|")
+            )),
+            None => Err(anyhow!("Balances need a result database (serve --db or --network-db)")),
+        }
+    }
+}
+
+/// `<network>=<path>`, as given to `serve --network-db`.
+pub fn parse_network_db(s: &str) -> Result<(NetworkType, PathBuf), String> {
+    let (network, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <network>=<path>, got '{}'", s))?;
+    Ok((network::parse_network_type(network)?, PathBuf::from(path)))
+}
+
+#[derive(Clone)]
+struct AppState {
+    stores: Stores,
+    /// Time limit for requests that don't set `deadline_ms`
+    deadline: Option<Duration>,
+}
+
+/// Body of `POST /decrypt`, mirroring the CLI's --txid/--height/--raw-tx/--ufvk.
+#[derive(Debug, Deserialize)]
+struct DecryptRequest {
+    txid: String,
+    height: u32,
+    raw_tx: String,
+    ufvk: String,
+    /// "main", "test" or "regtest"; the key must be for this network
+    #[serde(default)]
+    network: Option<String>,
+    /// Time limit in milliseconds, overriding `serve --deadline`
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+}
+
+/// Request failure, returned as `{"error": "..."}`: 504 when the request ran
+/// out of time, otherwise 400.
+struct ApiError(StatusCode, anyhow::Error);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": format!("{:#}", self.1) }));
+        (self.0, body).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, e)
+    }
+}
+
+/// Run the HTTP API until `shutdown` is requested and open requests are
+/// answered. Balances are read from `stores`; decrypt requests are limited
+/// to `deadline` unless they set their own. Each decrypt request is for the
+/// network of its key, so one server handles mainnet and testnet alike.
+pub async fn serve(
+    addr: SocketAddr,
+    stores: Stores,
+    deadline: Option<Duration>,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/decrypt", post(decrypt))
+        .route("/balance/:account", get(balance))
+        .with_state(AppState { stores, deadline });
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind {}", addr))?;
+    tracing::info!("Listening on http://{}", addr);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await?;
+    tracing::info!("Stopped serving");
+    Ok(())
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn decrypt(
+    State(state): State<AppState>,
+    Json(req): Json<DecryptRequest>,
+) -> Result<Json<TransactionDetails>, ApiError> {
+    let timeout = req.deadline_ms.map(Duration::from_millis).or(state.deadline);
+    let deadline = Deadline::after(timeout);
+
+    // Trial decryption is CPU-bound; keep it off the async workers. The task
+    // also checks the deadline between stages, so it stops soon after a
+    // timed-out response instead of running to completion.
+    let task = tokio::task::spawn_blocking(move || decrypt_request(&req, deadline));
+    let joined = match deadline.remaining() {
+        Some(remaining) => tokio::time::timeout(remaining, task).await.map_err(|_| {
+            ApiError(StatusCode::GATEWAY_TIMEOUT, anyhow!("Deadline exceeded"))
+        })?,
+        None => task.await,
+    };
+    let report = joined
+        .map_err(|e| anyhow!("Decryption task failed: {}", e))?
+        .map_err(|e| {
+            let status = if deadline.expired() {
+                StatusCode::GATEWAY_TIMEOUT
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            ApiError(status, e)
+        })?;
+    Ok(Json(TransactionDetails::try_from(&report)?))
+}
+
+/// Query string of `GET /balance/{account}`.
+#[derive(Debug, Deserialize)]
+struct BalanceQuery {
+    /// Which `--network-db` to read
+    network: Option<String>,
+}
+
+async fn balance(
+    State(state): State<AppState>,
+    UrlPath(account): UrlPath<u32>,
+    Query(query): Query<BalanceQuery>,
+) -> Result<Json<Balance>, ApiError> {
+    let (network, store) = state.stores.get(query.network.as_deref())?;
+    let mut balance = tokio::task::spawn_blocking(move || {
+        let store = store.lock().map_err(|_| anyhow!("Result store lock poisoned"))?;
+        store.balance(account)
+    })
+    .await
+    .map_err(|e| anyhow!("Balance task failed: {}", e))??;
+    // An empty store doesn't know its network yet
+    if balance.network.is_none() {
+        balance.network = network.map(str::to_string);
+    }
+    Ok(Json(balance))
+}
+
+fn decrypt_request(req: &DecryptRequest, deadline: Deadline) -> Result<TxReport> {
+    let requested = req
+        .network
+        .as_deref()
+        .map(network::parse_network_type)
+        .transpose()
+        .map_err(|e| anyhow!(e))?;
+    let network = network::resolve_network(requested, None, &req.ufvk)?;
+    let ufvk = UnifiedFullViewingKey::decode(&network, &req.ufvk)
+        .map_err(|e| anyhow!("Failed to decode UFVK: {}", e))?;
+
+    let tx_bytes = hex::decode(req.raw_tx.trim())
+        .context("Raw transaction hex is invalid (not hex or empty)")?;
+    if tx_bytes.is_empty() {
+        return Err(anyhow!("Transaction data is empty"));
+    }
+
+    let decryptor = Decryptor::new(network, vec![ufvk], None, true);
+    let options = DecryptOptions {
+        deadline,
+        ..Default::default()
+    };
+    decryptor.decrypt_with(BlockHeight::from_u32(req.height), Some(&req.txid), &tx_bytes, options)
+}
@@ -0,0 +1,91 @@
+//! Stopping long-running modes cleanly on SIGINT (Ctrl-C) or SIGTERM.
+//!
+//! The first signal asks the mode to stop where nothing is half done: `sync`
+//! and `scan-compact` after the block in hand, `watch` after the transaction
+//! in hand, `serve` once open requests are answered. Queued webhook and sink
+//! events are then delivered (or spilled) as on a normal exit. A second signal
+//! exits at once.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::watch;
+
+/// Exit code after a second signal, as for a process killed by SIGINT.
+const FORCED_EXIT: i32 = 130;
+
+/// Whether a stop was requested. Cheap to clone and check.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Start listening for SIGINT and SIGTERM. Must be called within the tokio
+    /// runtime.
+    pub fn listen() -> Result<Self> {
+        let (request, requested) = watch::channel(false);
+        let mut signals = Signals::new()?;
+        tokio::spawn(async move {
+            let name = signals.next().await;
+            tracing::warn!(
+                "{} received; finishing the work in hand (again to stop at once)",
+                name
+            );
+            let _ = request.send(true);
+            let name = signals.next().await;
+            tracing::warn!("{} received again; stopping at once", name);
+            std::process::exit(FORCED_EXIT);
+        });
+        Ok(Shutdown(requested))
+    }
+
+    pub fn requested(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until a stop is requested.
+    pub async fn wait(&self) {
+        let mut requested = self.0.clone();
+        if requested.wait_for(|stop| *stop).await.is_err() {
+            // The listener is gone, so no stop can come
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Sleep for `duration`, or until a stop is requested.
+    pub async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.wait() => {}
+        }
+    }
+}
+
+struct Signals {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    fn new() -> Result<Self> {
+        Ok(Signals {
+            #[cfg(unix)]
+            terminate: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .map_err(|e| anyhow::anyhow!("Failed to listen for SIGTERM: {}", e))?,
+        })
+    }
+
+    /// Wait for the next signal, returning its name.
+    #[cfg(unix)]
+    async fn next(&mut self) -> &'static str {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = self.terminate.recv() => "SIGTERM",
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn next(&mut self) -> &'static str {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use rand::Rng;
+
+use crate::{
+    models::{idempotency_key, BundleStats, OutputInfo, TxReport},
+    sink::EventSink,
+};
+
+/// Memo carried by every simulated output, so they can't be mistaken for real
+/// payments downstream.
+const SIMULATED_MEMO: &str = "SIMULATED PAYMENT - NOT ON CHAIN";
+
+/// Parse a `--rate` value such as `5/min`, `1/s` or `30/h` into the delay
+/// between events.
+pub fn parse_rate(s: &str) -> Result<Duration, String> {
+    let (count, unit) = s
+        .split_once('/')
+        .ok_or_else(|| format!("invalid rate '{}' (expected e.g. 5/min)", s))?;
+    let count: f64 = count
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rate count '{}'", count))?;
+    if count <= 0.0 {
+        return Err("rate must be positive".to_string());
+    }
+    let unit_secs = match unit.trim() {
+        "s" | "sec" => 1.0,
+        "m" | "min" => 60.0,
+        "h" | "hour" => 3600.0,
+        other => return Err(format!("unknown rate unit '{}' (expected s, min or h)", other)),
+    };
+    Ok(Duration::from_secs_f64(unit_secs / count))
+}
+
+/// Parse a `--amount-range` value in ZEC such as `0.1..2` into zatoshi bounds.
+pub fn parse_amount_range(s: &str) -> Result<(i64, i64), String> {
+    let (lo, hi) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid amount range '{}' (expected e.g. 0.1..2)", s))?;
+    let to_zats = |v: &str| -> Result<i64, String> {
+        let zec: f64 = v
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid ZEC amount '{}'", v))?;
+        Ok((zec * 100_000_000.0).round() as i64)
+    };
+    let (lo, hi) = (to_zats(lo)?, to_zats(hi)?);
+    if lo < 0 || hi < lo {
+        return Err(format!("invalid amount range '{}'", s));
+    }
+    Ok((lo, hi))
+}
+
+/// Emit fabricated `simulated_output` events through `sink` at the given
+/// interval, stopping after `count` events if set.
+pub async fn run(
+    sink: &mut EventSink<'_>,
+    interval: Duration,
+    amount_range: (i64, i64),
+    count: Option<u64>,
+) -> Result<()> {
+    let mut emitted = 0u64;
+    while count.is_none_or(|c| emitted < c) {
+        let report = fabricate(amount_range);
+        sink.emit("simulated_output", &report, 0, false)?;
+        emitted += 1;
+
+        if count.is_none_or(|c| emitted < c) {
+            tokio::time::sleep(interval).await;
+        }
+    }
+    Ok(())
+}
+
+/// A single-output incoming "transaction" with a random TXID and amount.
+fn fabricate((min_zats, max_zats): (i64, i64)) -> TxReport {
+    let mut rng = rand::thread_rng();
+    let protocol = if rng.gen_bool(0.5) { "Orchard" } else { "Sapling" };
+    let txid = hex::encode(rng.gen::<[u8; 32]>());
+
+    TxReport {
+        txid: txid.clone(),
+        height: 0,
+        tx_size_bytes: 0,
+        tx_version: 5,
+        decrypted_at: Utc::now(),
+        block_time: None,
+        block_hash: None,
+        network: None,
+        confirmations: None,
+        is_final: None,
+        outputs: vec![OutputInfo {
+            protocol: protocol.to_string(),
+            amount_zats: rng.gen_range(min_zats..=max_zats),
+            index: 0,
+            account: 0,
+            idempotency_key: idempotency_key(&txid, protocol, 0, "simulated"),
+            account_label: None,
+            counterparty: None,
+            payment_reference: None,
+            transfer_type: "Incoming".to_string(),
+            direction: "received".to_string(),
+            source: None,
+            funding_stream: None,
+            memo: SIMULATED_MEMO.to_string(),
+            address: None,
+            recipient_address: None,
+            diversifier_index: None,
+            note_commitment: None,
+            position: None,
+            nullifier: None,
+            detail: None,
+        }],
+        opaque: Vec::new(),
+        bundles: BundleStats::default(),
+        transparent_output_zats: 0,
+        spent_nullifiers: Vec::new(),
+        fiat: None,
+        filtered: None,
+        key_capabilities: None,
+        warnings: Vec::new(),
+    }
+}
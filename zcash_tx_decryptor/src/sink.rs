@@ -0,0 +1,287 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::{
+    models::{FilteredEvent, PaymentEvent, ReorgEvent, StoredOutput, TxReport},
+    pipeline::Forwarder,
+    seal::Sealer,
+    store::ResultStore,
+};
+
+/// Destination for decrypted-output events in long-running modes.
+///
+/// Every event is written as one NDJSON line, to stdout or to an `--output`
+/// file; if a result store is attached, the transaction's outputs are recorded
+/// there as well. With a [`Sealer`], each line is encrypted on its own. With a
+/// [`Forwarder`] per `--webhook` or `--sink`, each event is also delivered
+/// there, unencrypted.
+pub struct EventSink<'a> {
+    store: Option<&'a mut ResultStore>,
+    output: Option<NdjsonFile>,
+    sealer: Option<Sealer>,
+    forwarders: Vec<Forwarder>,
+    network: Option<&'static str>,
+}
+
+impl<'a> EventSink<'a> {
+    pub fn new(store: Option<&'a mut ResultStore>) -> Self {
+        EventSink {
+            store,
+            output: None,
+            sealer: None,
+            forwarders: Vec::new(),
+            network: None,
+        }
+    }
+
+    /// Write events to `output` instead of stdout.
+    pub fn with_output(mut self, output: Option<NdjsonFile>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Encrypt every line to the sealer's recipients (`--encrypt-to`).
+    pub fn with_sealer(mut self, sealer: Option<Sealer>) -> Self {
+        self.sealer = sealer;
+        self
+    }
+
+    /// Also deliver every event through `forwarders`.
+    pub fn with_forwarders(mut self, forwarders: Vec<Forwarder>) -> Self {
+        self.forwarders = forwarders;
+        self
+    }
+
+    /// Name the network in `reorg` events; payment events take it from the
+    /// report.
+    pub fn with_network(mut self, network: Option<&'static str>) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// The attached result store, if any.
+    pub fn store(&mut self) -> Option<&mut ResultStore> {
+        self.store.as_deref_mut()
+    }
+
+    /// Wait for queued webhook and sink deliveries and get the `--output`
+    /// file onto disk; call before exiting.
+    pub async fn close(self) {
+        for forwarder in self.forwarders {
+            forwarder.finish().await;
+        }
+        if let Some(Err(e)) = self.output.as_ref().map(NdjsonFile::sync) {
+            tracing::warn!("{:#}", e);
+        }
+    }
+
+    /// Emit one `kind` event per output of `report`, then a
+    /// `filtered_outputs` event if the report summarizes left-out outputs.
+    pub fn emit(
+        &mut self,
+        kind: &str,
+        report: &TxReport,
+        expected_height: u32,
+        confirmed: bool,
+    ) -> Result<()> {
+        if let Some(store) = self.store.as_deref_mut() {
+            store.insert(report, confirmed)?;
+        }
+
+        let detected_at = Utc::now();
+        for output in &report.outputs {
+            let event = PaymentEvent {
+                event: kind.to_string(),
+                transaction_id: report.txid.clone(),
+                detected_at,
+                expected_height,
+                network: report.network.map(str::to_string),
+                output: output.clone(),
+            };
+            self.write(&serde_json::to_string(&event)?, expected_height)?;
+        }
+        if let Some(filtered) = &report.filtered {
+            let event = FilteredEvent {
+                event: "filtered_outputs".to_string(),
+                transaction_id: report.txid.clone(),
+                detected_at,
+                expected_height,
+                network: report.network.map(str::to_string),
+                filtered: filtered.clone(),
+            };
+            self.write(&serde_json::to_string(&event)?, expected_height)?;
+        }
+        Ok(())
+    }
+
+    /// Emit one `reorg` event listing the stored outputs that were rolled
+    /// back from `fork_height` up.
+    pub fn emit_reorg(&mut self, fork_height: u32, rolled_back: Vec<StoredOutput>) -> Result<()> {
+        let event = ReorgEvent {
+            event: "reorg".to_string(),
+            detected_at: Utc::now(),
+            fork_height,
+            network: self.network.map(str::to_string),
+            rolled_back,
+        };
+        self.write(&serde_json::to_string(&event)?, fork_height)
+    }
+
+    fn write(&mut self, line: &str, height: u32) -> Result<()> {
+        for forwarder in &self.forwarders {
+            forwarder.send(line)?;
+        }
+        let line = match &self.sealer {
+            Some(sealer) => sealer.line(line)?,
+            None => line.to_string(),
+        };
+        match self.output.as_mut() {
+            Some(file) => file.write_line(&line, height),
+            None => {
+                println!("{}", line);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// When an `--output` file is rotated; either, both or neither may be set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rotation {
+    /// Rotate before a line would take the file past this many bytes
+    pub max_bytes: Option<u64>,
+    /// Rotate when an event's height enters a new range of this many blocks
+    pub blocks: Option<u32>,
+}
+
+/// An NDJSON event file, written a whole line at a time and never buffered,
+/// so a tailing reader sees each event as soon as it is emitted.
+///
+/// The live file keeps its path. On rotation it is renamed to
+/// `<stem>.<n>.<ext>`, with `n` one higher than any rotated file already
+/// there, so readers can follow the fixed path and ingest rotated files in
+/// order.
+pub struct NdjsonFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    rotation: Rotation,
+    /// Height range (height / `rotation.blocks`) of the live file's events
+    range: Option<u32>,
+}
+
+impl NdjsonFile {
+    /// Open `path`, appending to an existing file if `append` is set and
+    /// truncating it otherwise.
+    pub fn open(path: &Path, append: bool, rotation: Rotation) -> Result<Self> {
+        let file = open_file(path, append)?;
+        let written = file.metadata()?.len();
+        Ok(NdjsonFile {
+            path: path.to_path_buf(),
+            file,
+            written,
+            rotation,
+            range: None,
+        })
+    }
+
+    fn write_line(&mut self, line: &str, height: u32) -> Result<()> {
+        let len = line.len() as u64 + 1;
+        let range = self.rotation.blocks.map(|blocks| height / blocks.max(1));
+        let full = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| self.written + len > max);
+        let new_range = self.range.is_some() && range != self.range;
+        if self.written > 0 && (full || new_range) {
+            self.rotate()?;
+        }
+        self.range = range;
+
+        // One write per line, so readers never see half an event
+        self.file
+            .write_all(format!("{}\n", line).as_bytes())
+            .with_context(|| format!("Failed to write to {}", self.path.display()))?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.file
+            .sync_all()
+            .with_context(|| format!("Failed to sync {}", self.path.display()))
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = self.next_rotated_path()?;
+        std::fs::rename(&self.path, &rotated).with_context(|| {
+            format!("Failed to rotate {} to {}", self.path.display(), rotated.display())
+        })?;
+        self.file = open_file(&self.path, false)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    fn next_rotated_path(&self) -> Result<PathBuf> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = self
+            .path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+
+        let prefix = format!("{}.", stem);
+        let mut last = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let n = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(&ext))
+                .and_then(|n| n.parse::<u64>().ok());
+            last = last.max(n.unwrap_or(0));
+        }
+        Ok(dir.join(format!("{}{}{}", prefix, last + 1, ext)))
+    }
+}
+
+fn open_file(path: &Path, append: bool) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .with_context(|| format!("Failed to open output file {}", path.display()))
+}
+
+/// Parse a `--rotate-size` value such as `100M`, `512K`, `1G` or a plain byte
+/// count.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid size '{}' (expected e.g. 100M)", s))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        other => return Err(format!("unknown size unit '{}' (expected K, M or G)", other)),
+    };
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{}' is too large", s))
+}
@@ -0,0 +1,60 @@
+use std::{collections::HashSet, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use zcash_primitives::transaction::Transaction;
+
+use crate::models::DetectedSpend;
+
+/// Load a nullifier list: one 32-byte hex nullifier per line, as printed in
+/// `OutputInfo::nullifier` or `audit unspent` attestations. Blank lines and
+/// `#` comments are ignored.
+pub fn load_nullifiers(path: &Path) -> Result<HashSet<[u8; 32]>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read nullifier list {}", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            hex::decode(line)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| anyhow!("Line {}: expected a 64-character hex nullifier", i + 1))
+        })
+        .collect()
+}
+
+/// Sapling spends and Orchard actions in `tx` that reveal one of `nullifiers`.
+pub fn detect_spends(
+    tx: &Transaction,
+    txid: &str,
+    nullifiers: &HashSet<[u8; 32]>,
+) -> Vec<DetectedSpend> {
+    let sapling = tx.sapling_bundle().into_iter().flat_map(|bundle| {
+        bundle
+            .shielded_spends()
+            .iter()
+            .enumerate()
+            .map(|(index, spend)| ("Sapling", index, spend.nullifier().0))
+    });
+    let orchard = tx.orchard_bundle().into_iter().flat_map(|bundle| {
+        bundle
+            .actions()
+            .iter()
+            .enumerate()
+            .map(|(index, action)| ("Orchard", index, action.nullifier().to_bytes()))
+    });
+
+    sapling
+        .chain(orchard)
+        .filter(|(_, _, nf)| nullifiers.contains(nf))
+        .map(|(protocol, index, nf)| DetectedSpend {
+            transaction_id: txid.to_string(),
+            protocol: protocol.to_string(),
+            index,
+            nullifier: hex::encode(nf),
+        })
+        .collect()
+}
@@ -0,0 +1,706 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    coinbase::COINBASE_MATURITY,
+    models::{idempotency_key, Balance, StoredOutput, TxReport},
+};
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how many
+/// have run, so existing databases are upgraded in place on open.
+const MIGRATIONS: &[&str] = &[
+    // 1: decrypted outputs
+    "CREATE TABLE outputs (
+        id            INTEGER PRIMARY KEY,
+        txid          TEXT    NOT NULL,
+        height        INTEGER NOT NULL,
+        confirmed     INTEGER NOT NULL,
+        account       INTEGER NOT NULL,
+        protocol      TEXT    NOT NULL,
+        output_index  INTEGER NOT NULL,
+        transfer_type TEXT    NOT NULL,
+        value_zats    INTEGER NOT NULL,
+        memo          TEXT    NOT NULL,
+        address       TEXT,
+        recorded_at   TEXT    NOT NULL,
+        UNIQUE (txid, protocol, output_index, account)
+    );
+    CREATE INDEX outputs_height ON outputs (height);
+    CREATE INDEX outputs_account ON outputs (account);",
+    // 2: authoritative block time, filled in by `db enrich`
+    "ALTER TABLE outputs ADD COLUMN block_time TEXT;",
+    // 3: `--ufvk label=...` of the account
+    "ALTER TABLE outputs ADD COLUMN account_label TEXT;",
+    // 4: ZIP-32 diversifier index of the receiving address
+    "ALTER TABLE outputs ADD COLUMN diversifier_index INTEGER;",
+    // 5: hashes of scanned blocks, for reorg detection
+    "CREATE TABLE blocks (
+        height INTEGER PRIMARY KEY,
+        hash   TEXT    NOT NULL
+    );",
+    // 6: `--tags` counterparty of the output
+    "ALTER TABLE outputs ADD COLUMN counterparty TEXT;",
+    // 7: payment reference extracted from the memo
+    "ALTER TABLE outputs ADD COLUMN payment_reference TEXT;",
+    // 8: coinbase outputs and the funding stream they pay
+    "ALTER TABLE outputs ADD COLUMN source TEXT;
+     ALTER TABLE outputs ADD COLUMN funding_stream TEXT;",
+    // 9: network the output was decrypted on
+    "ALTER TABLE outputs ADD COLUMN network TEXT;",
+    // 10: idempotency key as emitted; older rows get the positional form
+    "ALTER TABLE outputs ADD COLUMN idempotency_key TEXT;",
+    // 11: nullifiers of received notes and the transactions that spent them
+    "ALTER TABLE outputs ADD COLUMN nullifier TEXT;
+     ALTER TABLE outputs ADD COLUMN spent_txid TEXT;
+     ALTER TABLE outputs ADD COLUMN spent_height INTEGER;
+     CREATE INDEX outputs_nullifier ON outputs (nullifier);",
+    // 12: value balances of each recorded transaction, for deriving fees
+    "CREATE TABLE transactions (
+        txid                    TEXT    PRIMARY KEY,
+        height                  INTEGER NOT NULL,
+        confirmed               INTEGER NOT NULL,
+        transparent_inputs      INTEGER NOT NULL,
+        sapling_value_balance   INTEGER NOT NULL,
+        orchard_value_balance   INTEGER NOT NULL,
+        sprout_value_balance    INTEGER NOT NULL,
+        transparent_output_zats INTEGER NOT NULL,
+        block_time              TEXT,
+        recorded_at             TEXT    NOT NULL
+    );",
+    // 13: outputs unique by idempotency key rather than by positional
+    // account, keeping the confirmed (then the first) of any duplicates
+    "UPDATE outputs
+     SET idempotency_key = txid || ':' || lower(protocol) || ':' || output_index || ':' || account
+     WHERE idempotency_key IS NULL OR idempotency_key = '';
+     CREATE TABLE outputs_new (
+        id                INTEGER PRIMARY KEY,
+        txid              TEXT    NOT NULL,
+        height            INTEGER NOT NULL,
+        confirmed         INTEGER NOT NULL,
+        account           INTEGER NOT NULL,
+        protocol          TEXT    NOT NULL,
+        output_index      INTEGER NOT NULL,
+        transfer_type     TEXT    NOT NULL,
+        value_zats        INTEGER NOT NULL,
+        memo              TEXT    NOT NULL,
+        address           TEXT,
+        recorded_at       TEXT    NOT NULL,
+        block_time        TEXT,
+        account_label     TEXT,
+        diversifier_index INTEGER,
+        counterparty      TEXT,
+        payment_reference TEXT,
+        source            TEXT,
+        funding_stream    TEXT,
+        network           TEXT,
+        idempotency_key   TEXT    NOT NULL,
+        nullifier         TEXT,
+        spent_txid        TEXT,
+        spent_height      INTEGER
+     );
+     INSERT INTO outputs_new
+     SELECT id, txid, height, confirmed, account, protocol, output_index, transfer_type,
+            value_zats, memo, address, recorded_at, block_time, account_label,
+            diversifier_index, counterparty, payment_reference, source, funding_stream,
+            network, idempotency_key, nullifier, spent_txid, spent_height
+     FROM outputs AS o
+     WHERE NOT EXISTS (
+        SELECT 1 FROM outputs AS d
+        WHERE d.idempotency_key = o.idempotency_key
+          AND (d.confirmed > o.confirmed OR (d.confirmed = o.confirmed AND d.id < o.id))
+     );
+     DROP TABLE outputs;
+     ALTER TABLE outputs_new RENAME TO outputs;
+     CREATE UNIQUE INDEX outputs_idempotency_key ON outputs (idempotency_key);
+     CREATE INDEX outputs_height ON outputs (height);
+     CREATE INDEX outputs_account ON outputs (account);
+     CREATE INDEX outputs_nullifier ON outputs (nullifier);",
+];
+
+/// Filters for [`ResultStore::query`]; `None` means unbounded.
+#[derive(Debug, Default)]
+pub struct OutputFilter {
+    pub from_height: Option<u32>,
+    pub to_height: Option<u32>,
+    pub account: Option<u32>,
+    pub min_value_zats: Option<i64>,
+}
+
+/// The fee of a transaction that spent an account's notes, as derived by
+/// [`ResultStore::fees`].
+#[derive(Debug)]
+pub struct PaidFee {
+    pub account: u32,
+    pub txid: String,
+    pub height: u32,
+    /// Block time where known, otherwise when the transaction was recorded
+    pub time: String,
+    pub fee_zats: i64,
+}
+
+/// SQLite ledger of decrypted outputs.
+pub struct ResultStore {
+    conn: Connection,
+}
+
+impl ResultStore {
+    /// Open (or create) the database at `path` and bring its schema up to date.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open result database {}", path.display()))?;
+        let mut store = ResultStore { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Open like [`open`](Self::open), failing if the database already holds
+    /// outputs from a network other than `network`, so one database never
+    /// mixes mainnet and testnet funds.
+    pub fn open_for(path: &Path, network: &str) -> Result<Self> {
+        let store = Self::open(path)?;
+        match store.network()? {
+            Some(recorded) if recorded != network => Err(anyhow!(
+                "Result database {} holds {} outputs, not {}; use one database per network",
+                path.display(),
+                recorded,
+                network
+            )),
+            _ => Ok(store),
+        }
+    }
+
+    /// How many [`MIGRATIONS`] the database has had applied.
+    pub fn schema_version(&self) -> Result<usize> {
+        self.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        let applied = self.schema_version()?;
+        if applied > MIGRATIONS.len() {
+            return Err(anyhow!(
+                "Result database schema version {} is newer than this build supports ({})",
+                applied,
+                MIGRATIONS.len()
+            ));
+        }
+
+        for (i, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(sql)
+                .with_context(|| format!("Result database migration {} failed", i + 1))?;
+            tx.pragma_update(None, "user_version", i + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Persist every decrypted output of a transaction. Outputs are unique by
+    /// idempotency key, and those already stored are left untouched, so
+    /// re-running a scan is harmless, even with the `--ufvk` flags reordered,
+    /// except that a mempool hit seen again as confirmed is promoted with its
+    /// mined height, and that an output takes its account's current position.
+    ///
+    /// `confirmed` is false for mempool hits, whose height is only the
+    /// expected mining height.
+    ///
+    /// The transaction's value balances are recorded alongside, for
+    /// [`fees`](Self::fees).
+    pub fn insert(&mut self, report: &TxReport, confirmed: bool) -> Result<()> {
+        let recorded_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO transactions (
+                txid, height, confirmed, transparent_inputs, sapling_value_balance,
+                orchard_value_balance, sprout_value_balance, transparent_output_zats,
+                block_time, recorded_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT (txid) DO UPDATE
+             SET confirmed = 1, height = excluded.height
+             WHERE excluded.confirmed AND NOT transactions.confirmed",
+            params![
+                report.txid,
+                report.height,
+                confirmed,
+                report.bundles.transparent_inputs as i64,
+                report.bundles.sapling_value_balance_zats,
+                report.bundles.orchard_value_balance_zats,
+                report.opaque.iter().map(|c| c.net_value_zats).sum::<i64>(),
+                report.transparent_output_zats,
+                report.block_time.map(|time| time.to_rfc3339()),
+                recorded_at,
+            ],
+        )?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO outputs (
+                    txid, height, confirmed, account, protocol, output_index,
+                    transfer_type, value_zats, memo, address, recorded_at, account_label,
+                    diversifier_index, counterparty, payment_reference, source, funding_stream,
+                    network, idempotency_key, nullifier
+                 ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                    ?18, ?19, ?20
+                 )
+                 ON CONFLICT (idempotency_key) DO UPDATE
+                 SET account = excluded.account,
+                     height = CASE WHEN excluded.confirmed AND NOT outputs.confirmed
+                                   THEN excluded.height ELSE outputs.height END,
+                     confirmed = MAX(outputs.confirmed, excluded.confirmed)",
+            )?;
+            for out in &report.outputs {
+                stmt.execute(params![
+                    report.txid,
+                    report.height,
+                    confirmed,
+                    out.account,
+                    out.protocol,
+                    out.index as i64,
+                    out.transfer_type,
+                    out.amount_zats,
+                    out.memo,
+                    // Outgoing rows record whom we paid
+                    out.address.as_ref().or(out.recipient_address.as_ref()),
+                    recorded_at,
+                    out.account_label,
+                    out.diversifier_index.map(|i| i as i64),
+                    out.counterparty,
+                    out.payment_reference,
+                    out.source,
+                    out.funding_stream,
+                    report.network,
+                    out.idempotency_key,
+                    out.nullifier,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stored outputs matching `filter`, ordered by height then position.
+    pub fn query(&self, filter: &OutputFilter) -> Result<Vec<StoredOutput>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT txid, height, confirmed, account, protocol, output_index,
+                    transfer_type, value_zats, memo, address, recorded_at, block_time,
+                    account_label, diversifier_index, counterparty, payment_reference,
+                    source, funding_stream, network, idempotency_key
+             FROM outputs
+             WHERE (?1 IS NULL OR height >= ?1)
+               AND (?2 IS NULL OR height <= ?2)
+               AND (?3 IS NULL OR account = ?3)
+               AND (?4 IS NULL OR value_zats >= ?4)
+             ORDER BY height, txid, protocol, output_index, account",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                filter.from_height,
+                filter.to_height,
+                filter.account,
+                filter.min_value_zats
+            ],
+            |row| {
+                let mut output = StoredOutput {
+                    transaction_id: row.get(0)?,
+                    block_height: row.get(1)?,
+                    confirmed: row.get(2)?,
+                    network: row.get(18)?,
+                    account: row.get(3)?,
+                    idempotency_key: row.get::<_, Option<String>>(19)?.unwrap_or_default(),
+                    protocol: row.get(4)?,
+                    index: row.get::<_, i64>(5)? as usize,
+                    transfer_type: row.get(6)?,
+                    amount_zats: row.get(7)?,
+                    memo: row.get(8)?,
+                    address: row.get(9)?,
+                    recorded_at: row.get(10)?,
+                    block_time: row.get(11)?,
+                    account_label: row.get(12)?,
+                    diversifier_index: row.get::<_, Option<i64>>(13)?.map(|i| i as u64),
+                    counterparty: row.get(14)?,
+                    payment_reference: row.get(15)?,
+                    source: row.get(16)?,
+                    funding_stream: row.get(17)?,
+                };
+                // Stored before keys named accounts by label or fingerprint
+                if output.idempotency_key.is_empty() {
+                    output.idempotency_key = idempotency_key(
+                        &output.transaction_id,
+                        &output.protocol,
+                        output.index,
+                        &output.account.to_string(),
+                    );
+                }
+                Ok(output)
+            },
+        )?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Network the stored outputs were decrypted on, if any recorded one.
+    pub fn network(&self) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT network FROM outputs WHERE network IS NOT NULL LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Transactions whose block time has not been filled in yet: everything
+    /// recorded offline or from the mempool and not enriched since.
+    pub fn unenriched_txids(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT txid FROM outputs WHERE block_time IS NULL ORDER BY txid")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Record where `txid` was mined, replacing any hinted or expected height
+    /// and marking its outputs confirmed. Returns the number of outputs updated.
+    pub fn set_block(&mut self, txid: &str, height: u32, block_time: &str) -> Result<usize> {
+        let updated = self.conn.execute(
+            "UPDATE outputs SET height = ?2, confirmed = 1, block_time = ?3 WHERE txid = ?1",
+            params![txid, height, block_time],
+        )?;
+        self.conn.execute(
+            "UPDATE transactions SET height = ?2, confirmed = 1, block_time = ?3 WHERE txid = ?1",
+            params![txid, height, block_time],
+        )?;
+        Ok(updated)
+    }
+
+    /// Hash recorded for the block at `height`, if it has been scanned.
+    pub fn block_hash(&self, height: u32) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT hash FROM blocks WHERE height = ?1",
+                params![height],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Highest scanned block height, if any block has been recorded.
+    pub fn scanned_tip(&self) -> Result<Option<u32>> {
+        self.conn
+            .query_row("SELECT MAX(height) FROM blocks", [], |row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Record `blocks` (height, hash) as scanned, replacing whatever was
+    /// recorded at the same heights.
+    pub fn record_blocks(&mut self, blocks: &[(u32, String)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare_cached("INSERT OR REPLACE INTO blocks (height, hash) VALUES (?1, ?2)")?;
+            for (height, hash) in blocks {
+                stmt.execute(params![height, hash])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Mark the stored notes whose nullifiers `spends` reveal as spent by the
+    /// transaction each is tagged with, mined at `height`. Nullifiers of notes
+    /// that aren't ours match nothing. Returns the number of notes marked.
+    pub fn mark_spent(&mut self, spends: &[([u8; 32], String)], height: u32) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut marked = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE outputs SET spent_txid = ?2, spent_height = ?3
+                 WHERE nullifier = ?1 AND transfer_type != 'Outgoing'",
+            )?;
+            for (nullifier, txid) in spends {
+                marked += stmt.execute(params![hex::encode(nullifier), txid, height])?;
+            }
+        }
+        tx.commit()?;
+        Ok(marked)
+    }
+
+    /// Forget every block from `fork_height` up, with the confirmed outputs
+    /// mined in them and the spends seen in them, and return the outputs
+    /// removed. Mempool hits are kept; their height was only ever an
+    /// expectation.
+    pub fn rollback(&mut self, fork_height: u32) -> Result<Vec<StoredOutput>> {
+        let filter = OutputFilter {
+            from_height: Some(fork_height),
+            ..OutputFilter::default()
+        };
+        let removed: Vec<StoredOutput> = self
+            .query(&filter)?
+            .into_iter()
+            .filter(|output| output.confirmed)
+            .collect();
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM outputs WHERE height >= ?1 AND confirmed",
+            params![fork_height],
+        )?;
+        tx.execute(
+            "DELETE FROM transactions WHERE height >= ?1 AND confirmed",
+            params![fork_height],
+        )?;
+        tx.execute(
+            "UPDATE outputs SET spent_txid = NULL, spent_height = NULL WHERE spent_height >= ?1",
+            params![fork_height],
+        )?;
+        tx.execute("DELETE FROM blocks WHERE height >= ?1", params![fork_height])?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Fees of the mined transactions between `from_height` and `to_height`
+    /// (inclusive, `None` for unbounded) that spent an account's notes,
+    /// charged to each such account. A transaction counts as the account's
+    /// when it spent one of its stored notes or paid it change or an
+    /// OVK-recovered output.
+    ///
+    /// Only fully shielded transactions are covered: the fee is the value
+    /// leaving the shielded pools minus the transparent outputs, and a
+    /// transaction with transparent inputs doesn't carry their values.
+    pub fn fees(&self, from_height: Option<u32>, to_height: Option<u32>) -> Result<Vec<PaidFee>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT o.account, t.txid, t.height, COALESCE(t.block_time, t.recorded_at),
+                    t.sapling_value_balance + t.orchard_value_balance + t.sprout_value_balance
+                        - t.transparent_output_zats
+             FROM transactions t
+             JOIN outputs o
+               ON o.spent_txid = t.txid
+               OR (o.txid = t.txid AND o.transfer_type IN ('Outgoing', 'WalletInternal'))
+             WHERE t.confirmed AND t.transparent_inputs = 0
+               AND (?1 IS NULL OR t.height >= ?1)
+               AND (?2 IS NULL OR t.height <= ?2)
+             ORDER BY t.height, t.txid, o.account",
+        )?;
+        let rows = stmt.query_map(params![from_height, to_height], |row| {
+            Ok(PaidFee {
+                account: row.get(0)?,
+                txid: row.get(1)?,
+                height: row.get(2)?,
+                time: row.get(3)?,
+                fee_zats: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Value of the notes `account` received and has not spent. Outgoing
+    /// outputs (notes sent to others, recovered with the OVK) are not counted.
+    ///
+    /// A note counts as spent once [`mark_spent`](Self::mark_spent) has seen
+    /// its nullifier. Sapling notes decrypted without their tree position
+    /// (outside `scan-compact`) have no nullifier and stay counted.
+    ///
+    /// Mined coinbase notes with fewer than [`COINBASE_MATURITY`]
+    /// confirmations are `immature_zats` rather than confirmed, counting from
+    /// the highest scanned block (or, without one, the highest stored output).
+    pub fn balance(&self, account: u32) -> Result<Balance> {
+        let tip: Option<u32> = match self.scanned_tip()? {
+            Some(tip) => Some(tip),
+            None => self
+                .conn
+                .query_row("SELECT MAX(height) FROM outputs WHERE confirmed", [], |row| {
+                    row.get(0)
+                })?,
+        };
+        let (confirmed_zats, pending_zats, immature_zats) = self.conn.query_row(
+            "SELECT COALESCE(SUM(CASE WHEN confirmed AND NOT immature THEN value_zats END), 0),
+                    COALESCE(SUM(CASE WHEN NOT confirmed THEN value_zats END), 0),
+                    COALESCE(SUM(CASE WHEN confirmed AND immature THEN value_zats END), 0)
+             FROM (
+                SELECT value_zats, confirmed,
+                       COALESCE(source = 'coinbase' AND ?2 - height + 1 < ?3, 0) AS immature
+                FROM outputs
+                WHERE account = ?1 AND transfer_type != 'Outgoing' AND spent_txid IS NULL
+             )",
+            params![account, tip, COINBASE_MATURITY],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        Ok(Balance {
+            account,
+            network: self.network()?,
+            confirmed_zats,
+            pending_zats,
+            immature_zats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::models::{BundleStats, OutputInfo};
+
+    fn received(txid: &str, height: u32, amount_zats: i64, nullifier: [u8; 32]) -> TxReport {
+        TxReport {
+            txid: txid.to_string(),
+            height,
+            tx_size_bytes: 0,
+            tx_version: 5,
+            decrypted_at: Utc::now(),
+            block_time: None,
+            block_hash: None,
+            network: Some("main"),
+            confirmations: None,
+            is_final: None,
+            outputs: vec![OutputInfo {
+                protocol: "Orchard".to_string(),
+                amount_zats,
+                index: 0,
+                account: 0,
+                idempotency_key: idempotency_key(txid, "Orchard", 0, "test"),
+                account_label: None,
+                counterparty: None,
+                payment_reference: None,
+                transfer_type: "Incoming".to_string(),
+                direction: "received".to_string(),
+                source: None,
+                funding_stream: None,
+                memo: String::new(),
+                address: None,
+                recipient_address: None,
+                diversifier_index: None,
+                note_commitment: None,
+                position: None,
+                nullifier: Some(hex::encode(nullifier)),
+                detail: None,
+            }],
+            opaque: Vec::new(),
+            bundles: BundleStats::default(),
+            transparent_output_zats: 0,
+            spent_nullifiers: Vec::new(),
+            fiat: None,
+            filtered: None,
+            key_capabilities: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn confirmed_zats(store: &ResultStore) -> i64 {
+        store.balance(0).unwrap().confirmed_zats
+    }
+
+    #[test]
+    fn spent_notes_leave_the_balance() {
+        let mut store = ResultStore::open(Path::new(":memory:")).unwrap();
+        store.insert(&received("aa", 100, 70_000, [1; 32]), true).unwrap();
+        store.insert(&received("bb", 101, 30_000, [2; 32]), true).unwrap();
+        assert_eq!(confirmed_zats(&store), 100_000);
+
+        // Someone else's spend changes nothing
+        assert_eq!(store.mark_spent(&[([9; 32], "cc".to_string())], 102).unwrap(), 0);
+        assert_eq!(confirmed_zats(&store), 100_000);
+
+        assert_eq!(store.mark_spent(&[([1; 32], "dd".to_string())], 103).unwrap(), 1);
+        assert_eq!(confirmed_zats(&store), 30_000);
+
+        // A reorg below the spend makes the note spendable again
+        store.rollback(103).unwrap();
+        assert_eq!(confirmed_zats(&store), 100_000);
+    }
+
+    #[test]
+    fn fees_of_shielded_spends_are_derived() {
+        let mut store = ResultStore::open(Path::new(":memory:")).unwrap();
+        store.insert(&received("aa", 100, 70_000, [1; 32]), true).unwrap();
+
+        // Spends the note: 20_000 to others, 40_000 change, 10_000 fee
+        let mut spend = received("bb", 101, 40_000, [2; 32]);
+        spend.outputs[0].transfer_type = "WalletInternal".to_string();
+        spend.bundles.orchard_value_balance_zats = 30_000;
+        spend.transparent_output_zats = 20_000;
+        store.insert(&spend, true).unwrap();
+        store.mark_spent(&[([1; 32], "bb".to_string())], 101).unwrap();
+
+        // Transparent inputs hide part of what went in
+        let mut shielding = received("cc", 102, 50_000, [3; 32]);
+        shielding.outputs[0].transfer_type = "WalletInternal".to_string();
+        shielding.bundles.transparent_inputs = 1;
+        shielding.bundles.orchard_value_balance_zats = -50_000;
+        store.insert(&shielding, true).unwrap();
+
+        let fees = store.fees(None, None).unwrap();
+        assert_eq!(fees.len(), 1);
+        assert_eq!((fees[0].account, fees[0].txid.as_str()), (0, "bb"));
+        assert_eq!(fees[0].fee_zats, 10_000);
+        assert!(store.fees(Some(102), None).unwrap().is_empty());
+
+        store.rollback(101).unwrap();
+        assert!(store.fees(None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn young_coinbase_notes_are_immature() {
+        let mut store = ResultStore::open(Path::new(":memory:")).unwrap();
+        let mut reward = received("aa", 1_000, 250_000_000, [1; 32]);
+        reward.outputs[0].source = Some("coinbase".to_string());
+        store.insert(&reward, true).unwrap();
+
+        store.record_blocks(&[(1_098, "00".to_string())]).unwrap();
+        let balance = store.balance(0).unwrap();
+        assert_eq!((balance.confirmed_zats, balance.immature_zats), (0, 250_000_000));
+
+        store.record_blocks(&[(1_099, "00".to_string())]).unwrap();
+        let balance = store.balance(0).unwrap();
+        assert_eq!((balance.confirmed_zats, balance.immature_zats), (250_000_000, 0));
+    }
+
+    #[test]
+    fn reordered_keys_keep_one_row_per_output() {
+        let mut store = ResultStore::open(Path::new(":memory:")).unwrap();
+        store.insert(&received("aa", 100, 70_000, [1; 32]), true).unwrap();
+
+        // The same key passed second on the next run
+        let mut again = received("aa", 100, 70_000, [1; 32]);
+        again.outputs[0].account = 1;
+        store.insert(&again, true).unwrap();
+
+        assert_eq!(store.query(&OutputFilter::default()).unwrap().len(), 1);
+        assert_eq!(store.balance(0).unwrap().confirmed_zats, 0);
+        assert_eq!(store.balance(1).unwrap().confirmed_zats, 70_000);
+    }
+
+    #[test]
+    fn migration_13_removes_duplicate_keys() {
+        let conn = Connection::open_in_memory().unwrap();
+        for sql in &MIGRATIONS[..12] {
+            conn.execute_batch(sql).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 12).unwrap();
+        let insert = "INSERT INTO outputs (
+                txid, height, confirmed, account, protocol, output_index, transfer_type,
+                value_zats, memo, recorded_at, idempotency_key
+            ) VALUES (?1, 100, ?2, ?3, 'Orchard', 0, 'Incoming', 70000, '', '', ?4)";
+        conn.execute(insert, params!["aa", false, 0, "aa:orchard:0:treasury"]).unwrap();
+        conn.execute(insert, params!["aa", true, 1, "aa:orchard:0:treasury"]).unwrap();
+        conn.execute(insert, params!["bb", true, 0, None::<String>]).unwrap();
+
+        let mut store = ResultStore { conn };
+        store.migrate().unwrap();
+        let outputs = store.query(&OutputFilter::default()).unwrap();
+        let kept: Vec<_> = outputs
+            .iter()
+            .map(|out| (out.idempotency_key.as_str(), out.account, out.confirmed))
+            .collect();
+        assert_eq!(kept, [("aa:orchard:0:treasury", 1, true), ("bb:orchard:0:0", 0, true)]);
+    }
+}
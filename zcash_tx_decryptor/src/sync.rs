@@ -0,0 +1,468 @@
+//! `sync`: keep a result store in step with the chain. Starting from the
+//! store's checkpoint (the highest block recorded), or from `--birthday` in a
+//! new store, full blocks are fetched from the node and trial-decrypted in
+//! batches until the tip is reached; after that the tip is polled for new
+//! blocks.
+//!
+//! A block's outputs are stored and emitted before the block itself is
+//! recorded, so the checkpoint never passes a block that wasn't fully handled
+//! and an interrupted sync resumes where it stopped. A recorded block the node
+//! no longer has is a reorg: outputs from the orphaned blocks are rolled back
+//! and a `reorg` event is emitted before syncing resumes from the fork.
+//!
+//! On a stop request the block in hand is finished and recorded, and the sync
+//! ends; the next one resumes at the block after it.
+
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use tokio::task::JoinSet;
+use zcash_primitives::consensus::BlockHeight;
+
+use crate::{
+    block::{self, RawBlock},
+    chain,
+    decrypt::{DecryptOptions, Decryptor, TreePositions},
+    metrics::METRICS,
+    models::TxReport,
+    network::ChainParams,
+    progress::Progress,
+    rpc::RpcClient,
+    shutdown::Shutdown,
+    sink::EventSink,
+    store::ResultStore,
+};
+
+pub struct SyncOptions {
+    /// Height to start from when the store has no checkpoint yet
+    pub birthday: Option<u32>,
+    /// Confirmations a block needs before it is scanned
+    pub min_confirmations: Option<u32>,
+    /// Blocks fetched concurrently and decrypted together while catching up
+    pub batch: u32,
+    /// Decryption threads; all cores if None
+    pub threads: Option<usize>,
+    /// Wait between tip polls once caught up
+    pub interval: Duration,
+}
+
+/// How a round of [`sync_once`] ended.
+enum Round {
+    /// Every block up to the tip was handled
+    CaughtUp,
+    /// A fetched block didn't build on the recorded chain; check for a reorg
+    /// again right away
+    ChainMoved,
+    /// A stop was requested; blocks up to the checkpoint were handled
+    Stopped,
+}
+
+/// Sync until `shutdown` is requested, reporting catch-up to `progress`.
+/// `sink` must have a result store attached.
+///
+/// Failures after the first round are logged as warnings and retried on the
+/// next poll, as in `watch`; nothing is checkpointed past the failed block.
+pub async fn run(
+    decryptor: &Decryptor,
+    rpc: &Arc<RpcClient>,
+    sink: &mut EventSink<'_>,
+    options: &SyncOptions,
+    progress: &mut Progress,
+    shutdown: &Shutdown,
+) -> Result<()> {
+    let mut builder = rayon::ThreadPoolBuilder::new();
+    if let Some(n) = options.threads {
+        builder = builder.num_threads(n);
+    }
+    let pool = builder
+        .build()
+        .context("Failed to start decryption thread pool")?;
+    let mut first_round = true;
+
+    while !shutdown.requested() {
+        match sync_once(decryptor, rpc, sink, &pool, options, progress, shutdown).await {
+            Ok(Round::CaughtUp) => {}
+            // The chain moved under the batch; look again without waiting
+            Ok(Round::ChainMoved) => {
+                first_round = false;
+                continue;
+            }
+            Ok(Round::Stopped) => break,
+            Err(e) if first_round => return Err(e),
+            Err(e) => tracing::warn!("sync failed: {:#}", e),
+        }
+        first_round = false;
+
+        shutdown.sleep(options.interval).await;
+    }
+    Ok(())
+}
+
+/// Catch up to the node's current tip, or until `shutdown` is requested.
+async fn sync_once(
+    decryptor: &Decryptor,
+    rpc: &Arc<RpcClient>,
+    sink: &mut EventSink<'_>,
+    pool: &rayon::ThreadPool,
+    options: &SyncOptions,
+    progress: &mut Progress,
+    shutdown: &Shutdown,
+) -> Result<Round> {
+    let tip = rpc.get_block_count().await?;
+    METRICS
+        .chain_tip_height
+        .store(u64::from(tip), Ordering::Relaxed);
+
+    if let Some(fork_height) = find_reorg(store(sink)?, rpc, tip).await? {
+        let rolled_back = store(sink)?.rollback(fork_height)?;
+        tracing::warn!(
+            "Reorg at height {}: rolled back {} stored output(s)",
+            fork_height,
+            rolled_back.len()
+        );
+        sink.emit_reorg(fork_height, rolled_back)?;
+    }
+
+    let mut next = match store(sink)?.scanned_tip()? {
+        Some(checkpoint) => checkpoint + 1,
+        None => options.birthday.ok_or_else(|| {
+            anyhow!("The result store has no checkpoint yet; pass --birthday to start from")
+        })?,
+    };
+
+    // Blocks too shallow for --min-confirmations wait for a later round
+    let target = match options.min_confirmations {
+        Some(min) => (tip + 1).saturating_sub(min.max(1)),
+        None => tip,
+    };
+    // Progress counts blocks from where this round started
+    let (first, round_started) = (next, Instant::now());
+    let blocks_to_scan = u64::from((target + 1).saturating_sub(first));
+    while next <= target {
+        let end = target.min(next.saturating_add(options.batch.max(1) - 1));
+        let started = Instant::now();
+        tracing::debug!(from = next, to = end, tip, "fetching blocks");
+        let blocks = fetch(rpc, decryptor.network(), next, end).await?;
+        let start = tree_start(rpc, next).await?;
+
+        let mut parent = match next.checked_sub(1) {
+            Some(below) => store(sink)?.block_hash(below)?,
+            None => None,
+        };
+        for block in &blocks {
+            if parent
+                .as_ref()
+                .is_some_and(|parent| *parent != block.prev_hash.to_string())
+            {
+                tracing::warn!(
+                    "Block {} does not build on the recorded chain; checking for a reorg",
+                    block.height
+                );
+                return Ok(Round::ChainMoved);
+            }
+            parent = Some(block.hash.to_string());
+        }
+
+        let reports = decrypt_blocks(decryptor, pool, &blocks, start);
+        for (block, reports) in blocks.iter().zip(reports) {
+            if shutdown.requested() {
+                return Ok(Round::Stopped);
+            }
+            let block_time = block.time.to_rfc3339();
+            let mut spends = Vec::new();
+            for mut report in reports {
+                let txid = &report.txid;
+                spends.extend(report.spent_nullifiers.iter().map(|nf| (*nf, txid.clone())));
+                if report.outputs.is_empty() && report.filtered.is_none() {
+                    continue;
+                }
+                report.block_time = Some(block.time);
+                report.block_hash = Some(block.hash.to_string());
+                METRICS
+                    .outputs_found
+                    .fetch_add(report.outputs.len() as u64, Ordering::Relaxed);
+                if !report.outputs.is_empty() {
+                    progress.hit();
+                }
+                sink.emit("block_output", &report, block.height, true)?;
+                store(sink)?.set_block(&report.txid, block.height, &block_time)?;
+            }
+            // After the block's outputs, which its later transactions may spend
+            store(sink)?.mark_spent(&spends, block.height)?;
+            store(sink)?.record_blocks(&[(block.height, block.hash.to_string())])?;
+            METRICS.blocks_scanned.fetch_add(1, Ordering::Relaxed);
+            METRICS
+                .scanned_height
+                .fetch_max(u64::from(block.height), Ordering::Relaxed);
+            progress.report(
+                round_started,
+                (u64::from(block.height + 1 - first), blocks_to_scan),
+                (block.height, target),
+            );
+        }
+
+        tracing::info!(
+            "Synced blocks {}..={} of {} in {:.2}s",
+            next,
+            end,
+            tip,
+            started.elapsed().as_secs_f64()
+        );
+        next = end + 1;
+    }
+    Ok(Round::CaughtUp)
+}
+
+/// Trial-decrypt every transaction of `blocks` on `pool`, grouped by block.
+///
+/// `start` is where the first block's outputs start in the commitment trees.
+/// Transactions with outputs for our keys are decrypted again at their
+/// position, which gives their Sapling notes nullifiers; after a transaction
+/// that can't be read, positions are unknown for the rest of the batch.
+///
+/// As in `decrypt-block`, a transaction that fails to parse or decrypt is
+/// skipped with a warning rather than failing the round, which would retry
+/// the same block forever.
+fn decrypt_blocks(
+    decryptor: &Decryptor,
+    pool: &rayon::ThreadPool,
+    blocks: &[RawBlock],
+    start: Option<TreePositions>,
+) -> Vec<Vec<TxReport>> {
+    let transactions: Vec<(u32, usize, &[u8])> = blocks
+        .iter()
+        .flat_map(|block| {
+            let height = block.height;
+            block.transactions.iter().enumerate().map(move |(i, tx)| (height, i, &tx[..]))
+        })
+        .collect();
+    let mut results = pool
+        .install(|| {
+            transactions
+                .par_iter()
+                .map(|(height, _, tx)| decryptor.decrypt(BlockHeight::from_u32(*height), None, tx))
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+        .zip(&transactions);
+    METRICS
+        .transactions_decrypted
+        .fetch_add(transactions.len() as u64, Ordering::Relaxed);
+
+    let mut positions = start;
+    blocks
+        .iter()
+        .map(|block| {
+            let mut reports = Vec::new();
+            for (result, &(height, i, tx)) in results.by_ref().take(block.transactions.len()) {
+                let report = match result {
+                    Ok(report) if report.outputs.is_empty() => report,
+                    Ok(report) => match positions {
+                        Some(positions) => {
+                            let options = DecryptOptions {
+                                positions: Some(positions),
+                                ..DecryptOptions::default()
+                            };
+                            let height = BlockHeight::from_u32(height);
+                            decryptor.decrypt_with(height, None, tx, options).unwrap_or(report)
+                        }
+                        None => report,
+                    },
+                    Err(e) => {
+                        METRICS.decrypt_errors.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            "skipping transaction {} of block {}: {:#}",
+                            i,
+                            height,
+                            e
+                        );
+                        positions = None;
+                        continue;
+                    }
+                };
+                positions = positions.map(|p| TreePositions {
+                    sapling: p.sapling + report.bundles.sapling_outputs as u64,
+                    orchard: p.orchard + report.bundles.orchard_actions as u64,
+                });
+                reports.push(report);
+            }
+            reports
+        })
+        .collect()
+}
+
+/// Commitment tree sizes before block `height`: those the node reports for
+/// the block below it, or None if it doesn't report them.
+async fn tree_start(rpc: &RpcClient, height: u32) -> Result<Option<TreePositions>> {
+    let Some(below) = height.checked_sub(1) else {
+        return Ok(Some(TreePositions {
+            sapling: 0,
+            orchard: 0,
+        }));
+    };
+    let sizes = rpc.get_tree_sizes(below).await?;
+    if sizes.is_none() {
+        tracing::warn!(
+            "The node doesn't report commitment tree sizes; Sapling notes from block {} are \
+             stored without nullifiers, so their spends won't be seen",
+            height
+        );
+    }
+    Ok(sizes)
+}
+
+/// Lowest recorded height the node no longer has, or None if the recorded
+/// chain is still the node's (up to the node's `tip`, if that is lower).
+async fn find_reorg(store: &ResultStore, rpc: &RpcClient, tip: u32) -> Result<Option<u32>> {
+    let Some(checkpoint) = store.scanned_tip()? else {
+        return Ok(None);
+    };
+    let height = checkpoint.min(tip);
+    match store.block_hash(height)? {
+        Some(recorded) if recorded != rpc.get_block_hash(height).await? => {
+            chain::walk_back(store, rpc, height, height).await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Fetch and parse blocks `from..=to`, all requested at once.
+async fn fetch(
+    rpc: &Arc<RpcClient>,
+    params: ChainParams,
+    from: u32,
+    to: u32,
+) -> Result<Vec<RawBlock>> {
+    let mut tasks = JoinSet::new();
+    for height in from..=to {
+        let rpc = Arc::clone(rpc);
+        tasks.spawn(async move {
+            let raw_hex = rpc.get_block_hex(height).await?;
+            let data = hex::decode(raw_hex.trim())
+                .with_context(|| format!("Node returned invalid hex for block {}", height))?;
+            block::parse_raw_block(&data, &params, Some(BlockHeight::from_u32(height)), None)
+                .with_context(|| format!("Failed to parse block {}", height))
+        });
+    }
+
+    let mut blocks = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        blocks.push(joined.context("Block fetch task failed")??);
+    }
+    blocks.sort_by_key(|block| block.height);
+    if let Some((block, height)) = blocks
+        .iter()
+        .zip(from..)
+        .find(|(block, height)| block.height != *height)
+    {
+        return Err(anyhow!(
+            "Node returned a block at height {} for height {}",
+            block.height,
+            height
+        ));
+    }
+    Ok(blocks)
+}
+
+fn store<'s>(sink: &'s mut EventSink<'_>) -> Result<&'s mut ResultStore> {
+    sink.store()
+        .ok_or_else(|| anyhow!("sync needs a result store"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Utc;
+    use zcash_client_backend::keys::UnifiedFullViewingKey;
+    use zcash_primitives::block::BlockHash;
+    use zcash_protocol::consensus::Network;
+
+    use super::*;
+    use crate::golden::{self, Fixture};
+
+    /// A fixture's transaction and a decryptor with its key.
+    fn fixture(name: &str) -> (Decryptor, u32, Vec<u8>) {
+        let (_, json) = golden::EMBEDDED.iter().find(|(embedded, _)| *embedded == name).unwrap();
+        let fixture = Fixture::parse(json).unwrap();
+        let params = ChainParams::Standard(Network::TestNetwork);
+        let key = UnifiedFullViewingKey::decode(&params, &fixture.ufvk).unwrap();
+        let decryptor = Decryptor::new(params, vec![key], None, false);
+        (decryptor, fixture.height, hex::decode(&fixture.raw_tx).unwrap())
+    }
+
+    fn block(height: u32, transactions: Vec<Vec<u8>>) -> RawBlock {
+        RawBlock {
+            hash: BlockHash([0; 32]),
+            prev_hash: BlockHash([0; 32]),
+            height,
+            time: Utc::now(),
+            transactions,
+        }
+    }
+
+    fn pool() -> rayon::ThreadPool {
+        rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap()
+    }
+
+    #[test]
+    fn malformed_transactions_are_skipped() {
+        let (decryptor, height, tx) = fixture("synthetic-v5-orchard");
+        let blocks = [
+            block(height, vec![vec![0x05, 0x00], tx.clone()]),
+            block(height + 1, vec![tx[..tx.len() / 2].to_vec()]),
+        ];
+
+        let errors = METRICS.decrypt_errors.load(Ordering::Relaxed);
+        let reports = decrypt_blocks(&decryptor, &pool(), &blocks, None);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].len(), 1);
+        assert_eq!(reports[0][0].outputs.len(), 3);
+        assert!(reports[1].is_empty());
+        assert!(METRICS.decrypt_errors.load(Ordering::Relaxed) >= errors + 2);
+    }
+
+    #[test]
+    fn sapling_spends_reduce_the_balance() {
+        let (decryptor, height, tx) = fixture("synthetic-v5-sapling");
+        // Two earlier transactions in the block, one of them ours
+        let blocks = [block(height, vec![tx.clone(), tx.clone(), tx])];
+        let start = TreePositions {
+            sapling: 1_000,
+            orchard: 0,
+        };
+        let reports = decrypt_blocks(&decryptor, &pool(), &blocks, Some(start));
+        let report = &reports[0][2];
+        let incoming = report
+            .outputs
+            .iter()
+            .find(|out| out.transfer_type == "Incoming")
+            .unwrap();
+        assert_eq!(incoming.position, Some(1_000 + 3 + 3 + incoming.index as u64));
+
+        let mut store = ResultStore::open(Path::new(":memory:")).unwrap();
+        store.insert(report, true).unwrap();
+        assert_eq!(store.balance(0).unwrap().confirmed_zats, 134_990_000);
+
+        let nullifier = hex::decode(incoming.nullifier.as_ref().unwrap()).unwrap();
+        let spend = (nullifier.try_into().unwrap(), "spender".to_string());
+        assert_eq!(store.mark_spent(&[spend], height + 1).unwrap(), 1);
+        assert_eq!(store.balance(0).unwrap().confirmed_zats, 14_990_000);
+    }
+
+    #[test]
+    fn positions_are_unknown_after_an_unreadable_transaction() {
+        let (decryptor, height, tx) = fixture("synthetic-v5-sapling");
+        let blocks = [block(height, vec![vec![0x05], tx])];
+        let start = TreePositions {
+            sapling: 0,
+            orchard: 0,
+        };
+        let reports = decrypt_blocks(&decryptor, &pool(), &blocks, Some(start));
+        assert!(reports[0][0].outputs.iter().all(|out| out.nullifier.is_none()));
+    }
+}
@@ -0,0 +1,103 @@
+//! Address book (`--tags`): known counterparties, matched by address or by
+//! memo text, reported as each output's `counterparty`.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Labels for known addresses and memo patterns.
+#[derive(Debug, Default, Clone)]
+pub struct AddressBook {
+    addresses: HashMap<String, String>,
+    /// (lowercased text, label), in file order
+    memos: Vec<(String, String)>,
+}
+
+impl AddressBook {
+    /// Read a CSV file of `match,label` rows; see [`AddressBook::parse`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tags file {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid tags file {}", path.display()))
+    }
+
+    /// Parse `match,label` rows. `match` is an address, or `memo:<text>` for
+    /// any memo containing `<text>`, ignoring case. Fields may be quoted as in
+    /// RFC 4180. Blank lines, `#` comments and a `match,label` header are
+    /// skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut book = AddressBook::default();
+        for (number, line) in (1..).zip(text.lines()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields =
+                split_row(line).ok_or_else(|| anyhow!("Line {}: unbalanced quotes", number))?;
+            let [pattern, label] = fields.as_slice() else {
+                return Err(anyhow!(
+                    "Line {}: expected 2 fields (match,label), found {}",
+                    number,
+                    fields.len()
+                ));
+            };
+            let (pattern, label) = (pattern.trim(), label.trim().to_string());
+            if pattern.eq_ignore_ascii_case("match") && label.eq_ignore_ascii_case("label") {
+                continue;
+            }
+            if pattern.is_empty() || label.is_empty() {
+                return Err(anyhow!("Line {}: empty match or label", number));
+            }
+
+            match pattern.strip_prefix("memo:") {
+                Some(memo) => book.memos.push((memo.to_lowercase(), label)),
+                None => {
+                    book.addresses.insert(pattern.to_string(), label);
+                }
+            }
+        }
+        Ok(book)
+    }
+
+    /// Label for an output paying or paid by `address` with `memo`. An
+    /// address match wins over a memo match; among memo patterns the first
+    /// in the file wins.
+    pub fn counterparty(&self, address: Option<&str>, memo: &str) -> Option<String> {
+        if let Some(label) = address.and_then(|address| self.addresses.get(address)) {
+            return Some(label.clone());
+        }
+        if self.memos.is_empty() {
+            return None;
+        }
+        let memo = memo.trim_end_matches('\0').to_lowercase();
+        self.memos
+            .iter()
+            .find(|(text, _)| memo.contains(text.as_str()))
+            .map(|(_, label)| label.clone())
+    }
+}
+
+/// Split one CSV row into fields, unquoting quoted ones; None if a quote is
+/// left open.
+fn split_row(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
@@ -0,0 +1,95 @@
+//! Transparent addresses to watch alongside the shielded keys: given one by
+//! one (`--taddr`) or derived from a BIP 44 account xpub (`--xpub`).
+
+use anyhow::{anyhow, Result};
+use zcash_client_backend::address::Address;
+use zcash_primitives::{
+    consensus::Parameters,
+    legacy::{
+        keys::{AccountPubKey, IncomingViewingKey, NonHardenedChildIndex},
+        TransparentAddress,
+    },
+};
+use zcash_protocol::consensus::NetworkType;
+
+use crate::{decrypt::WatchedAddress, network::ChainParams};
+
+/// BIP 32 version bytes of an extended public key: `xpub` on mainnet, `tpub`
+/// on testnet and regtest.
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+
+/// Depth of an account key, `m/44'/<coin_type>'/<account>'`.
+const ACCOUNT_DEPTH: u8 = 3;
+
+/// Decode `--taddr` values. TEX addresses (ZIP 320) are watched as the P2PKH
+/// address they wrap.
+pub fn parse_taddrs(network: &ChainParams, taddrs: &[String]) -> Result<Vec<WatchedAddress>> {
+    taddrs
+        .iter()
+        .map(|taddr| {
+            let address = match crate::address::decode(network, taddr)? {
+                Address::Transparent(address) => address,
+                Address::Tex(hash) => TransparentAddress::PublicKeyHash(hash),
+                _ => return Err(anyhow!("Not a transparent address: {}", taddr.trim())),
+            };
+            Ok(WatchedAddress {
+                address,
+                child_index: None,
+            })
+        })
+        .collect()
+}
+
+/// The first `count` addresses on the external (receiving) chain of an
+/// account-level `xpub`. Change addresses on the internal chain are not
+/// derived.
+pub fn derive_xpub(network: &ChainParams, xpub: &str, count: u32) -> Result<Vec<WatchedAddress>> {
+    let data = bs58::decode(xpub.trim())
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| anyhow!("Invalid xpub: {}", e))?;
+    if data.len() != 78 {
+        return Err(anyhow!(
+            "Invalid xpub: expected 78 bytes, got {}",
+            data.len()
+        ));
+    }
+
+    let expected = match network.network_type() {
+        NetworkType::Main => XPUB_VERSION,
+        NetworkType::Test | NetworkType::Regtest => TPUB_VERSION,
+    };
+    if data[..4] != expected {
+        return Err(anyhow!(
+            "Extended key is not a public key for {}",
+            crate::network::network_name(network.network_type())
+        ));
+    }
+    if data[4] != ACCOUNT_DEPTH {
+        return Err(anyhow!(
+            "Expected an account-level xpub (m/44'/coin'/account', depth {}), got depth {}",
+            ACCOUNT_DEPTH,
+            data[4]
+        ));
+    }
+
+    // Chain code and compressed public key, the encoding AccountPubKey uses
+    let key: [u8; 65] = data[13..].try_into().expect("78 - 13 bytes");
+    let external = AccountPubKey::deserialize(&key)
+        .and_then(|account| account.derive_external_ivk())
+        .map_err(|e| anyhow!("Invalid xpub: {}", e))?;
+
+    (0..count)
+        .filter_map(|index| NonHardenedChildIndex::from_index(index).map(|child| (index, child)))
+        .map(|(index, child)| {
+            let address = external
+                .derive_address(child)
+                .map_err(|e| anyhow!("Failed to derive address {}: {}", index, e))?;
+            Ok(WatchedAddress {
+                address,
+                child_index: Some(index),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,399 @@
+//! `tui`: browse a `--db` result database in the terminal. Transactions are
+//! listed newest first; Enter opens one with its outputs and the selected
+//! output's memo, `a` steps through the accounts in the database.
+//!
+//! Only built with the `tui` feature. Stored outputs are only read.
+
+use anyhow::Result;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table, TableState, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    models::{StoredOutput, Zec},
+    store::{OutputFilter, ResultStore},
+};
+
+/// Rows moved by PageUp and PageDown.
+const PAGE: usize = 20;
+
+/// Browse `store`, limited to `filter`, until the user quits.
+pub fn run(store: ResultStore, filter: OutputFilter) -> Result<()> {
+    let mut app = App::new(store, filter)?;
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// A stored transaction: its outputs, in store order.
+struct Transaction {
+    txid: String,
+    height: u32,
+    confirmed: bool,
+    block_time: Option<String>,
+    outputs: Vec<StoredOutput>,
+}
+
+impl Transaction {
+    /// Value received, change included; sent outputs don't count.
+    fn received_zats(&self) -> i64 {
+        self.outputs
+            .iter()
+            .filter(|out| out.transfer_type != "Outgoing")
+            .map(|out| out.amount_zats)
+            .sum()
+    }
+}
+
+enum View {
+    List,
+    Transaction,
+}
+
+struct App {
+    store: ResultStore,
+    /// Height range from the command line; the account is set with `a`
+    filter: OutputFilter,
+    /// Every account in the range, with its label
+    accounts: Vec<(u32, Option<String>)>,
+    transactions: Vec<Transaction>,
+    list: TableState,
+    outputs: TableState,
+    view: View,
+}
+
+impl App {
+    fn new(store: ResultStore, filter: OutputFilter) -> Result<Self> {
+        let mut accounts: Vec<(u32, Option<String>)> = Vec::new();
+        let all = OutputFilter {
+            account: None,
+            ..filter
+        };
+        for out in store.query(&all)? {
+            if !accounts.iter().any(|(account, _)| *account == out.account) {
+                accounts.push((out.account, out.account_label));
+            }
+        }
+        accounts.sort_by_key(|(account, _)| *account);
+
+        let mut app = App {
+            store,
+            filter,
+            accounts,
+            transactions: Vec::new(),
+            list: TableState::default(),
+            outputs: TableState::default(),
+            view: View::List,
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    /// Re-read the transactions matching the filter, newest first.
+    fn reload(&mut self) -> Result<()> {
+        let mut transactions: Vec<Transaction> = Vec::new();
+        for out in self.store.query(&self.filter)? {
+            match transactions.last_mut() {
+                Some(tx) if tx.txid == out.transaction_id => tx.outputs.push(out),
+                _ => transactions.push(Transaction {
+                    txid: out.transaction_id.clone(),
+                    height: out.block_height,
+                    confirmed: out.confirmed,
+                    block_time: out.block_time.clone(),
+                    outputs: vec![out],
+                }),
+            }
+        }
+        transactions.reverse();
+        self.transactions = transactions;
+        self.list
+            .select((!self.transactions.is_empty()).then_some(0));
+        self.view = View::List;
+        Ok(())
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle(key)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Act on `key`; false once the user quits.
+    fn handle(&mut self, key: KeyEvent) -> Result<bool> {
+        match (&self.view, key.code) {
+            (_, KeyCode::Char('q')) => return Ok(false),
+            (View::List, KeyCode::Esc) => return Ok(false),
+            (View::List, KeyCode::Enter) => {
+                if self.selected().is_some() {
+                    self.outputs.select(Some(0));
+                    self.view = View::Transaction;
+                }
+            }
+            (View::List, KeyCode::Char('a')) => {
+                self.next_account();
+                self.reload()?;
+            }
+            (View::List, KeyCode::Char('r')) => self.reload()?,
+            (View::Transaction, KeyCode::Esc | KeyCode::Backspace) => self.view = View::List,
+            (_, code) => {
+                let (state, len) = match self.view {
+                    View::List => (&mut self.list, self.transactions.len()),
+                    View::Transaction => {
+                        let len = self.selected().map_or(0, |tx| tx.outputs.len());
+                        (&mut self.outputs, len)
+                    }
+                };
+                move_selection(state, len, code);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Show the next account in turn, then all of them again.
+    fn next_account(&mut self) {
+        let current = self
+            .accounts
+            .iter()
+            .position(|(account, _)| Some(*account) == self.filter.account);
+        self.filter.account = match current {
+            None => self.accounts.first().map(|(account, _)| *account),
+            Some(i) => self.accounts.get(i + 1).map(|(account, _)| *account),
+        };
+    }
+
+    fn selected(&self) -> Option<&Transaction> {
+        self.transactions.get(self.list.selected()?)
+    }
+
+    fn account_name(&self, account: u32) -> String {
+        match self.accounts.iter().find(|(a, _)| *a == account) {
+            Some((_, Some(label))) => format!("{} ({})", account, label),
+            _ => account.to_string(),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let keys = match self.view {
+            View::List => "↑/↓ move  Enter open  a account  r reload  q quit",
+            View::Transaction => "↑/↓ output  Esc back  q quit",
+        };
+        frame.render_widget(Line::from(keys), help);
+        match self.view {
+            View::List => self.draw_list(frame, main),
+            View::Transaction => self.draw_transaction(frame, main),
+        }
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let account = match self.filter.account {
+            Some(account) => self.account_name(account),
+            None => "all".to_string(),
+        };
+        let title = format!(
+            " Transactions: {} | account: {} ",
+            self.transactions.len(),
+            account
+        );
+
+        let rows = self.transactions.iter().map(|tx| {
+            let accounts = distinct(tx.outputs.iter().map(|out| out.account.to_string()));
+            let memo = tx
+                .outputs
+                .iter()
+                .map(|out| memo_text(&out.memo))
+                .find(|memo| !memo.is_empty())
+                .unwrap_or_default();
+            Row::new([
+                height_text(tx.height, tx.confirmed),
+                tx.txid.clone(),
+                tx.outputs.len().to_string(),
+                zec(tx.received_zats()),
+                accounts,
+                first_line(&memo),
+            ])
+        });
+        let widths = [
+            Constraint::Length(9),
+            Constraint::Length(64),
+            Constraint::Length(7),
+            Constraint::Length(16),
+            Constraint::Length(8),
+            Constraint::Min(10),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header(&[
+                "HEIGHT",
+                "TXID",
+                "OUTPUTS",
+                "RECEIVED ZEC",
+                "ACCOUNT",
+                "MEMO",
+            ]))
+            .block(Block::bordered().title(title))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut self.list);
+    }
+
+    fn draw_transaction(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(tx) = self.list.selected().and_then(|i| self.transactions.get(i)) else {
+            return;
+        };
+        let [summary, outputs, detail] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(5),
+            Constraint::Length(9),
+        ])
+        .areas(area);
+
+        let status = if tx.confirmed {
+            "mined"
+        } else {
+            "mempool, expected"
+        };
+        let summary_text = vec![
+            Line::from(format!("TXID:   {}", tx.txid)),
+            Line::from(format!(
+                "Height: {} ({}){}",
+                tx.height,
+                status,
+                tx.block_time
+                    .as_deref()
+                    .map(|time| format!(", {}", time))
+                    .unwrap_or_default()
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(summary_text).block(Block::bordered().title(" Transaction ")),
+            summary,
+        );
+
+        let rows = tx.outputs.iter().map(|out| {
+            Row::new([
+                out.protocol.clone(),
+                out.index.to_string(),
+                self.account_name(out.account),
+                out.transfer_type.clone(),
+                zec(out.amount_zats),
+                out.payment_reference.clone().unwrap_or_default(),
+                first_line(&memo_text(&out.memo)),
+            ])
+        });
+        let widths = [
+            Constraint::Length(11),
+            Constraint::Length(5),
+            Constraint::Length(16),
+            Constraint::Length(14),
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Min(10),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header(&[
+                "PROTOCOL",
+                "INDEX",
+                "ACCOUNT",
+                "TYPE",
+                "ZEC",
+                "REFERENCE",
+                "MEMO",
+            ]))
+            .block(Block::bordered().title(" Outputs "))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, outputs, &mut self.outputs);
+
+        let Some(out) = self.outputs.selected().and_then(|i| tx.outputs.get(i)) else {
+            return;
+        };
+        let mut lines = vec![Line::from(format!(
+            "Address:      {}",
+            out.address.as_deref().unwrap_or("-")
+        ))];
+        if let Some(index) = out.diversifier_index {
+            lines.push(Line::from(format!("Address index: {}", index)));
+        }
+        if let Some(counterparty) = &out.counterparty {
+            lines.push(Line::from(format!("Counterparty: {}", counterparty)));
+        }
+        lines.push(Line::from(format!("Recorded:     {}", out.recorded_at)));
+        lines.push(Line::from(""));
+        lines.extend(
+            memo_text(&out.memo)
+                .lines()
+                .map(|line| Line::from(line.to_string())),
+        );
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Output ")),
+            detail,
+        );
+    }
+}
+
+/// Move the selection of a table of `len` rows as `code` says.
+fn move_selection(state: &mut TableState, len: usize, code: KeyCode) {
+    let Some(current) = state.selected() else {
+        return;
+    };
+    let last = len.saturating_sub(1);
+    let next = match code {
+        KeyCode::Up | KeyCode::Char('k') => current.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => (current + 1).min(last),
+        KeyCode::PageUp => current.saturating_sub(PAGE),
+        KeyCode::PageDown => (current + PAGE).min(last),
+        KeyCode::Home | KeyCode::Char('g') => 0,
+        KeyCode::End | KeyCode::Char('G') => last,
+        _ => return,
+    };
+    state.select(Some(next));
+}
+
+fn header(titles: &[&'static str]) -> Row<'static> {
+    Row::new(titles.iter().copied()).style(Style::new().add_modifier(Modifier::BOLD))
+}
+
+/// Height, marked `?` while only expected, as in `query`.
+fn height_text(height: u32, confirmed: bool) -> String {
+    if confirmed {
+        height.to_string()
+    } else {
+        format!("{}?", height)
+    }
+}
+
+fn zec(zats: i64) -> String {
+    Zec::from_zats(zats).to_string()
+}
+
+/// Memo text without the zero padding of short memos.
+fn memo_text(memo: &str) -> String {
+    memo.trim_end_matches('\0').to_string()
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+/// `values` without repeats, comma-separated, in first-seen order.
+fn distinct(values: impl Iterator<Item = String>) -> String {
+    let mut seen: Vec<String> = Vec::new();
+    for value in values {
+        if !seen.contains(&value) {
+            seen.push(value);
+        }
+    }
+    seen.join(",")
+}
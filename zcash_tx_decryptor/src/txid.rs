@@ -0,0 +1,70 @@
+use blake2b_simd::{Hash as Blake2bHash, Params};
+use zcash_primitives::transaction::{txid::TxIdDigester, Transaction, TxId, TxVersion};
+
+const ZCASH_TX_PERSONALIZATION_PREFIX: &[u8; 12] = b"ZcashTxHash_";
+const ZCASH_HEADERS_HASH_PERSONALIZATION: &[u8; 16] = b"ZTxIdHeadersHash";
+const ZCASH_TRANSPARENT_HASH_PERSONALIZATION: &[u8; 16] = b"ZTxIdTranspaHash";
+const ZCASH_SAPLING_HASH_PERSONALIZATION: &[u8; 16] = b"ZTxIdSaplingHash";
+const ZCASH_ORCHARD_HASH_PERSONALIZATION: &[u8; 16] = b"ZTxIdOrchardHash";
+
+fn hasher(personal: &[u8; 16]) -> blake2b_simd::State {
+    Params::new().hash_length(32).personal(personal).to_state()
+}
+
+/// Compute the TXID of a parsed transaction, committing to the given consensus
+/// branch ID instead of the one the transaction was parsed with.
+///
+/// librustzcash only knows branch IDs up to NU6, so NU6.1 transactions are
+/// parsed with a substituted branch ID and `Transaction::txid()` no longer
+/// matches the chain. For v5 transactions the ZIP-244 header and top-level
+/// digests are recomputed here with the on-chain branch ID; the per-bundle
+/// digests do not depend on it and are reused as-is. Pre-v5 transactions are
+/// hashed over their raw bytes, so their TXID is returned unchanged.
+pub fn compute_txid(tx: &Transaction, consensus_branch_id: u32) -> TxId {
+    if tx.version() != TxVersion::Zip225 || u32::from(tx.consensus_branch_id()) == consensus_branch_id {
+        return tx.txid();
+    }
+
+    let digests = tx.digest(TxIdDigester);
+
+    // ZIP 244, T.1: header digest
+    let mut h = hasher(ZCASH_HEADERS_HASH_PERSONALIZATION);
+    h.update(&tx.version().header().to_le_bytes());
+    h.update(&tx.version().version_group_id().to_le_bytes());
+    h.update(&consensus_branch_id.to_le_bytes());
+    h.update(&tx.lock_time().to_le_bytes());
+    h.update(&u32::from(tx.expiry_height()).to_le_bytes());
+    let header_digest = h.finalize();
+
+    // ZIP 244, T.2: transparent digest
+    let mut h = hasher(ZCASH_TRANSPARENT_HASH_PERSONALIZATION);
+    if let Some(d) = &digests.transparent_digests {
+        h.update(d.prevouts_digest.as_bytes());
+        h.update(d.sequence_digest.as_bytes());
+        h.update(d.outputs_digest.as_bytes());
+    }
+    let transparent_digest = h.finalize();
+
+    // ZIP 244, T.3 / T.4: shielded digests (empty-bundle hashes when absent)
+    let empty = |personal: &[u8; 16]| -> Blake2bHash { hasher(personal).finalize() };
+    let sapling_digest = digests
+        .sapling_digest
+        .unwrap_or_else(|| empty(ZCASH_SAPLING_HASH_PERSONALIZATION));
+    let orchard_digest = digests
+        .orchard_digest
+        .unwrap_or_else(|| empty(ZCASH_ORCHARD_HASH_PERSONALIZATION));
+
+    let mut personal = [0u8; 16];
+    personal[..12].copy_from_slice(ZCASH_TX_PERSONALIZATION_PREFIX);
+    personal[12..].copy_from_slice(&consensus_branch_id.to_le_bytes());
+
+    let mut h = hasher(&personal);
+    h.update(header_digest.as_bytes());
+    h.update(transparent_digest.as_bytes());
+    h.update(sapling_digest.as_bytes());
+    h.update(orchard_digest.as_bytes());
+
+    let mut txid = [0u8; 32];
+    txid.copy_from_slice(h.finalize().as_bytes());
+    TxId::from_bytes(txid)
+}
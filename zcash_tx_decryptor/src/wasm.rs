@@ -0,0 +1,11 @@
+//! Browser bindings (`--features wasm`), built with e.g.
+//! `wasm-pack build --target web --no-default-features --features wasm`.
+
+use wasm_bindgen::prelude::*;
+
+/// Decrypt a raw transaction with one UFVK; see
+/// [`decrypt_tx_json`](crate::embed::decrypt_tx_json).
+#[wasm_bindgen(js_name = decryptTx)]
+pub fn decrypt_tx(raw_tx_hex: &str, ufvk: &str, height: u32, network: &str) -> String {
+    crate::embed::decrypt_tx_json(raw_tx_hex, ufvk, height, network)
+}
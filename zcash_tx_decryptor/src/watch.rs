@@ -0,0 +1,97 @@
+use std::{collections::HashSet, sync::atomic::Ordering, time::Duration};
+
+use anyhow::Result;
+use zcash_primitives::consensus::BlockHeight;
+
+use crate::{
+    decrypt::Decryptor, metrics::METRICS, rpc::RpcClient, shutdown::Shutdown, sink::EventSink,
+};
+
+/// Poll the node's mempool until `shutdown` is requested, trial-decrypting
+/// every transaction not seen before and sending one `mempool_output` event
+/// per decrypted output to `sink`.
+///
+/// Transient RPC failures are reported on stderr and retried on the next poll;
+/// only a failure on the very first poll aborts, since that usually means the
+/// node is unreachable or misconfigured.
+pub async fn run(
+    decryptor: &Decryptor,
+    rpc: &RpcClient,
+    sink: &mut EventSink<'_>,
+    interval: Duration,
+    shutdown: &Shutdown,
+) -> Result<()> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut first_poll = true;
+
+    while !shutdown.requested() {
+        match poll_once(decryptor, rpc, sink, &mut seen, shutdown).await {
+            Ok(()) => {}
+            Err(e) if first_poll => return Err(e),
+            Err(e) => tracing::warn!("mempool poll failed: {:#}", e),
+        }
+        first_poll = false;
+
+        shutdown.sleep(interval).await;
+    }
+    Ok(())
+}
+
+async fn poll_once(
+    decryptor: &Decryptor,
+    rpc: &RpcClient,
+    sink: &mut EventSink<'_>,
+    seen: &mut HashSet<String>,
+    shutdown: &Shutdown,
+) -> Result<()> {
+    // Pending transactions are decrypted as if mined in the next block.
+    let tip = rpc.get_block_count().await?;
+    METRICS.chain_tip_height.store(u64::from(tip), Ordering::Relaxed);
+    let expected_height = tip + 1;
+    let mempool: HashSet<String> = rpc.get_raw_mempool().await?.into_iter().collect();
+
+    // Forget transactions that were mined or evicted so `seen` stays bounded.
+    seen.retain(|txid| mempool.contains(txid));
+
+    for txid in mempool {
+        if shutdown.requested() {
+            break;
+        }
+        if seen.contains(&txid) {
+            continue;
+        }
+
+        // The transaction may have left the mempool since `getrawmempool`; it
+        // will not come back, so mark it seen either way.
+        let raw_hex = match rpc.get_raw_transaction_hex(&txid).await {
+            Ok(hex) => hex,
+            Err(e) => {
+                tracing::warn!("could not fetch {}: {:#}", txid, e);
+                seen.insert(txid);
+                continue;
+            }
+        };
+
+        let report = hex::decode(raw_hex.trim())
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| {
+                decryptor.decrypt(BlockHeight::from_u32(expected_height), Some(&txid), &bytes)
+            });
+        seen.insert(txid.clone());
+
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => {
+                METRICS.decrypt_errors.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("could not decrypt {}: {:#}", txid, e);
+                continue;
+            }
+        };
+        METRICS.transactions_decrypted.fetch_add(1, Ordering::Relaxed);
+        METRICS.outputs_found.fetch_add(report.outputs.len() as u64, Ordering::Relaxed);
+
+        sink.emit("mempool_output", &report, expected_height, false)?;
+    }
+
+    Ok(())
+}
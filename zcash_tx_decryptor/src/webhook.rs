@@ -0,0 +1,97 @@
+//! `--webhook`: POST every event a long-running mode emits to an HTTP
+//! endpoint, so payment backends are pushed deposits instead of polling the
+//! result store. Queueing, ordering and retries are the [`Forwarder`]'s; see
+//! [`crate::pipeline`]. Output events carry their idempotency key in an
+//! `X-Ztd-Idempotency-Key` header as well, for receivers to upsert on.
+//!
+//! [`Forwarder`]: crate::pipeline::Forwarder
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use sha2::Sha256;
+
+use crate::pipeline::{self, DeliveryError, ResultSink};
+
+/// Delivers events to one URL.
+pub struct Webhook {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    secret: Option<Vec<u8>>,
+}
+
+impl Webhook {
+    /// Deliver to `url`, signing each body with `secret` if set.
+    pub fn new(url: &str, secret: Option<&str>) -> Result<Self> {
+        let url =
+            reqwest::Url::parse(url).with_context(|| format!("Invalid webhook URL {}", url))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        let secret = secret.map(|secret| secret.as_bytes().to_vec());
+        Ok(Webhook {
+            client,
+            url,
+            secret,
+        })
+    }
+}
+
+impl ResultSink for Webhook {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(&mut self, event: &str) -> Result<(), DeliveryError> {
+        post(&self.client, &self.url, self.secret.as_deref(), event).await
+    }
+}
+
+/// One delivery attempt. Client errors other than 408 and 429 mean the
+/// receiver won't take the event, so they aren't retried.
+async fn post(
+    client: &reqwest::Client,
+    url: &reqwest::Url,
+    secret: Option<&[u8]>,
+    body: &str,
+) -> Result<(), DeliveryError> {
+    let mut request = client
+        .post(url.clone())
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(key) = pipeline::idempotency_key(body) {
+        request = request.header("X-Ztd-Idempotency-Key", key);
+    }
+    if let Some(secret) = secret {
+        // Signed fresh on each attempt, so receivers can reject stale replays
+        let timestamp = Utc::now().timestamp();
+        request = request.header("X-Ztd-Timestamp", timestamp).header(
+            "X-Ztd-Signature",
+            format!("sha256={}", sign(secret, timestamp, body)),
+        );
+    }
+
+    let response = request.send().await.map_err(DeliveryError::transient)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let retry = !status.is_client_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS;
+    Err(DeliveryError {
+        error: anyhow!("receiver answered HTTP {}", status),
+        retry,
+    })
+}
+
+/// HMAC-SHA256 of `<timestamp>.<body>` under `secret`, as hex.
+fn sign(secret: &[u8], timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
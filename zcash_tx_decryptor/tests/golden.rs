@@ -0,0 +1,54 @@
+//! Decrypt every fixture in `fixtures/` and compare with its expected report.
+
+use std::path::Path;
+
+use zcash_tx_decryptor::golden::{self, Fixture};
+
+#[test]
+fn fixtures_match_expected_reports() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .expect("fixtures/ is readable")
+        .map(|entry| entry.expect("fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no fixtures in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for path in &files {
+        let json = std::fs::read_to_string(path).expect("fixture is readable");
+        let fixture = Fixture::parse(&json).expect("fixture parses");
+        if let Err(e) = golden::check(&fixture) {
+            failures.push(format!("{}:\n{:#}", path.display(), e));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn every_fixture_is_embedded_for_self_test() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    for entry in std::fs::read_dir(&dir).expect("fixtures/ is readable") {
+        let path = entry.expect("fixture entry").path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            let json = std::fs::read_to_string(&path).expect("fixture is readable");
+            assert!(
+                golden::EMBEDDED
+                    .iter()
+                    .any(|(embedded, contents)| *embedded == name && *contents == json),
+                "{} is missing from golden::EMBEDDED or out of date",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn fixture_names_match_file_names() {
+    for (name, json) in golden::EMBEDDED {
+        let fixture = Fixture::parse(json).expect("fixture parses");
+        assert_eq!(fixture.name, *name);
+    }
+}